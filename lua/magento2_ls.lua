@@ -110,7 +110,7 @@ end
 M.setup = function(opts)
   opts = opts or {}
   opts = vim.tbl_deep_extend('keep', opts, {
-    filetypes = { 'xml', 'javascript' },
+    filetypes = { 'xml', 'javascript', 'typescript', 'html', 'php' },
     name = 'magento2-ls',
     cmd = { script_path('../target/release/magento2-ls') .. (get_system() == 'windows' and '.exe' or '') },
     root_dir = vim.fn.getcwd(),
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use serde::Deserialize;
+
+use crate::{m2::M2Path, state::ArcState};
+
+#[derive(Debug, Deserialize)]
+struct ComposerJson {
+    name: Option<String>,
+}
+
+/// Packages of the installation to index, filtered by `indexVendors` setting.
+pub fn indexed_vendor_packages(state: &ArcState, root: &Path) -> Vec<PathBuf> {
+    // settings are cloned to not keep the state locked while reading `composer.json` files
+    let vendors = state.lock().settings().index_vendors.clone();
+    vendor_packages(root, &vendors)
+}
+
+/// `vendor/<vendor>/<package>` directories of the installation that should be indexed,
+/// only packages of listed vendors are kept unless the list is empty.
+pub fn vendor_packages(root: &Path, vendors: &[String]) -> Vec<PathBuf> {
+    let pattern = root.to_path_buf().append(&["vendor", "*", "*"]);
+    glob(pattern.to_path_str())
+        .expect("Failed to read glob pattern")
+        .filter_map(Result::ok)
+        .filter(|package| package.is_dir())
+        .filter(|package| {
+            vendors.is_empty()
+                || package_vendor(package)
+                    .is_some_and(|vendor| vendors.iter().any(|v| v.eq_ignore_ascii_case(&vendor)))
+        })
+        .collect()
+}
+
+/// Vendor of the package, taken from `composer.json` name (`magento/module-catalog`
+/// is `magento`), or the name of the parent directory when there is no name.
+pub fn package_vendor(package: &Path) -> Option<String> {
+    let from_composer = std::fs::read_to_string(package.join("composer.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<ComposerJson>(&content).ok())
+        .and_then(|composer| composer.name)
+        .and_then(|name| Some(name.split_once('/')?.0.to_string()));
+    from_composer.or_else(|| Some(package.parent()?.file_name()?.to_str()?.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fixture_path(parts: &[&str]) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .append(&["tests", "fixtures", "project"])
+            .append(parts)
+    }
+
+    #[test]
+    fn test_package_vendor_from_composer_name() {
+        let package = fixture_path(&["vendor", "some-dir", "theme-frontend-other"]);
+        assert_eq!(package_vendor(&package), Some("other".into()));
+    }
+
+    #[test]
+    fn test_package_vendor_without_composer_json() {
+        let package = PathBuf::from("/a/vendor/acme/module-foo");
+        assert_eq!(package_vendor(&package), Some("acme".into()));
+    }
+
+    #[test]
+    fn test_vendor_packages() {
+        let root = fixture_path(&[]);
+        assert_eq!(
+            vendor_packages(&root, &[]),
+            vec![
+                fixture_path(&["vendor", "magento", "framework"]),
+                fixture_path(&["vendor", "magento", "module-sample"]),
+                fixture_path(&["vendor", "some-dir", "theme-frontend-other"]),
+            ]
+        );
+        assert_eq!(
+            vendor_packages(&root, &["Magento".into()]),
+            vec![
+                fixture_path(&["vendor", "magento", "framework"]),
+                fixture_path(&["vendor", "magento", "module-sample"]),
+            ]
+        );
+        assert!(vendor_packages(&root, &["some-dir".into()]).is_empty());
+    }
+}
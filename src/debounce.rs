@@ -0,0 +1,89 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Collects files to reindex and releases each of them once no new event
+/// for that file came within the interval.
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    interval: Duration,
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Schedule (or postpone already scheduled) reindex of the file.
+    pub fn schedule(&mut self, path: &Path) {
+        self.pending
+            .insert(path.to_path_buf(), Instant::now() + self.interval);
+    }
+
+    /// Schedule the file to be due right away, regardless of the interval.
+    pub fn schedule_now(&mut self, path: &Path) {
+        self.pending.insert(path.to_path_buf(), Instant::now());
+    }
+
+    /// Time left until the first scheduled file is due, `None` if nothing is scheduled.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.pending
+            .values()
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Remove and return files that are due.
+    pub fn take_due(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let due = self
+            .pending
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        for path in &due {
+            self.pending.remove(path);
+        }
+        due
+    }
+
+    /// Remove and return all scheduled files, regardless of their deadline.
+    pub fn take_all(&mut self) -> Vec<PathBuf> {
+        self.pending.drain().map(|(path, _)| path).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_events_for_the_same_file_are_coalesced() {
+        let mut debouncer = Debouncer::new(Duration::ZERO);
+        debouncer.schedule(Path::new("/a/etc/di.xml"));
+        debouncer.schedule(Path::new("/a/etc/di.xml"));
+
+        assert_eq!(debouncer.timeout(), Some(Duration::ZERO));
+        assert_eq!(debouncer.take_due(), vec![PathBuf::from("/a/etc/di.xml")]);
+        assert_eq!(debouncer.timeout(), None);
+        assert!(debouncer.take_due().is_empty());
+    }
+
+    #[test]
+    fn test_file_is_not_due_before_interval() {
+        let mut debouncer = Debouncer::new(Duration::from_secs(60));
+        debouncer.schedule(Path::new("/a/etc/di.xml"));
+
+        assert!(debouncer.timeout().is_some_and(|t| t > Duration::ZERO));
+        assert!(debouncer.take_due().is_empty());
+        assert_eq!(debouncer.take_all(), vec![PathBuf::from("/a/etc/di.xml")]);
+        assert_eq!(debouncer.timeout(), None);
+    }
+}
@@ -0,0 +1,191 @@
+use std::path::Path;
+
+use lsp_types::Position;
+
+use crate::{js, m2::M2Item, state::State};
+
+/// Knockout binding that references other file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Binding {
+    Template,
+    Component,
+}
+
+pub fn get_item_from_position(state: &State, path: &Path, pos: Position) -> Option<M2Item> {
+    let content = state.get_file(&path.to_path_buf())?;
+    get_item_from_pos(state, content, path, pos)
+}
+
+fn get_item_from_pos(state: &State, content: &str, path: &Path, pos: Position) -> Option<M2Item> {
+    let (binding, text) = get_binding_at_pos(content, pos)?;
+    match binding {
+        Binding::Template => {
+            let (mod_name, template) = text.split_once('/')?;
+            let template = if template.ends_with(".html") {
+                template.to_string()
+            } else {
+                format!("{template}.html")
+            };
+            match js::text_to_component(state, &format!("{mod_name}/template/{template}"), path)? {
+                item @ M2Item::ModHtml(..) => Some(item),
+                _ => None,
+            }
+        }
+        Binding::Component => js::text_to_component(state, &text, path),
+    }
+}
+
+/// Find quoted string under the cursor and the binding it is value of, like
+/// `data-bind="template: 'Module_Name/path'"`, `data-template="Module_Name/path"`
+/// or `<!-- ko template: { name: 'Module_Name/path' } -->`.
+fn get_binding_at_pos(content: &str, pos: Position) -> Option<(Binding, String)> {
+    let line = content.lines().nth(pos.line as usize)?;
+    let chars = line.chars().collect::<Vec<_>>();
+    get_binding_in_chars(&chars, pos.character as usize)
+}
+
+fn get_binding_in_chars(chars: &[char], cursor: usize) -> Option<(Binding, String)> {
+    let mut start = None;
+    for (i, c) in chars.iter().enumerate() {
+        match start {
+            None if *c == '\'' || *c == '"' => start = Some((i, *c)),
+            Some((s, quote)) if *c == quote => {
+                if s < cursor && cursor <= i {
+                    let prefix = chars[..s].iter().collect::<String>();
+                    let text = chars[s + 1..i].iter().collect::<String>();
+                    return binding_from_prefix(&prefix).map_or_else(
+                        // attribute value can contain binding with quoted values
+                        || get_binding_in_chars(&chars[s + 1..i], cursor - s - 1),
+                        |binding| Some((binding, text)),
+                    );
+                }
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn binding_from_prefix(prefix: &str) -> Option<Binding> {
+    let prefix = prefix.trim_end();
+    if prefix.ends_with("template:") || prefix.ends_with("data-template=") {
+        return Some(Binding::Template);
+    }
+    if prefix.ends_with("component:") {
+        return Some(Binding::Component);
+    }
+    let prefix = prefix.strip_suffix("name:")?.trim_end().strip_suffix('{')?;
+    if prefix.trim_end().ends_with("template:") {
+        Some(Binding::Template)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn get_test_item(html: &str, path: &str) -> Option<M2Item> {
+        let win_path = format!("c:{}", path.replace('/', "\\"));
+        let mut character = 0;
+        let mut line = 0;
+        for l in html.lines() {
+            if l.contains('|') {
+                character = l.find('|').expect("Test has to have a | character") as u32;
+                break;
+            }
+            line += 1;
+        }
+        let pos = Position { line, character };
+        let uri = PathBuf::from(if cfg!(windows) { &win_path } else { path });
+        let mut state = State::new();
+        state.add_module_path("Some_Module", PathBuf::from("/a/b/c/Some_Module"));
+        get_item_from_pos(&state, &html.replace('|', ""), &uri, pos)
+    }
+
+    #[test]
+    fn test_get_item_from_pos_template_in_data_bind() {
+        let item = get_test_item(
+            r#"<div data-bind="template: 'Some_Module/summ|ary/item'"></div>"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::ModHtml(
+                "Some_Module".into(),
+                "template/summary/item.html".into(),
+                PathBuf::from("/a/b/c/Some_Module")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_data_template_attribute() {
+        let item = get_test_item(
+            r#"<div class="item" data-template="Some_Module/summ|ary/item"></div>"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::ModHtml(
+                "Some_Module".into(),
+                "template/summary/item.html".into(),
+                PathBuf::from("/a/b/c/Some_Module")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_template_name_in_ko_comment() {
+        let item = get_test_item(
+            r#"<!-- ko template: { name: "Some_Module/i|tem", data: $data } --><!-- /ko -->"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::ModHtml(
+                "Some_Module".into(),
+                "template/item.html".into(),
+                PathBuf::from("/a/b/c/Some_Module")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_component_in_data_bind() {
+        let item = get_test_item(
+            r#"<div data-bind="component: 'Some_Module/js/vi|ew'"></div>"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::ModComponent(
+                "Some_Module".into(),
+                "js/view".into(),
+                PathBuf::from("/a/b/c/Some_Module")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_template_of_unknown_module() {
+        let item = get_test_item(
+            r#"<div data-bind="template: 'Unknown_Module/it|em'"></div>"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_other_binding() {
+        let item = get_test_item(
+            r#"<div data-bind="i18n: 'Some_Module/it|em'"></div>"#,
+            "/a/view/frontend/web/template/file.html",
+        );
+        assert_eq!(item, None);
+    }
+}
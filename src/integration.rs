@@ -0,0 +1,1202 @@
+//! End-to-end tests running the server over in-memory connection against fixture project.
+
+use std::{
+    path::{Path, PathBuf},
+    thread::{sleep, spawn, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{DidOpenTextDocument, Exit, Initialized, Notification as _, PublishDiagnostics},
+    request::{
+        ExecuteCommand, GotoDeclaration, GotoDefinition, GotoImplementation, HoverRequest,
+        Initialize, References, Shutdown,
+    },
+    Diagnostic, DiagnosticSeverity, DidOpenTextDocumentParams, ExecuteCommandParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, InitializeParams,
+    InitializedParams, Location, Position, ProgressToken, PublishDiagnosticsParams, Range,
+    ReferenceContext, ReferenceParams, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, Url, WorkDoneProgressParams,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct TestServer {
+    client: Connection,
+    thread: Option<JoinHandle<()>>,
+    last_id: i32,
+    notifications: Vec<Notification>,
+}
+
+impl TestServer {
+    fn start(root: &Path) -> Self {
+        Self::start_with_options(root, None)
+    }
+
+    fn start_with_options(root: &Path, options: Option<serde_json::Value>) -> Self {
+        let (server, client) = Connection::memory();
+        let thread = spawn(move || {
+            super::run_server(&server).expect("Server should exit cleanly");
+        });
+        let mut test_server = Self {
+            client,
+            thread: Some(thread),
+            last_id: 0,
+            notifications: vec![],
+        };
+        #[allow(deprecated)]
+        let params = InitializeParams {
+            root_uri: Some(Url::from_file_path(root).expect("Root should be valid Url")),
+            initialization_options: options,
+            ..Default::default()
+        };
+        test_server.request::<Initialize>(params);
+        test_server.notify::<Initialized>(InitializedParams {});
+        test_server
+    }
+
+    fn request<R>(&mut self, params: R::Params) -> R::Result
+    where
+        R: lsp_types::request::Request,
+    {
+        let params = serde_json::to_value(params).expect("Params should serialize");
+        let response = self.send_request(R::METHOD, params);
+        if let Some(err) = response.error {
+            panic!("{} request failed: {}", R::METHOD, err.message);
+        }
+        serde_json::from_value(response.result.unwrap_or_default())
+            .expect("Response should match request type")
+    }
+
+    /// Send request with raw params and wait for its response.
+    fn send_request(&mut self, method: &str, params: serde_json::Value) -> Response {
+        self.last_id += 1;
+        let id = RequestId::from(self.last_id);
+        self.client
+            .sender
+            .send(Message::Request(Request::new(
+                id.clone(),
+                method.into(),
+                params,
+            )))
+            .expect("Request should be sent");
+        loop {
+            match self.client.receiver.recv_timeout(TIMEOUT) {
+                Ok(Message::Response(response)) if response.id == id => return response,
+                Ok(Message::Notification(notification)) => self.notifications.push(notification),
+                Ok(_) => (),
+                Err(err) => panic!("No response for {method} request: {err}"),
+            }
+        }
+    }
+
+    fn notify<N>(&self, params: N::Params)
+    where
+        N: lsp_types::notification::Notification,
+    {
+        self.client
+            .sender
+            .send(Message::Notification(Notification::new(
+                N::METHOD.into(),
+                params,
+            )))
+            .expect("Notification should be sent");
+    }
+
+    fn open(&self, path: &Path) {
+        let text = std::fs::read_to_string(path).expect("Fixture should be readable");
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: Url::from_file_path(path).expect("Path should be valid Url"),
+                language_id: String::new(),
+                version: 0,
+                text,
+            },
+        });
+    }
+
+    fn hover(&mut self, path: &Path, line: u32, character: u32) -> Option<Hover> {
+        let params = HoverParams {
+            text_document_position_params: text_document_position(path, line, character),
+            work_done_progress_params: Default::default(),
+        };
+        self.poll(|server| server.request::<HoverRequest>(params.clone()))
+    }
+
+    fn definition(&mut self, path: &Path, line: u32, character: u32) -> Vec<Location> {
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.poll(
+            |server| match server.request::<GotoDefinition>(params.clone())? {
+                GotoDefinitionResponse::Array(locations) if !locations.is_empty() => {
+                    Some(locations)
+                }
+                GotoDefinitionResponse::Scalar(location) => Some(vec![location]),
+                _ => None,
+            },
+        )
+        .unwrap_or_default()
+    }
+
+    fn references(&mut self, path: &Path, line: u32, character: u32) -> Vec<Location> {
+        let params = ReferenceParams {
+            text_document_position: text_document_position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        };
+        self.poll(|server| {
+            server
+                .request::<References>(params.clone())
+                .filter(|locations| !locations.is_empty())
+        })
+        .unwrap_or_default()
+    }
+
+    fn implementation(&mut self, path: &Path, line: u32, character: u32) -> Vec<Location> {
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.poll(
+            |server| match server.request::<GotoImplementation>(params.clone())? {
+                GotoDefinitionResponse::Array(locations) if !locations.is_empty() => {
+                    Some(locations)
+                }
+                _ => None,
+            },
+        )
+        .unwrap_or_default()
+    }
+
+    fn declaration(&mut self, path: &Path, line: u32, character: u32) -> Vec<Location> {
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(path, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        self.poll(
+            |server| match server.request::<GotoDeclaration>(params.clone())? {
+                GotoDefinitionResponse::Array(locations) if !locations.is_empty() => {
+                    Some(locations)
+                }
+                _ => None,
+            },
+        )
+        .unwrap_or_default()
+    }
+
+    /// Wait for diagnostics published for the file.
+    fn diagnostics(&mut self, path: &Path) -> Vec<Diagnostic> {
+        let uri = Url::from_file_path(path).expect("Path should be valid Url");
+        loop {
+            let found = self.notifications.iter().position(|n| {
+                n.method == PublishDiagnostics::METHOD
+                    && n.params.get("uri").and_then(|u| u.as_str()) == Some(uri.as_str())
+            });
+            if let Some(index) = found {
+                let notification = self.notifications.remove(index);
+                let params: PublishDiagnosticsParams = serde_json::from_value(notification.params)
+                    .expect("Should be diagnostics params");
+                return params.diagnostics;
+            }
+            match self.client.receiver.recv_timeout(TIMEOUT) {
+                Ok(Message::Notification(notification)) => self.notifications.push(notification),
+                Ok(_) => (),
+                Err(err) => panic!("No diagnostics published: {err}"),
+            }
+        }
+    }
+
+    /// Index is built in background, so repeat the request until it gives some result.
+    fn poll<T>(&mut self, mut request: impl FnMut(&mut Self) -> Option<T>) -> Option<T> {
+        let start = Instant::now();
+        loop {
+            match request(self) {
+                Some(result) => return Some(result),
+                None if start.elapsed() > TIMEOUT => return None,
+                None => sleep(Duration::from_millis(20)),
+            }
+        }
+    }
+
+    /// Shutdown the server and wait for it to exit.
+    fn stop(&mut self) -> std::thread::Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        thread.join()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stop().ok();
+    }
+}
+
+fn text_document_position(path: &Path, line: u32, character: u32) -> TextDocumentPositionParams {
+    TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier {
+            uri: Url::from_file_path(path).expect("Path should be valid Url"),
+        },
+        position: Position { line, character },
+    }
+}
+
+fn fixture_path(parts: &[&str]) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.extend(["tests", "fixtures", "project"]);
+    path.extend(parts);
+    path
+}
+
+mod test {
+    use lsp_types::{
+        request::DocumentHighlightRequest, DocumentHighlight, DocumentHighlightKind,
+        DocumentHighlightParams, HoverContents,
+    };
+
+    use super::*;
+
+    const MODULE: [&str; 4] = ["app", "code", "Some", "Module"];
+
+    fn module_path(parts: &[&str]) -> PathBuf {
+        fixture_path(&[&MODULE[..], parts].concat())
+    }
+
+    fn location(path: &Path, line: u32, start: u32, end: u32) -> Location {
+        Location {
+            uri: Url::from_file_path(path).expect("Path should be valid Url"),
+            range: Range {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: end,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_shutdown_before_index_is_done() {
+        for _ in 0..3 {
+            let mut server = TestServer::start(&fixture_path(&[]));
+            let start = Instant::now();
+            assert!(server.stop().is_ok(), "Server thread should exit cleanly");
+            assert!(start.elapsed() < TIMEOUT);
+        }
+    }
+
+    #[test]
+    fn test_server_with_nonexistent_root() {
+        let mut server = TestServer::start(&fixture_path(&["missing"]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(&di_xml, 2, 80),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        assert_eq!(
+            server.request::<GotoDefinition>(params),
+            Some(GotoDefinitionResponse::Array(vec![]))
+        );
+        assert!(server.stop().is_ok());
+    }
+
+    #[test]
+    fn test_server_answers_malformed_request_with_error() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let response = server.send_request(
+            "textDocument/definition",
+            serde_json::json!({ "textDocument": "di.xml" }),
+        );
+        assert_eq!(
+            response.error.map(|err| err.code),
+            Some(ErrorCode::InvalidParams as i32)
+        );
+        let response = server.send_request("magento2-ls/unknown", serde_json::json!({}));
+        assert_eq!(
+            response.error.map(|err| err.code),
+            Some(ErrorCode::MethodNotFound as i32)
+        );
+
+        assert_eq!(
+            server.definition(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+        assert!(server.stop().is_ok());
+    }
+
+    #[test]
+    fn test_definition_times_out_without_blocking_next_requests() {
+        let mut server = TestServer::start_with_options(
+            &fixture_path(&[]),
+            Some(serde_json::json!({
+                "requestTimeout": 100,
+                "parsers": { "hang": "hanging" },
+            })),
+        );
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let start = Instant::now();
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(
+                &fixture_path(&["file.hang"]),
+                0,
+                0,
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        assert_eq!(
+            server.request::<GotoDefinition>(params),
+            Some(GotoDefinitionResponse::Array(vec![]))
+        );
+        assert!(start.elapsed() < TIMEOUT);
+
+        assert_eq!(
+            server.definition(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+        assert!(server.stop().is_ok());
+    }
+
+    #[test]
+    fn test_definition_of_scope_config_path() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let config_php = module_path(&["Model", "Config.php"]);
+        server.open(&config_php);
+
+        assert_eq!(
+            server.definition(&config_php, 15, 55),
+            vec![location(
+                &module_path(&["etc", "adminhtml", "system.xml"]),
+                5,
+                27,
+                34
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_class_in_di_xml() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        assert_eq!(
+            server.definition(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_console_command_in_di_xml() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        assert_eq!(
+            server.definition(&di_xml, 20, 90),
+            vec![location(
+                &module_path(&["Console", "Command", "ItemList.php"]),
+                5,
+                6,
+                14
+            )]
+        );
+    }
+
+    #[test]
+    fn test_hover_of_console_command_in_di_xml() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let content = server.poll(|server| match server.hover(&di_xml, 20, 90)?.contents {
+            HoverContents::Markup(content) if content.value.contains("some:item:list") => {
+                Some(content)
+            }
+            _ => None,
+        });
+        assert_eq!(
+            content.expect("Should have hover").value,
+            "Console command `some:item:list`\n\n\
+            Declared by `Some\\Module\\Console\\Command\\ItemList`"
+        );
+    }
+
+    #[test]
+    fn test_definition_of_deploy_config_in_di_xml() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        assert_eq!(
+            server.definition(&di_xml, 5, 60),
+            vec![location(
+                &fixture_path(&["app", "etc", "env.php"]),
+                5,
+                16,
+                27
+            )]
+        );
+    }
+
+    #[test]
+    fn test_hover_of_deploy_config_in_di_xml() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let hover = server.hover(&di_xml, 5, 60).expect("Should have hover");
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("Hover should be markup");
+        };
+        assert!(content
+            .value
+            .contains("`['cache']['frontend']['default']['id_prefix']`"));
+    }
+
+    #[test]
+    fn test_references_of_plugin_on_interface() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        assert_eq!(
+            server.references(&di_xml, 9, 50),
+            vec![
+                location(&module_path(&["Model", "Item.php"]), 6, 6, 10),
+                location(&module_path(&["Model", "SpecialItem.php"]), 4, 6, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references_of_block_class_in_layouts() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let block_php = module_path(&["Block", "ItemList.php"]);
+        server.open(&block_php);
+
+        assert_eq!(
+            server.references(&block_php, 4, 8),
+            vec![
+                location(
+                    &module_path(&["view", "frontend", "layout", "default.xml"]),
+                    4,
+                    26,
+                    52
+                ),
+                location(
+                    &module_path(&["view", "adminhtml", "layout", "some_item_index.xml"]),
+                    4,
+                    26,
+                    52
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_vendor_class_constant() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let export_php = module_path(&["Model", "ItemExport.php"]);
+        server.open(&export_php);
+
+        let directory_list = fixture_path(&[
+            "vendor",
+            "magento",
+            "framework",
+            "App",
+            "Filesystem",
+            "DirectoryList.php",
+        ]);
+        assert_eq!(
+            server.definition(&export_php, 11, 64),
+            vec![location(&directory_list, 10, 10, 17)]
+        );
+        assert_eq!(
+            server.definition(&export_php, 11, 50),
+            vec![location(&directory_list, 4, 6, 19)]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_trait_used_in_class() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let logger_php = module_path(&["Model", "ItemExportLogger.php"]);
+        server.open(&logger_php);
+
+        assert_eq!(
+            server.definition(&logger_php, 6, 12),
+            vec![location(
+                &module_path(&["Model", "ExportLoggerTrait.php"]),
+                4,
+                6,
+                23
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_patch_dependencies() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let patch_php = module_path(&["Setup", "Patch", "Data", "UpdateItemNames.php"]);
+        server.open(&patch_php);
+
+        let install_items = location(
+            &module_path(&["Setup", "Patch", "Data", "InstallItems.php"]),
+            6,
+            6,
+            18,
+        );
+        assert_eq!(
+            server.definition(&patch_php, 16, 16),
+            vec![install_items.clone()]
+        );
+        assert_eq!(server.definition(&patch_php, 17, 45), vec![install_items]);
+    }
+
+    #[test]
+    fn test_implementation_of_interface() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let interface_php = module_path(&["Api", "ItemInterface.php"]);
+        server.open(&interface_php);
+
+        assert_eq!(
+            server.implementation(&interface_php, 4, 14),
+            vec![
+                location(&module_path(&["Model", "Item.php"]), 6, 6, 10),
+                location(&module_path(&["Model", "SpecialItem.php"]), 4, 6, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_declaration_of_method_called_on_injected_interface() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let repository_php = module_path(&["Model", "ItemRepository.php"]);
+        server.open(&repository_php);
+        let item_method = vec![location(&module_path(&["Model", "Item.php"]), 10, 20, 27)];
+        let interface_method = vec![location(
+            &module_path(&["Api", "ItemInterface.php"]),
+            6,
+            20,
+            27,
+        )];
+
+        // declared type (and definition) is used until preference and classes are indexed
+        assert_eq!(
+            server.poll(|server| {
+                Some(server.definition(&repository_php, 16, 30)).filter(|l| *l == item_method)
+            }),
+            Some(item_method)
+        );
+        assert_eq!(
+            server.poll(|server| {
+                Some(server.declaration(&repository_php, 16, 30)).filter(|l| *l == interface_method)
+            }),
+            Some(interface_method)
+        );
+    }
+
+    #[test]
+    fn test_declaration_falls_back_to_definition() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        assert_eq!(
+            server.declaration(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+    }
+
+    #[test]
+    fn test_declaration_times_out_without_blocking_next_requests() {
+        let mut server = TestServer::start_with_options(
+            &fixture_path(&[]),
+            Some(serde_json::json!({
+                "requestTimeout": 100,
+                "parsers": { "hang": "hanging" },
+            })),
+        );
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let start = Instant::now();
+        let params = GotoDefinitionParams {
+            text_document_position_params: text_document_position(
+                &fixture_path(&["file.hang"]),
+                0,
+                0,
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        assert_eq!(
+            server.request::<GotoDeclaration>(params),
+            Some(GotoDefinitionResponse::Array(vec![]))
+        );
+        assert!(start.elapsed() < TIMEOUT);
+
+        assert_eq!(
+            server.declaration(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+        assert!(server.stop().is_ok());
+    }
+
+    #[test]
+    fn test_hover_of_plugin_on_interface() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+
+        let expected = "Plugin on `Some\\Module\\Api\\ItemInterface` applies to all implementing \
+            classes:\n\n- `Some\\Module\\Model\\Item`\n- `Some\\Module\\Model\\SpecialItem`";
+
+        // implementing classes are listed once PHP files are indexed
+        let content = server.poll(|server| {
+            let hover = server.hover(&di_xml, 9, 50)?;
+            let HoverContents::Markup(content) = hover.contents else {
+                panic!("Hover should be markup");
+            };
+            Some(content.value).filter(|value| value == expected)
+        });
+        assert_eq!(content.as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn test_hover_of_constructor_param_with_preference() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let repository_php = module_path(&["Model", "ItemRepository.php"]);
+        server.open(&repository_php);
+
+        let content = server.poll(
+            |server| match server.hover(&repository_php, 9, 30)?.contents {
+                HoverContents::Markup(content) if content.value.contains("Injected") => {
+                    Some(content)
+                }
+                _ => None,
+            },
+        );
+        assert_eq!(
+            content.expect("Should have hover").value,
+            "`$item`: `Some\\Module\\Api\\ItemInterface`\n\n\
+            Injected as `Some\\Module\\Model\\Item` (global preference)"
+        );
+    }
+
+    #[test]
+    fn test_hover_of_observer_class() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let observer_php = module_path(&["Observer", "LogItem.php"]);
+        server.open(&observer_php);
+
+        let hover = server
+            .hover(&observer_php, 7, 8)
+            .expect("Should have hover");
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("Hover should be markup");
+        };
+        assert_eq!(
+            content.value,
+            "Observer of events:\n\n\
+            - `controller_action_predispatch` (`Some_Module` `etc/frontend/events.xml`)\n\
+            - `some_item_save_after` (`Some_Module` `etc/events.xml`)"
+        );
+    }
+
+    #[test]
+    fn test_references_of_observer_class() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let observer_php = module_path(&["Observer", "LogItem.php"]);
+        server.open(&observer_php);
+
+        // both events.xml files have to be indexed
+        assert_eq!(
+            server.poll(|server| {
+                Some(server.references(&observer_php, 7, 8)).filter(|l| l.len() == 2)
+            }),
+            Some(vec![
+                location(&module_path(&["etc", "frontend", "events.xml"]), 3, 49, 77),
+                location(&module_path(&["etc", "events.xml"]), 3, 49, 77),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_hover_of_view_image_inherited_from_parent_themes() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let view_xml = fixture_path(&[
+            "app", "design", "frontend", "Some", "child", "etc", "view.xml",
+        ]);
+        server.open(&view_xml);
+
+        let content = server.poll(|server| match server.hover(&view_xml, 4, 25)?.contents {
+            HoverContents::Markup(content)
+                if content.value.contains("module") && content.value.contains("base") =>
+            {
+                Some(content)
+            }
+            _ => None,
+        });
+        assert_eq!(
+            content.expect("Should have hover").value,
+            "Image `some_item_thumbnail` of `Some_Module`:\n\n\
+            - `type`: `thumbnail`\n\
+            - `width`: `100` (from `frontend/Some/base`)\n\
+            - `height`: `120`\n\
+            - `aspect_ratio`: `true` (from module `view.xml`)"
+        );
+    }
+
+    #[test]
+    fn test_hover_of_constructor_param_with_di_argument() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let repository_php = module_path(&["Model", "ItemRepository.php"]);
+        server.open(&repository_php);
+
+        let content = server.poll(
+            |server| match server.hover(&repository_php, 10, 17)?.contents {
+                HoverContents::Markup(content) if content.value.contains("Argument") => {
+                    Some(content)
+                }
+                _ => None,
+            },
+        );
+        assert_eq!(
+            content.expect("Should have hover").value,
+            "`$prefix`: `string`\n\n\
+            Argument set in global `di.xml` to `repository` (`string`)"
+        );
+    }
+
+    #[test]
+    fn test_definition_of_class_constant_array_key_in_php() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let pool_php = module_path(&["Model", "ItemPool.php"]);
+        server.open(&pool_php);
+
+        assert_eq!(
+            server.definition(&pool_php, 9, 12),
+            vec![location(
+                &module_path(&["Api", "ItemInterface.php"]),
+                4,
+                10,
+                23
+            )]
+        );
+        assert_eq!(
+            server.definition(&pool_php, 9, 33),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_class_alias_in_file_with_more_classes() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let legacy_php = module_path(&["Model", "Legacy.php"]);
+        server.open(&legacy_php);
+
+        assert_eq!(
+            server.definition(&legacy_php, 17, 13),
+            vec![location(&legacy_php, 8, 6, 16)]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_ui_component_in_layout() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let layout_xml = module_path(&["view", "adminhtml", "layout", "some_item_index.xml"]);
+        server.open(&layout_xml);
+
+        assert_eq!(
+            server.definition(&layout_xml, 5, 35),
+            vec![location(
+                &module_path(&["view", "adminhtml", "ui_component", "some_item_listing.xml"]),
+                0,
+                0,
+                0
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_ui_data_source_reference() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let listing_xml =
+            module_path(&["view", "adminhtml", "ui_component", "some_item_listing.xml"]);
+        server.open(&listing_xml);
+
+        assert_eq!(
+            server.definition(&listing_xml, 4, 40),
+            vec![
+                location(&listing_xml, 7, 22, 51),
+                location(&listing_xml, 8, 105, 134),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_option_source_in_ui_component() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let listing_xml =
+            module_path(&["view", "adminhtml", "ui_component", "some_item_listing.xml"]);
+        server.open(&listing_xml);
+
+        assert_eq!(
+            server.definition(&listing_xml, 13, 60),
+            vec![location(&module_path(&["etc", "di.xml"]), 24, 23, 42)]
+        );
+        assert_eq!(
+            server.definition(&listing_xml, 19, 50),
+            vec![location(
+                &module_path(&["Model", "Config", "Source", "ItemType.php"]),
+                6,
+                6,
+                14
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_component_in_ui_component() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let listing_xml =
+            module_path(&["view", "adminhtml", "ui_component", "some_item_listing.xml"]);
+        server.open(&listing_xml);
+
+        assert_eq!(
+            server.definition(&listing_xml, 10, 60),
+            vec![location(
+                &module_path(&["view", "adminhtml", "web", "js", "grid", "columns.js"]),
+                0,
+                0,
+                0
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_component_overridden_in_theme() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let layout_xml = module_path(&["view", "frontend", "layout", "default.xml"]);
+        server.open(&layout_xml);
+
+        let expected = vec![
+            location(
+                &module_path(&["view", "frontend", "web", "js", "item.js"]),
+                0,
+                0,
+                0,
+            ),
+            location(
+                &fixture_path(&[
+                    "app",
+                    "design",
+                    "frontend",
+                    "Some",
+                    "child",
+                    "Some_Module",
+                    "web",
+                    "js",
+                    "item.js",
+                ]),
+                0,
+                0,
+                0,
+            ),
+        ];
+        assert_eq!(
+            server.poll(|s| Some(s.definition(&layout_xml, 10, 70)).filter(|l| *l == expected)),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_definition_of_less_variable_and_mixin() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let source = [
+            "app", "design", "frontend", "Some", "child", "web", "css", "source",
+        ];
+        let extend_less = fixture_path(&[&source[..], &["_extend.less"]].concat());
+        server.open(&extend_less);
+
+        assert_eq!(
+            server.definition(&extend_less, 4, 16),
+            vec![location(
+                &fixture_path(&[&source[..], &["_variables.less"]].concat()),
+                1,
+                0,
+                12
+            )]
+        );
+        assert_eq!(
+            server.definition(&extend_less, 5, 8),
+            vec![location(
+                &fixture_path(&["lib", "web", "css", "source", "lib", "_buttons.less"]),
+                0,
+                0,
+                11
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_magento_import_and_symbol_imported_by_it() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let styles_less = fixture_path(&[
+            "app",
+            "design",
+            "frontend",
+            "Some",
+            "child",
+            "web",
+            "css",
+            "styles.less",
+        ]);
+        server.open(&styles_less);
+        let module_less =
+            module_path(&["view", "frontend", "web", "css", "source", "_module.less"]);
+
+        // modules are known once registrations are indexed
+        assert_eq!(
+            server.poll(|server| {
+                Some(server.definition(&styles_less, 0, 25)).filter(|l| !l.is_empty())
+            }),
+            Some(vec![location(&module_less, 0, 0, 0)])
+        );
+        assert_eq!(
+            server.definition(&styles_less, 3, 16),
+            vec![location(&module_less, 0, 0, 19)]
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_of_block_name() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let layout_xml = module_path(&["view", "frontend", "layout", "default.xml"]);
+        server.open(&layout_xml);
+
+        let highlight = |line, start, end, kind| DocumentHighlight {
+            range: location(&layout_xml, line, start, end).range,
+            kind: Some(kind),
+        };
+        let params = DocumentHighlightParams {
+            text_document_position_params: text_document_position(&layout_xml, 6, 35),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        assert_eq!(
+            server.request::<DocumentHighlightRequest>(params),
+            Some(vec![
+                highlight(4, 60, 70, DocumentHighlightKind::WRITE),
+                highlight(6, 30, 40, DocumentHighlightKind::READ),
+                highlight(8, 30, 40, DocumentHighlightKind::READ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_definition_of_doc_references() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let repository_php = module_path(&["Model", "ItemRepository.php"]);
+        server.open(&repository_php);
+
+        assert_eq!(
+            server.definition(&repository_php, 20, 40),
+            vec![location(&module_path(&["Model", "Item.php"]), 10, 20, 27)]
+        );
+        assert_eq!(
+            server.definition(&repository_php, 21, 15),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_doc_types_with_preference() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let repository_php = module_path(&["Model", "ItemRepository.php"]);
+        server.open(&repository_php);
+        let interface = location(&module_path(&["Api", "ItemInterface.php"]), 4, 10, 23);
+        let item = location(&module_path(&["Model", "Item.php"]), 6, 6, 10);
+        let with_preference = vec![interface, item.clone()];
+
+        assert_eq!(
+            server.poll(|server| {
+                Some(server.definition(&repository_php, 29, 20)).filter(|l| *l == with_preference)
+            }),
+            Some(with_preference.clone())
+        );
+        assert_eq!(server.definition(&repository_php, 30, 20), with_preference);
+        assert_eq!(server.definition(&repository_php, 38, 16), vec![item]);
+    }
+
+    #[test]
+    fn test_definition_of_data_fixtures() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let test_php = module_path(&["Test", "Integration", "ItemTest.php"]);
+        server.open(&test_php);
+        let fixture_php = location(
+            &module_path(&["Test", "Integration", "_files", "item.php"]),
+            0,
+            0,
+            0,
+        );
+
+        assert_eq!(
+            server.definition(&test_php, 9, 50),
+            vec![fixture_php.clone()]
+        );
+        assert_eq!(server.definition(&test_php, 10, 40), vec![fixture_php]);
+        assert_eq!(
+            server.definition(&test_php, 11, 70),
+            vec![location(
+                &module_path(&["Test", "Fixture", "ItemFixture.php"]),
+                6,
+                27,
+                37
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_in_minified_js_with_source_map() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let js_dir = ["view", "frontend", "web", "js"];
+        let min_js = module_path(&[&js_dir[..], &["item.min.js"]].concat());
+        server.open(&min_js);
+
+        assert_eq!(
+            server.definition(&min_js, 0, 52),
+            vec![location(
+                &module_path(&[&js_dir[..], &["item.js"]].concat()),
+                3,
+                20,
+                20
+            )]
+        );
+    }
+
+    #[test]
+    fn test_definition_of_magic_method_annotation() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let data_php = module_path(&["Model", "ItemData.php"]);
+        server.open(&data_php);
+
+        assert_eq!(
+            server.definition(&data_php, 12, 24),
+            vec![location(&data_php, 5, 18, 26)]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_of_orphan_layout_references() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let layout_xml = module_path(&["view", "frontend", "layout", "default.xml"]);
+        server.open(&layout_xml);
+
+        let diagnostics = server.diagnostics(&layout_xml);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(7, 34));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].message,
+            "Container `missing.container` is not defined in any indexed layout"
+        );
+    }
+
+    #[test]
+    fn test_rebuild_index_command() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let params = ExecuteCommandParams {
+            command: "magento2-ls.rebuildIndex".into(),
+            arguments: vec![],
+            work_done_progress_params: WorkDoneProgressParams {
+                work_done_token: Some(ProgressToken::String("rebuild".into())),
+            },
+        };
+        let stats = server
+            .poll(|server| server.request::<ExecuteCommand>(params.clone()))
+            .expect("Index should be rebuilt");
+        assert!(stats["modules"].as_u64().is_some_and(|count| count > 0));
+        assert!(stats["classes"].as_u64().is_some_and(|count| count > 0));
+        let progress = server
+            .notifications
+            .iter()
+            .filter(|n| n.method == "$/progress")
+            .map(|n| n.params["value"]["kind"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(progress, vec!["begin", "end"]);
+
+        let di_xml = module_path(&["etc", "di.xml"]);
+        server.open(&di_xml);
+        assert_eq!(
+            server.definition(&di_xml, 2, 80),
+            vec![location(&module_path(&["Model", "Item.php"]), 6, 6, 10)]
+        );
+    }
+
+    #[test]
+    fn test_unused_preferences_report() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let params = ExecuteCommandParams {
+            command: "magento2-ls.unusedPreferences".into(),
+            arguments: vec![],
+            work_done_progress_params: Default::default(),
+        };
+        let count = server.poll(|server| server.request::<ExecuteCommand>(params.clone()));
+        assert_eq!(count, Some(serde_json::json!(1)));
+
+        let diagnostics = server.diagnostics(&module_path(&["etc", "di.xml"]));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(16, 64));
+        assert_eq!(
+            diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "Preference for `Some\\Module\\Api\\ItemExportInterface` is not used in any indexed file"
+        );
+    }
+
+    #[test]
+    fn test_definition_of_module_name() {
+        let mut server = TestServer::start(&fixture_path(&[]));
+        let module_xml = module_path(&["etc", "module.xml"]);
+        server.open(&module_xml);
+
+        let locations = server.definition(&module_xml, 2, 20);
+        let paths = locations
+            .iter()
+            .map(|l| l.uri.to_file_path().expect("Should be file Url"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![module_path(&["registration.php"]), module_xml.clone()]
+        );
+    }
+}
@@ -1,12 +1,14 @@
 use std::path::{Path, PathBuf};
 
 use glob::glob;
-use lsp_types::{Position, Range};
+use lsp_types::{Location, Position, Range, Url};
 use tree_sitter::{Node, QueryCursor};
 
 use crate::{
-    m2::{M2Area, M2Item, M2Path},
+    composer,
+    m2::{self, M2Area, M2Item, M2Path},
     queries,
+    sourcemap::{self, SourceMap},
     state::{ArcState, State},
     ts::{self, node_at_position},
 };
@@ -35,14 +37,16 @@ pub fn update_index(state: &ArcState, path: &PathBuf) {
     // if current workspace is magento module
     process_glob(state, &path.append(&["view", "*", "requirejs-config.js"]));
     // if current workspace is magento installation
-    process_glob(
-        state,
-        &path.append(&["vendor", "*", "*", "view", "*", "requirejs-config.js"]),
-    );
-    process_glob(
-        state,
-        &path.append(&["vendor", "*", "*", "Magento_Theme", "requirejs-config.js"]),
-    );
+    for package in composer::indexed_vendor_packages(state, path) {
+        process_glob(
+            state,
+            &package.append(&["view", "*", "requirejs-config.js"]),
+        );
+        process_glob(
+            state,
+            &package.append(&["Magento_Theme", "requirejs-config.js"]),
+        );
+    }
     process_glob(
         state,
         &path.append(&["app", "code", "*", "*", "view", "*", "requirejs-config.js"]),
@@ -60,8 +64,9 @@ pub fn maybe_index_file(state: &mut State, content: &str, file_path: &PathBuf) {
 }
 
 fn index_file(state: &ArcState, file_path: &PathBuf) {
-    let content =
-        std::fs::read_to_string(file_path).expect("Should have been able to read the file");
+    let Ok(content) = std::fs::read_to_string(file_path) else {
+        return;
+    };
 
     update_index_from_config(&mut state.lock(), &content, file_path);
 }
@@ -72,6 +77,9 @@ fn process_glob(state: &ArcState, glob_path: &PathBuf) {
         .filter_map(Result::ok);
 
     for file_path in modules {
+        if state.lock().is_index_cancelled() {
+            return;
+        }
         index_file(state, &file_path);
     }
 }
@@ -114,6 +122,43 @@ pub fn get_item_from_position(state: &State, path: &PathBuf, pos: Position) -> O
     get_item_from_pos(state, content, path, pos)
 }
 
+/// Original source location of the position in generated (like minified) file, found with
+/// source map from `//# sourceMappingURL=` comment. Inline (`data:`) maps are not supported.
+pub fn get_source_map_location(state: &State, path: &Path, pos: Position) -> Option<Location> {
+    let content = state.get_file(&path.to_path_buf())?;
+    let url = sourcemap::source_mapping_url(content)?;
+    if url.starts_with("data:") {
+        return None;
+    }
+    let map_path = match Url::parse(url) {
+        Ok(url) => url.to_file_path().ok()?,
+        Err(_) => path.parent()?.join(url.split(['?', '#']).next()?),
+    };
+    let map = SourceMap::parse(&std::fs::read_to_string(&map_path).ok()?)?;
+    let (source, position) = map.original_position(pos)?;
+
+    let source_path = match Url::parse(source) {
+        Ok(url) => url.to_file_path().ok()?,
+        Err(_) => {
+            let mut source_path = map_path.parent()?.to_path_buf();
+            if let Some(root) = &map.source_root {
+                source_path.push(root);
+            }
+            source_path.join(source)
+        }
+    };
+    if !source_path.is_file() {
+        return None;
+    }
+    Some(Location {
+        uri: m2::path_to_uri(&source_path).ok()?,
+        range: Range {
+            start: position,
+            end: position,
+        },
+    })
+}
+
 pub fn text_to_component(state: &State, text: &str, path: &Path) -> Option<M2Item> {
     let mut text = text;
     if text.starts_with("text!") {
@@ -121,7 +166,7 @@ pub fn text_to_component(state: &State, text: &str, path: &Path) -> Option<M2Ite
     }
     let text = &resolve_paths(state, text, &path.to_path_buf().get_area())?;
     let text = resolve_maps(state, text, &path.to_path_buf().get_area())?;
-    return resolved_text_to_component(state, text, path);
+    resolved_text_to_component(state, text, path)
 }
 
 fn get_item_from_pos(state: &State, content: &str, path: &Path, pos: Position) -> Option<M2Item> {
@@ -0,0 +1,470 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Position, Range};
+
+use crate::{m2::M2Item, state::State};
+
+// There is no LESS grammar available, stylesheets are scanned as text with comments masked,
+// which is enough for finding imports, variable and mixin declarations.
+
+/// At-rules that look like variable references, but are not.
+const AT_RULES: [&str; 12] = [
+    "import",
+    "media",
+    "charset",
+    "font-face",
+    "keyframes",
+    "supports",
+    "namespace",
+    "page",
+    "plugin",
+    "document",
+    "viewport",
+    "magento_import",
+];
+
+/// Magento preprocessor directive importing the file of all modules, it is a comment
+/// for LESS, so it is looked for before comments are masked.
+const MAGENTO_IMPORT: &str = "//@magento_import";
+
+/// Variable or mixin of LESS stylesheet, declared in the file or in one of its imports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LessSymbol {
+    /// `@name: value;`, referenced as `@name` (or `@{name}` in interpolation)
+    Variable(String),
+    /// `.name(@param) { ... }`, called as `.name();`
+    Mixin(String),
+}
+
+pub fn get_item_from_position(state: &State, path: &Path, pos: Position) -> Option<M2Item> {
+    let content = state.get_file(&path.to_path_buf())?;
+    get_item_from_pos(content, path, pos)
+}
+
+fn get_item_from_pos(content: &str, path: &Path, pos: Position) -> Option<M2Item> {
+    let offset = position_to_offset(content, pos)?;
+    if let Some((import, _)) = magento_import_ranges(content)
+        .into_iter()
+        .find(|(_, (start, end))| *start <= offset && offset <= *end)
+    {
+        return Some(M2Item::LessMagentoImport(import, path.to_path_buf()));
+    }
+
+    let content = mask_comments(content);
+
+    if let Some((import, _)) = import_ranges(&content)
+        .into_iter()
+        .find(|(_, (start, end))| *start <= offset && offset <= *end)
+    {
+        return Some(M2Item::LessImport(import, path.to_path_buf()));
+    }
+
+    let bytes = content.as_bytes();
+    let mut start = offset;
+    while start > 0 && is_name_byte(bytes[start - 1]) {
+        start -= 1;
+    }
+    let mut end = offset;
+    while end < bytes.len() && is_name_byte(bytes[end]) {
+        end += 1;
+    }
+    if start == end {
+        return None;
+    }
+    let name = &content[start..end];
+    let before = &content[..start];
+    let symbol = if before.ends_with('@') || before.ends_with("@{") {
+        if AT_RULES.contains(&name) {
+            return None;
+        }
+        LessSymbol::Variable(name.into())
+    } else if before.ends_with('.') && !name.starts_with(|c: char| c.is_ascii_digit()) {
+        LessSymbol::Mixin(name.into())
+    } else {
+        return None;
+    };
+    Some(M2Item::LessSymbol(symbol, path.to_path_buf()))
+}
+
+/// Imported files, as written in `@import` statements, in the order of the statements.
+pub fn get_imports(content: &str) -> Vec<String> {
+    import_ranges(&mask_comments(content))
+        .into_iter()
+        .map(|(import, _)| import)
+        .collect()
+}
+
+/// Files imported by `//@magento_import` directives, in the order of the directives.
+pub fn get_magento_imports(content: &str) -> Vec<String> {
+    magento_import_ranges(content)
+        .into_iter()
+        .map(|(import, _)| import)
+        .collect()
+}
+
+/// Imports with offsets of their paths, in content with comments masked.
+fn import_ranges(content: &str) -> Vec<(String, (usize, usize))> {
+    directive_ranges(content, "@import")
+}
+
+/// `//@magento_import` directives starting the line, with offsets of their paths.
+fn magento_import_ranges(content: &str) -> Vec<(String, (usize, usize))> {
+    directive_ranges(content, MAGENTO_IMPORT)
+        .into_iter()
+        .filter(|(_, (start, _))| {
+            let line_start = content[..*start].rfind('\n').map_or(0, |i| i + 1);
+            content[line_start..]
+                .trim_start()
+                .starts_with(MAGENTO_IMPORT)
+        })
+        .collect()
+}
+
+/// Paths of import directives (with optional options and `url()`), and their offsets.
+fn directive_ranges(content: &str, directive: &str) -> Vec<(String, (usize, usize))> {
+    let mut result = vec![];
+    for (offset, _) in content.match_indices(directive) {
+        let mut rest = offset + directive.len();
+        let after = &content[rest..];
+        // options, like `@import (reference) 'file';`
+        let trimmed = after.trim_start();
+        rest += after.len() - trimmed.len();
+        if trimmed.starts_with('(') {
+            let Some(close) = trimmed.find(')') else {
+                continue;
+            };
+            rest += close + 1;
+        }
+        let after = &content[rest..];
+        let mut trimmed = after.trim_start();
+        rest += after.len() - trimmed.len();
+        if let Some(url) = trimmed.strip_prefix("url(") {
+            rest += "url(".len();
+            trimmed = url;
+        }
+        let Some(quote) = trimmed.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+            continue;
+        };
+        let Some(len) = trimmed[1..].find(quote) else {
+            continue;
+        };
+        let start = rest + 1;
+        let import = &content[start..start + len];
+        if !import.is_empty() && !import.contains('\n') {
+            result.push((import.to_string(), (start, start + len)));
+        }
+    }
+    result
+}
+
+/// Ranges of declarations of the symbol in the file, mixins with guards can be declared
+/// more than once.
+pub fn find_declarations(content: &str, symbol: &LessSymbol) -> Vec<Range> {
+    let content = mask_comments(content);
+    let (prefix, name) = match symbol {
+        LessSymbol::Variable(name) => ('@', name),
+        LessSymbol::Mixin(name) => ('.', name),
+    };
+    let mut result = vec![];
+    for (offset, _) in content.match_indices(name.as_str()) {
+        let end = offset + name.len();
+        if !content[..offset].ends_with(prefix)
+            || content[..offset - 1].ends_with(prefix)
+            || content[end..].starts_with(|c: char| c.is_ascii() && is_name_byte(c as u8))
+        {
+            continue;
+        }
+        // declarations start the statement (or selector of the rule set)
+        let before = content[..offset - 1].trim_end();
+        if !before.is_empty() && !before.ends_with(['{', '}', ';']) {
+            continue;
+        }
+        let after = content[end..].trim_start();
+        let is_declaration = match symbol {
+            LessSymbol::Variable(_) => after.starts_with(':'),
+            LessSymbol::Mixin(_) => {
+                let after = match after.strip_prefix('(') {
+                    Some(params) => {
+                        closing_paren(params).map_or("", |i| params[i + 1..].trim_start())
+                    }
+                    None => after,
+                };
+                after.starts_with('{') || after.starts_with("when")
+            }
+        };
+        if is_declaration {
+            result.push(Range {
+                start: offset_to_position(&content, offset - 1),
+                end: offset_to_position(&content, end),
+            });
+        }
+    }
+    result
+}
+
+/// Candidates of the imported file, relative to the importing one, `.less` extension is
+/// added when the import has none.
+pub fn import_candidates(dir: &Path, import: &str) -> Vec<PathBuf> {
+    if import.contains("://") || import.starts_with("//") || import.ends_with(".css") {
+        return vec![];
+    }
+    let file = if Path::new(import).extension().is_some() {
+        import.to_string()
+    } else {
+        format!("{import}.less")
+    };
+    vec![dir.join(file)]
+}
+
+/// Index of the parenthesis closing already opened one.
+fn closing_paren(content: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in content.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Replace comments with spaces, so offsets are kept and commented out code is skipped.
+fn mask_comments(content: &str) -> String {
+    let bytes = content.as_bytes();
+    let mut result = bytes.to_vec();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match (quote, bytes[i]) {
+            (Some(q), b) if b == q => quote = None,
+            (Some(_), b'\\') => i += 1,
+            (Some(_), _) => (),
+            (None, b'\'' | b'"') => quote = Some(bytes[i]),
+            (None, b'/') if bytes.get(i + 1) == Some(&b'/') => {
+                // `//` of urls, like `url(http://...)`, is not a comment
+                if i > 0 && bytes[i - 1] == b':' {
+                    i += 2;
+                    continue;
+                }
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    result[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            }
+            (None, b'/') if bytes.get(i + 1) == Some(&b'*') => {
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    if bytes[i] != b'\n' {
+                        result[i] = b' ';
+                    }
+                    i += 1;
+                }
+                for b in result.iter_mut().skip(i).take(2) {
+                    *b = b' ';
+                }
+                i += 2;
+                continue;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    // only ascii bytes are replaced, so the content is still valid utf-8
+    String::from_utf8(result).unwrap_or_else(|_| content.to_string())
+}
+
+const fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+fn position_to_offset(content: &str, pos: Position) -> Option<usize> {
+    let mut offset = 0;
+    for (i, line) in content.split('\n').enumerate() {
+        if i == pos.line as usize {
+            let column = line
+                .char_indices()
+                .nth(pos.character as usize)
+                .map_or(line.len(), |(i, _)| i);
+            return Some(offset + column);
+        }
+        offset += line.len() + 1;
+    }
+    None
+}
+
+fn offset_to_position(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: line as u32,
+        character: before[line_start..].chars().count() as u32,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_test_item(less: &str) -> Option<M2Item> {
+        let mut character = 0;
+        let mut line = 0;
+        for l in less.lines() {
+            if l.contains('|') {
+                character = l.find('|').expect("Test has to have a | character") as u32;
+                break;
+            }
+            line += 1;
+        }
+        let pos = Position { line, character };
+        get_item_from_pos(
+            &less.replace('|', ""),
+            Path::new("/a/web/css/file.less"),
+            pos,
+        )
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> Range {
+        Range {
+            start: Position {
+                line,
+                character: start,
+            },
+            end: Position {
+                line,
+                character: end,
+            },
+        }
+    }
+
+    #[test]
+    fn test_get_item_from_pos_variable() {
+        assert_eq!(
+            get_test_item(".a { color: @text__co|lor; }"),
+            Some(M2Item::LessSymbol(
+                LessSymbol::Variable("text__color".into()),
+                PathBuf::from("/a/web/css/file.less")
+            ))
+        );
+        assert_eq!(
+            get_test_item(".a-@{na|me} { }"),
+            Some(M2Item::LessSymbol(
+                LessSymbol::Variable("name".into()),
+                PathBuf::from("/a/web/css/file.less")
+            ))
+        );
+        assert_eq!(get_test_item("@med|ia (min-width: 768px) { }"), None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_mixin_call() {
+        assert_eq!(
+            get_test_item(".a { .lib-font|-size(14); }"),
+            Some(M2Item::LessSymbol(
+                LessSymbol::Mixin("lib-font-size".into()),
+                PathBuf::from("/a/web/css/file.less")
+            ))
+        );
+        assert_eq!(get_test_item(".a { margin: 0.|5em; }"), None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_import() {
+        assert_eq!(
+            get_test_item("@import (reference) 'source/_var|iables';"),
+            Some(M2Item::LessImport(
+                "source/_variables".into(),
+                PathBuf::from("/a/web/css/file.less")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_magento_import() {
+        assert_eq!(
+            get_test_item("//@magento_import 'source/_mod|ule.less';"),
+            Some(M2Item::LessMagentoImport(
+                "source/_module.less".into(),
+                PathBuf::from("/a/web/css/file.less")
+            ))
+        );
+        assert_eq!(
+            get_test_item("// //@magento_import 'source/_mod|ule.less';"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_magento_imports() {
+        let content = r#"
+//@magento_import 'source/_module.less';
+  //@magento_import (reference) "source/_widgets.less";
+@import 'source/_variables';
+// //@magento_import 'commented.less';
+"#;
+        assert_eq!(
+            get_magento_imports(content),
+            vec!["source/_module.less", "source/_widgets.less"]
+        );
+    }
+
+    #[test]
+    fn test_get_imports() {
+        let content = r#"
+@import 'source/_variables';
+// @import 'commented';
+/* @import "also-commented"; */
+@import url("https://example.com/font.css");
+@import (css) "print.css";
+"#;
+        assert_eq!(
+            get_imports(content),
+            vec![
+                "source/_variables",
+                "https://example.com/font.css",
+                "print.css"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_declarations() {
+        let content = r#"@color: #000;
+.a { color: @color; }
+.lib-button(@color: @color) when (@color = #000) {
+    color: @color;
+}
+.lib-button(@color) { }
+.b { .lib-button(); }
+// .lib-button() { }
+"#;
+        assert_eq!(
+            find_declarations(content, &LessSymbol::Variable("color".into())),
+            vec![range(0, 0, 6)]
+        );
+        assert_eq!(
+            find_declarations(content, &LessSymbol::Mixin("lib-button".into())),
+            vec![range(2, 0, 11), range(5, 0, 11)]
+        );
+        assert_eq!(
+            find_declarations(content, &LessSymbol::Mixin("lib".into())),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_import_candidates() {
+        let dir = Path::new("/a/web/css");
+        assert_eq!(
+            import_candidates(dir, "source/_variables"),
+            vec![PathBuf::from("/a/web/css/source/_variables.less")]
+        );
+        assert_eq!(
+            import_candidates(dir, "_module.less"),
+            vec![PathBuf::from("/a/web/css/_module.less")]
+        );
+        assert!(import_candidates(dir, "print.css").is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use parking_lot::Mutex;
+
+static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Write message to stderr (stdout is used by the LSP connection) and the log file
+/// when it is set.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::logger::write(format_args!($($arg)*))
+    };
+}
+pub(crate) use log;
+
+pub fn write(message: fmt::Arguments) {
+    eprintln!("{message}");
+    if let Some(file) = LOG_FILE.get() {
+        writeln!(file.lock(), "{message}").ok();
+    }
+}
+
+/// Append log messages to the file too, missing parent directories are created.
+pub fn set_log_file(path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    LOG_FILE
+        .set(Mutex::new(file))
+        .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "Log file is already set"))
+}
+
+/// Path of `--log-file <path>` (or `--log-file=<path>`) command line option.
+pub fn log_file_from_args<I>(args: I) -> Result<Option<PathBuf>, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    let mut result = None;
+    while let Some(arg) = args.next() {
+        if arg == "--log-file" {
+            let path = args
+                .next()
+                .ok_or_else(|| "Missing path of --log-file option".to_string())?;
+            result = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--log-file=") {
+            result = Some(PathBuf::from(path));
+        }
+    }
+    Ok(result.filter(|path| !path.as_os_str().is_empty()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_log_file_from_args() {
+        assert_eq!(
+            log_file_from_args(args(&["--stdio", "--log-file", "/tmp/ls.log"])),
+            Ok(Some(PathBuf::from("/tmp/ls.log")))
+        );
+        assert_eq!(
+            log_file_from_args(args(&["--log-file=/tmp/ls.log"])),
+            Ok(Some(PathBuf::from("/tmp/ls.log")))
+        );
+        assert_eq!(log_file_from_args(args(&["--stdio"])), Ok(None));
+        assert!(log_file_from_args(args(&["--log-file"])).is_err());
+    }
+}
@@ -1,13 +1,52 @@
 mod completion;
 mod definition;
+mod diagnostics;
+mod highlight;
+mod hover;
+mod implementation;
+mod references;
 
 use lsp_types::{
-    CompletionParams, CompletionResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    request::{
+        GotoDeclarationParams, GotoDeclarationResponse, GotoImplementationParams,
+        GotoImplementationResponse, Request,
+    },
+    CompletionParams, CompletionResponse, Diagnostic, DocumentHighlight, DocumentHighlightParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, Location, ReferenceParams,
+    TextDocumentIdentifier,
 };
 
+use std::{collections::HashMap, path::PathBuf};
+
 use crate::state::State;
 
-use self::{completion::get_completion_from_params, definition::get_location_from_params};
+use self::{
+    completion::get_completion_from_params,
+    definition::{
+        get_declaration_from_params, get_location_from_params, get_module_locations_from_params,
+    },
+    diagnostics::{get_diagnostics_for_file, get_unused_preference_diagnostics},
+    highlight::get_highlights_from_params,
+    hover::get_hover_from_params,
+    implementation::get_implementations_from_params,
+    references::get_references_from_params,
+};
+
+/// Command publishing diagnostics for `di.xml` preferences of unused interfaces.
+pub const UNUSED_PREFERENCES_COMMAND: &str = "magento2-ls.unusedPreferences";
+
+/// Command rebuilding the index from scratch, the current index is used until it is done.
+pub const REBUILD_INDEX_COMMAND: &str = "magento2-ls.rebuildIndex";
+
+/// Custom request returning `registration.php` and `etc/module.xml` of the module
+/// that given document belongs to.
+pub enum ModuleFiles {}
+
+impl Request for ModuleFiles {
+    type Params = TextDocumentIdentifier;
+    type Result = Vec<Location>;
+    const METHOD: &'static str = "magento2-ls/moduleFiles";
+}
 
 pub fn completion_handler(state: &State, params: &CompletionParams) -> CompletionResponse {
     CompletionResponse::Array(
@@ -20,3 +59,48 @@ pub fn definition_handler(state: &State, params: &GotoDefinitionParams) -> GotoD
         get_location_from_params(state, params).map_or(vec![], |loc_list| loc_list),
     )
 }
+
+pub fn declaration_handler(
+    state: &State,
+    params: &GotoDeclarationParams,
+) -> GotoDeclarationResponse {
+    GotoDeclarationResponse::Array(
+        get_declaration_from_params(state, params).map_or(vec![], |loc_list| loc_list),
+    )
+}
+
+pub fn hover_handler(state: &State, params: &HoverParams) -> Option<Hover> {
+    get_hover_from_params(state, params)
+}
+
+pub fn references_handler(state: &State, params: &ReferenceParams) -> Vec<Location> {
+    get_references_from_params(state, params).map_or(vec![], |loc_list| loc_list)
+}
+
+pub fn implementation_handler(
+    state: &State,
+    params: &GotoImplementationParams,
+) -> GotoImplementationResponse {
+    GotoImplementationResponse::Array(
+        get_implementations_from_params(state, params).map_or(vec![], |loc_list| loc_list),
+    )
+}
+
+pub fn document_highlight_handler(
+    state: &State,
+    params: &DocumentHighlightParams,
+) -> Option<Vec<DocumentHighlight>> {
+    get_highlights_from_params(state, params)
+}
+
+pub fn module_files_handler(state: &State, params: &TextDocumentIdentifier) -> Vec<Location> {
+    get_module_locations_from_params(state, params).map_or(vec![], |loc_list| loc_list)
+}
+
+pub fn file_diagnostics(state: &State, path: &PathBuf) -> Vec<Diagnostic> {
+    get_diagnostics_for_file(state, path)
+}
+
+pub fn unused_preferences_report(state: &State) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    get_unused_preference_diagnostics(state)
+}
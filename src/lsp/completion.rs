@@ -10,9 +10,10 @@ use lsp_types::{
 
 use crate::{
     js::{self, JsCompletionType},
-    m2::{self, M2Area, M2Path, M2Uri},
+    m2::{self, M2Area, M2LayoutElement, M2Path, M2Uri},
+    php,
     state::State,
-    xml,
+    xml::{self, XmlCompletion},
 };
 
 pub fn get_completion_from_params(
@@ -23,12 +24,14 @@ pub fn get_completion_from_params(
         .text_document_position
         .text_document
         .uri
-        .to_path_buf();
+        .to_path_buf()
+        .ok()?;
     let pos = params.text_document_position.position;
 
     match path.get_ext().as_str() {
         "xml" => xml_completion_handler(state, &path, pos),
         "js" => js_completion_handler(state, &path, pos),
+        "php" | "phtml" => php_completion_handler(state, &path, pos),
         _ => None,
     }
 }
@@ -50,6 +53,15 @@ fn js_completion_handler(
     }
 }
 
+fn php_completion_handler(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<Vec<CompletionItem>> {
+    let range = php::get_event_name_range(state.get_file(path)?, pos)?;
+    Some(events::get_completion_items(state, range))
+}
+
 fn xml_completion_handler(
     state: &State,
     path: &PathBuf,
@@ -66,8 +78,14 @@ fn xml_completion_handler(
         x if x.attribute_eq("xsi:type", "string") && x.attribute_eq("name", "component") => {
             completion_for_component(state, &x.text, x.range, &path.get_area())
         }
+        x if x.match_path("/referenceBlock[@name]") && xml::is_layout_file(path) => Some(
+            completion_for_layout_names(state, M2LayoutElement::Block, &x, &path.get_area()),
+        ),
+        x if x.match_path("/referenceContainer[@name]") && xml::is_layout_file(path) => Some(
+            completion_for_layout_names(state, M2LayoutElement::Container, &x, &path.get_area()),
+        ),
         x if x.match_path("/config/event[@name]") && path.ends_with("events.xml") => {
-            Some(events::get_completion_items(x.range))
+            Some(events::get_completion_items(state, x.range))
         }
         x if x.match_path("/config/preference[@for]") && path.ends_with("di.xml") => {
             completion_for_classes(state, &x.text, x.range)
@@ -140,7 +158,7 @@ fn completion_for_classes_full(state: &State, text: &str, range: Range) -> Vec<C
             let candidates = glob(module_path.append(&["**", "*.php"]).to_path_str())
                 .expect("Failed to read glob pattern");
             for p in candidates {
-                let path = p.map_or_else(|_| std::path::PathBuf::new(), |p| p);
+                let path = p.unwrap_or_else(|_| std::path::PathBuf::new());
                 let rel_path = path.relative_to(&module_path).str_components().join("\\");
                 let class_suffix = rel_path.trim_end_matches(".php");
                 let class = format!("{}\\{}", prefix, class_suffix);
@@ -161,6 +179,38 @@ fn completion_for_classes_full(state: &State, text: &str, range: Range) -> Vec<C
     string_vec_and_range_to_completion_list(classes, range)
 }
 
+/// Block or container names from indexed layouts, the file defining the name is the detail.
+fn completion_for_layout_names(
+    state: &State,
+    element: M2LayoutElement,
+    at_position: &XmlCompletion,
+    area: &M2Area,
+) -> Vec<CompletionItem> {
+    let workspaces = state.workspace_paths();
+    state
+        .get_layout_names(area, element)
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(&at_position.text))
+        .map(|(name, location)| CompletionItem {
+            label: name.clone(),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: at_position.range,
+                new_text: name,
+            })),
+            kind: Some(CompletionItemKind::REFERENCE),
+            detail: location.uri.to_path_buf().ok().map(|path| {
+                workspaces
+                    .iter()
+                    .find(|workspace| path.starts_with(workspace))
+                    .map_or(path.clone(), |workspace| path.relative_to(workspace))
+                    .to_path_str()
+                    .to_string()
+            }),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
 fn completion_for_template(
     state: &State,
     text: &str,
@@ -173,7 +223,7 @@ fn completion_for_template(
     } else if text.contains("::") {
         let module_name = text.split("::").next()?;
         let path = state.get_module_path(module_name)?;
-        let mut theme_paths = state.list_themes_paths(&area);
+        let mut theme_paths = state.list_themes_paths(area);
         theme_paths.push(&path);
 
         let mut files = vec![];
@@ -295,3 +345,64 @@ fn string_vec_and_range_to_completion_list(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn completion_labels(xml: &str, path: &str) -> Vec<(String, Option<String>)> {
+        let mut state = State::new();
+        state.add_workspace_path(&PathBuf::from("/a"));
+        state.set_file(
+            &PathBuf::from("/a/view/frontend/layout/default.xml"),
+            r#"<page><body>
+    <block name="header.links"/>
+    <container name="header.panel"/>
+    <block name="footer"/>
+</body></page>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/view/adminhtml/layout/default.xml"),
+            r#"<page><body><block name="header.admin"/></body></page>"#,
+        );
+        let path = PathBuf::from(path);
+        let line = xml.lines().position(|l| l.contains('|')).unwrap_or(0);
+        let character = xml.lines().nth(line).and_then(|l| l.find('|')).unwrap_or(0);
+        state.set_file(&path, xml.replace('|', ""));
+        let pos = Position::new(line as u32, character as u32);
+        xml_completion_handler(&state, &path, pos)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| (item.label, item.detail))
+            .collect()
+    }
+
+    #[test]
+    fn test_completion_of_reference_block_names() {
+        let labels = completion_labels(
+            r#"<page><body><referenceBlock name="head|"/></body></page>"#,
+            "/a/view/frontend/layout/catalog_product_view.xml",
+        );
+        let detail = Some("view/frontend/layout/default.xml".to_string());
+        assert_eq!(labels, vec![("header.links".into(), detail)]);
+    }
+
+    #[test]
+    fn test_completion_of_reference_container_names() {
+        let labels = completion_labels(
+            r#"<page><body><referenceContainer name="|"/></body></page>"#,
+            "/a/view/frontend/layout/catalog_product_view.xml",
+        );
+        let detail = Some("view/frontend/layout/default.xml".to_string());
+        assert_eq!(labels, vec![("header.panel".into(), detail)]);
+    }
+
+    #[test]
+    fn test_completion_of_reference_block_names_outside_of_layout() {
+        let labels = completion_labels(
+            r#"<config><referenceBlock name="head|"/></config>"#,
+            "/a/etc/di.xml",
+        );
+        assert!(labels.is_empty());
+    }
+}
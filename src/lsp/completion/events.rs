@@ -1,4 +1,7 @@
 use lsp_types::{CompletionItem, CompletionItemKind, CompletionTextEdit, Range, TextEdit};
+
+use crate::state::State;
+
 pub const EVENT_LIST: [&str; 344] = [
     "abstract_search_result_load_after",
     "abstract_search_result_load_before",
@@ -346,19 +349,72 @@ pub const EVENT_LIST: [&str; 344] = [
     "{eventPrefix}_validate_before",
 ];
 
-pub fn get_completion_items(range: Range) -> Vec<CompletionItem> {
-    EVENT_LIST
-        .iter()
+/// Known Magento events and events observed in indexed `events.xml` files, with number
+/// of observers as the detail.
+pub fn get_completion_items(state: &State, range: Range) -> Vec<CompletionItem> {
+    let mut events = EVENT_LIST.to_vec();
+    events.extend(state.get_event_names());
+    events.sort_unstable();
+    events.dedup();
+    events
+        .into_iter()
         .map(|event| CompletionItem {
-            label: (*event).to_string(),
+            label: event.to_string(),
             text_edit: Some(CompletionTextEdit::Edit(TextEdit {
                 range,
-                new_text: (*event).to_string(),
+                new_text: event.to_string(),
             })),
             label_details: None,
             kind: Some(CompletionItemKind::EVENT),
-            detail: None,
+            detail: observers_detail(state.get_event_observers(event).len()),
             ..CompletionItem::default()
         })
         .collect()
 }
+
+fn observers_detail(count: usize) -> Option<String> {
+    match count {
+        0 => None,
+        1 => Some("1 observer".into()),
+        _ => Some(format!("{count} observers")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_get_completion_items_with_observer_count() {
+        let mut state = State::new();
+        state.set_file(
+            Path::new("/a/etc/events.xml"),
+            r#"<config>
+    <event name="sales_order_place_after">
+        <observer name="first" instance="A\First"/>
+        <observer name="second" instance="A\Second"/>
+        <observer name="off" instance="A\Off" disabled="true"/>
+    </event>
+    <event name="custom_event">
+        <observer name="custom" instance="A\Custom"/>
+    </event>
+</config>"#,
+        );
+
+        let items = get_completion_items(&state, Range::default());
+        let detail = |label: &str| {
+            items
+                .iter()
+                .find(|i| i.label == label)
+                .map(|i| i.detail.clone())
+        };
+        assert_eq!(
+            detail("sales_order_place_after"),
+            Some(Some("2 observers".into()))
+        );
+        assert_eq!(detail("custom_event"), Some(Some("1 observer".into())));
+        assert_eq!(detail("sales_order_save_after"), Some(None));
+    }
+}
@@ -1,13 +1,21 @@
+mod asset;
 mod component;
+mod deploy_config;
+mod less;
+mod module;
 mod php;
 mod phtml;
+mod typescript;
+mod ui_component;
 
 use std::path::Path;
 
-use lsp_types::{GotoDefinitionParams, Location, Range, Url};
+use lsp_types::{GotoDefinitionParams, Location, Range, TextDocumentIdentifier};
 
 use crate::{
-    m2::{M2Item, M2Uri},
+    js,
+    m2::{self, M2Item, M2Path, M2Uri},
+    parser::Parser,
     state::State,
 };
 
@@ -19,9 +27,15 @@ pub fn get_location_from_params(
         .text_document_position_params
         .text_document
         .uri
-        .to_path_buf();
+        .to_path_buf()
+        .ok()?;
     let pos = params.text_document_position_params.position;
-    let item = state.get_item_from_position(&path, pos)?;
+    let Some(item) = state.get_item_from_position(&path, pos) else {
+        if state.get_parser(&path) == Some(Parser::Js) {
+            return js::get_source_map_location(state, &path, pos).map(|l| vec![l]);
+        }
+        return None;
+    };
     Some(match item {
         M2Item::ModComponent(mod_name, file_path, mod_path) => {
             component::mod_location(state, mod_name, &file_path, mod_path, &path)
@@ -35,15 +49,89 @@ pub fn get_location_from_params(
         M2Item::FrontPhtml(mod_name, template) => phtml::find_front(state, &mod_name, &template),
         M2Item::BasePhtml(mod_name, template) => phtml::find_base(state, &mod_name, &template),
         M2Item::Class(class) => vec![php::find_class(state, &class)?],
+        M2Item::ClassOrVirtualType(name, area) => php::find_class(state, &name).map_or_else(
+            || state.get_di_virtual_type_locations(&area, &name),
+            |l| vec![l],
+        ),
         M2Item::Method(class, method) => vec![php::find_method(state, &class, &method)?],
+        M2Item::InjectedMethod(declared_type, method) => {
+            vec![php::find_injected_method(
+                state,
+                &declared_type,
+                &method,
+                &path.get_area(),
+            )?]
+        }
         M2Item::Const(class, constant) => vec![php::find_const(state, &class, &constant)?],
+        M2Item::ConfigPath(config_path) => state.get_system_config_fields(&config_path),
+        M2Item::Module(mod_name) => module::find_module_files(&state.get_module_path(&mod_name)?),
+        M2Item::WebAsset(mod_name, file_path, area) => {
+            asset::find_web_asset(state, mod_name.as_deref(), &file_path, &area)
+        }
+        M2Item::DeployConfig(config_path) => deploy_config::find_config(state, &config_path),
+        M2Item::LayoutName(element, name, area) => {
+            state.get_layout_name_locations(&area, element, &name)
+        }
+        M2Item::UiDataSource(component, name) => {
+            state.get_ui_data_sources(&component, &name).to_vec()
+        }
+        M2Item::UiComponent(mod_name, name, area) => {
+            ui_component::find_ui_component(state, mod_name.as_deref(), &name, &area)
+        }
+        M2Item::DocType(class) => php::find_class_with_preference(state, &class, &path.get_area()),
+        M2Item::DataFixture(mod_name, file_path) => {
+            php::find_data_fixture(state, mod_name.as_deref(), &file_path, &path)
+        }
+        M2Item::TsSymbol(symbol, source, dir) => {
+            typescript::find_symbol(state, &symbol, &source, &dir)
+        }
+        M2Item::LessImport(import, file_path) => less::find_import(state, &import, &file_path),
+        M2Item::LessMagentoImport(import, file_path) => {
+            less::find_magento_import(state, &import, &file_path)
+        }
+        M2Item::LessSymbol(symbol, file_path) => less::find_symbol(state, &symbol, &file_path),
     })
 }
 
+/// Interface methods the method under the cursor implements (or declared type of injected
+/// dependency declares), definition is used when there is no interface.
+pub fn get_declaration_from_params(
+    state: &State,
+    params: &GotoDefinitionParams,
+) -> Option<Vec<Location>> {
+    let path = params
+        .text_document_position_params
+        .text_document
+        .uri
+        .to_path_buf()
+        .ok()?;
+    let pos = params.text_document_position_params.position;
+    let declarations = match state.get_item_from_position(&path, pos) {
+        Some(M2Item::Method(class, method) | M2Item::InjectedMethod(class, method)) => {
+            php::find_interface_methods(state, &class, &method)
+        }
+        _ => vec![],
+    };
+    if declarations.is_empty() {
+        get_location_from_params(state, params)
+    } else {
+        Some(declarations)
+    }
+}
+
+pub fn get_module_locations_from_params(
+    state: &State,
+    params: &TextDocumentIdentifier,
+) -> Option<Vec<Location>> {
+    let path = params.uri.to_path_buf().ok()?;
+    let (_, mod_path) = state.module_for_path(&path)?;
+    Some(module::find_module_files(&mod_path))
+}
+
 fn path_to_location(path: &Path) -> Option<Location> {
     if path.is_file() {
         Some(Location {
-            uri: Url::from_file_path(path).expect("Should be valid Url"),
+            uri: m2::path_to_uri(path).ok()?,
             range: Range::default(),
         })
     } else {
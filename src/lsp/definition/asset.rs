@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use lsp_types::Location;
+
+use crate::{
+    m2::{M2Area, M2Path},
+    state::State,
+};
+
+use super::path_to_location;
+
+pub fn find_web_asset(
+    state: &State,
+    mod_name: Option<&str>,
+    file_path: &str,
+    area: &M2Area,
+) -> Vec<Location> {
+    let mut candidates = vec![];
+    match mod_name {
+        Some(mod_name) => {
+            if let Some(mod_path) = state.get_module_path(mod_name) {
+                for area_path in area.path_candidates() {
+                    candidates.push(mod_path.append(&["view", area_path, "web", file_path]));
+                }
+            }
+            for theme_path in state.list_themes_paths(area) {
+                candidates.push(theme_path.append(&[mod_name, "web", file_path]));
+            }
+        }
+        None => {
+            for theme_path in state.list_themes_paths(area) {
+                candidates.push(theme_path.append(&["web", file_path]));
+            }
+            for workspace_path in state.workspace_paths() {
+                candidates.push(workspace_path.append(&["lib", "web", file_path]));
+            }
+        }
+    }
+
+    let mut result = vec![];
+    for path in candidates {
+        if let Some(location) = path_to_location(&path).or_else(|| less_source(&path)) {
+            result.push(location);
+        }
+    }
+    result
+}
+
+/// Css files are usually compiled from less source with the same name.
+fn less_source(path: &PathBuf) -> Option<Location> {
+    if path.get_ext() == "css" {
+        path_to_location(&path.with_extension("less"))
+    } else {
+        None
+    }
+}
@@ -13,8 +13,8 @@ pub fn find_plain(state: &State, comp: &str) -> Vec<Location> {
     let mut result = vec![];
     let workspace_paths = state.workspace_paths();
     for path in workspace_paths {
-        let path = path.append(&["lib", "web", comp]).append_ext("js");
-        if let Some(location) = path_to_location(&path) {
+        let path = path.append(&["lib", "web", comp]);
+        if let Some(location) = path_to_location(&js_file_path(&path)) {
             result.push(location);
         }
     }
@@ -45,12 +45,19 @@ pub fn mod_location(
     components.extend(state.get_component_mixins_for_area(mod_name + "/" + file_path, &area));
 
     for component in components {
-        if let M2Item::ModComponent(_, file_path, mod_path) = component {
+        if let M2Item::ModComponent(mod_name, file_path, mod_path) = component {
+            let mut candidates = vec![];
             for area_path in area.path_candidates() {
-                let comp_path = mod_path
-                    .append(&["view", area_path, "web", &file_path])
-                    .append_ext("js");
-                if let Some(location) = path_to_location(&comp_path) {
+                candidates.push(mod_path.append(&["view", area_path, "web", &file_path]));
+            }
+            // themes override module files in `<theme>/Vendor_Module/web/`
+            let mut theme_paths = state.list_themes_paths(&area);
+            theme_paths.sort();
+            for theme_path in theme_paths {
+                candidates.push(theme_path.append(&[&mod_name, "web", &file_path]));
+            }
+            for comp_path in candidates {
+                if let Some(location) = path_to_location(&js_file_path(&comp_path)) {
                     result.push(location);
                 }
             }
@@ -60,11 +67,20 @@ pub fn mod_location(
     result
 }
 
+/// Components are usually referenced without extension, but `.js` is allowed.
+fn js_file_path(path: &PathBuf) -> PathBuf {
+    if path.get_ext() == "js" {
+        path.clone()
+    } else {
+        path.append_ext("js")
+    }
+}
+
 pub fn mod_html_location(file_path: &str, mod_path: PathBuf, path: &PathBuf) -> Vec<Location> {
     let mut result = vec![];
     let area = path.get_area();
     for area_path in area.path_candidates() {
-        let comp_path = mod_path.append(&["view", area_path, "web", &file_path]);
+        let comp_path = mod_path.append(&["view", area_path, "web", file_path]);
         if let Some(location) = path_to_location(&comp_path) {
             result.push(location);
         }
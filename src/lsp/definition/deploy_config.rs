@@ -0,0 +1,28 @@
+use lsp_types::Location;
+
+use crate::{
+    m2::{self, M2Path},
+    php,
+    state::State,
+};
+
+/// Deployment config is merged from `app/etc/config.php` and `app/etc/env.php`.
+const DEPLOY_CONFIG_FILES: [&str; 2] = ["env.php", "config.php"];
+
+pub fn find_config(state: &State, config_path: &str) -> Vec<Location> {
+    let keys = config_path.split('/').collect::<Vec<_>>();
+    let mut result = vec![];
+    for workspace_path in state.workspace_paths() {
+        for file_name in DEPLOY_CONFIG_FILES {
+            let file_path = workspace_path.append(&["app", "etc", file_name]);
+            let Ok(content) = std::fs::read_to_string(&file_path) else {
+                continue;
+            };
+            let range = php::find_config_key_range(&content, &keys);
+            if let (Some(range), Ok(uri)) = (range, m2::path_to_uri(&file_path)) {
+                result.push(Location { uri, range });
+            }
+        }
+    }
+    result
+}
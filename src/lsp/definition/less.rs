@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::Location;
+
+use crate::{
+    less::{self, LessSymbol},
+    m2::{self, M2Area, M2Path},
+    state::State,
+};
+
+use super::path_to_location;
+
+/// How many levels of `@import` statements are followed when looking for declarations.
+const IMPORT_DEPTH: usize = 10;
+
+pub fn find_import(state: &State, import: &str, file_path: &Path) -> Vec<Location> {
+    resolve_import(state, import, file_path)
+        .and_then(|path| path_to_location(&path))
+        .into_iter()
+        .collect()
+}
+
+/// Files imported by `//@magento_import` directive, from all modules.
+pub fn find_magento_import(state: &State, import: &str, file_path: &Path) -> Vec<Location> {
+    resolve_magento_import(state, import, file_path)
+        .iter()
+        .filter_map(|path| path_to_location(path))
+        .collect()
+}
+
+/// Declarations of the file, or of the first imported file declaring the symbol, imports
+/// are followed depth first in the order of `@import` statements, then `//@magento_import`
+/// directives.
+pub fn find_symbol(state: &State, symbol: &LessSymbol, file_path: &Path) -> Vec<Location> {
+    find_in_file(state, symbol, file_path, IMPORT_DEPTH, &mut vec![])
+}
+
+fn find_in_file(
+    state: &State,
+    symbol: &LessSymbol,
+    file_path: &Path,
+    depth: usize,
+    visited: &mut Vec<PathBuf>,
+) -> Vec<Location> {
+    let file_path = file_path.to_path_buf();
+    if visited.contains(&file_path) {
+        return vec![];
+    }
+    visited.push(file_path.clone());
+    let Some(content) = state
+        .get_file(&file_path)
+        .cloned()
+        .or_else(|| std::fs::read_to_string(&file_path).ok())
+    else {
+        return vec![];
+    };
+    let Ok(uri) = m2::path_to_uri(&file_path) else {
+        return vec![];
+    };
+    let declarations = less::find_declarations(&content, symbol)
+        .into_iter()
+        .map(|range| Location {
+            uri: uri.clone(),
+            range,
+        })
+        .collect::<Vec<_>>();
+    if !declarations.is_empty() || depth == 0 {
+        return declarations;
+    }
+    for import in less::get_imports(&content) {
+        let Some(import_path) = resolve_import(state, &import, &file_path) else {
+            continue;
+        };
+        let declarations = find_in_file(state, symbol, &import_path, depth - 1, visited);
+        if !declarations.is_empty() {
+            return declarations;
+        }
+    }
+    for import in less::get_magento_imports(&content) {
+        for import_path in resolve_magento_import(state, &import, &file_path) {
+            let declarations = find_in_file(state, symbol, &import_path, depth - 1, visited);
+            if !declarations.is_empty() {
+                return declarations;
+            }
+        }
+    }
+    vec![]
+}
+
+/// Imports are relative to the importing file, files of theme (or module) `web/` directory
+/// can also import files of `lib/web`, like `source/lib/_lib.less`.
+fn resolve_import(state: &State, import: &str, file_path: &Path) -> Option<PathBuf> {
+    let dir = file_path.parent()?;
+    let mut candidates = less::import_candidates(dir, import);
+    let components = dir
+        .components()
+        .map(|c| c.as_os_str().to_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    if let Some(web) = components.iter().rposition(|c| *c == "web") {
+        let web_dir = &components[web + 1..];
+        for workspace_path in state.workspace_paths() {
+            let lib_dir = workspace_path.append(&["lib", "web"]).append(web_dir);
+            candidates.extend(less::import_candidates(&lib_dir, import));
+        }
+    }
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// `//@magento_import` imports the file of `view/<area>/web/` of all modules (`base` and the
+/// area of the importing file), and of module directories of the theme, the path is relative
+/// to the same directory (under `web/`) as the importing file.
+fn resolve_magento_import(state: &State, import: &str, file_path: &Path) -> Vec<PathBuf> {
+    let Some(dir) = file_path.parent() else {
+        return vec![];
+    };
+    let components = dir
+        .components()
+        .map(|c| c.as_os_str().to_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let Some(web) = components.iter().rposition(|c| *c == "web") else {
+        return vec![];
+    };
+    let web_dir = &components[web + 1..];
+    let areas = match file_path.to_path_buf().get_area() {
+        M2Area::Frontend => vec!["base", "frontend"],
+        M2Area::Adminhtml => vec!["base", "adminhtml"],
+        M2Area::Base => vec!["base"],
+    };
+    let theme_path = state.get_theme_of_path(file_path).map(|(_, path)| path);
+    let mut dirs = vec![];
+    for module in state.get_modules() {
+        if let Some(module_path) = state.get_module_path(&module) {
+            for area in &areas {
+                dirs.push(module_path.append(&["view", area, "web"]).append(web_dir));
+            }
+        }
+        if let Some(theme_path) = theme_path {
+            dirs.push(theme_path.append(&[&module, "web"]).append(web_dir));
+        }
+    }
+    dirs.iter()
+        .flat_map(|dir| less::import_candidates(dir, import))
+        .filter(|path| path.is_file())
+        .collect()
+}
@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use lsp_types::Location;
+
+use crate::m2::M2Path;
+
+use super::path_to_location;
+
+pub fn find_module_files(mod_path: &Path) -> Vec<Location> {
+    let mod_path = mod_path.to_path_buf();
+    [
+        mod_path.append(&["registration.php"]),
+        mod_path.append(&["etc", "module.xml"]),
+    ]
+    .iter()
+    .filter_map(|path| path_to_location(path))
+    .collect()
+}
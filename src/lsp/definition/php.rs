@@ -1,30 +1,108 @@
+use std::{collections::HashSet, path::Path};
+
 use lsp_types::Location;
 
 use crate::{
+    m2::{M2Area, M2Path, M2Uri},
     php::{parse_php_file, PHPClass},
     state::State,
 };
 
+use super::path_to_location;
+
 pub fn find_class(state: &State, class: &str) -> Option<Location> {
-    let phpclass = get_php_class_from_class_name(state, class)?;
-    Some(Location {
-        uri: phpclass.uri.clone(),
-        range: phpclass.range,
-    })
+    let class = resolve_class_alias(state, class);
+    match get_php_class_from_class_name(state, class) {
+        Some(phpclass) => Some(Location {
+            uri: phpclass.uri.clone(),
+            range: phpclass.range,
+        }),
+        // class in file not following PSR-4 path, but known to the class index
+        None => Some(state.get_php_class(class)?.location.clone()),
+    }
 }
 
 pub fn find_method(state: &State, class: &str, method: &str) -> Option<Location> {
+    let class = resolve_class_alias(state, class);
     let phpclass = get_php_class_from_class_name(state, class)?;
     Some(Location {
         uri: phpclass.uri.clone(),
         range: phpclass
             .methods
             .get(method)
+            .or_else(|| phpclass.magic_methods.get(method))
             .map_or(phpclass.range, |method| method.range),
     })
 }
 
+/// Class followed by the class configured (for the area of the file) as its preference
+/// (like for constructor injection), the preference is skipped for concrete classes.
+pub fn find_class_with_preference(state: &State, class: &str, area: &M2Area) -> Vec<Location> {
+    let mut result = vec![];
+    result.extend(find_class(state, class));
+    let is_concrete = state
+        .get_php_class(class)
+        .is_some_and(|declaration| !declaration.is_interface && !declaration.is_abstract);
+    if !is_concrete {
+        if let Some(preference) = state.resolve_di_preference(area, class) {
+            result.extend(find_class(state, &preference).filter(|l| !result.contains(l)));
+        }
+    }
+    result
+}
+
+/// Method called on injected dependency, the class configured (for the area of the file)
+/// as preference of declared type is used, when it implements the method.
+pub fn find_injected_method(
+    state: &State,
+    declared_type: &str,
+    method: &str,
+    area: &M2Area,
+) -> Option<Location> {
+    state
+        .resolve_di_preference(area, declared_type)
+        .and_then(|class| {
+            let class = resolve_class_alias(state, &class);
+            let phpclass = get_php_class_from_class_name(state, class)?;
+            let range = phpclass.methods.get(method)?.range;
+            Some(Location {
+                uri: phpclass.uri,
+                range,
+            })
+        })
+        .or_else(|| find_method(state, declared_type, method))
+}
+
+/// Methods of interfaces, implemented by the class directly or through its parents,
+/// that declare the method.
+pub fn find_interface_methods(state: &State, class: &str, method: &str) -> Vec<Location> {
+    let mut visited = HashSet::new();
+    let mut queue = vec![resolve_class_alias(state, class).to_string()];
+    let mut result = vec![];
+    while let Some(current) = queue.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        let Some(declaration) = state.get_php_class(&current) else {
+            continue;
+        };
+        if declaration.is_interface {
+            if let Some(phpclass) = get_php_class_from_class_name(state, &current) {
+                if let Some(declared) = phpclass.methods.get(method) {
+                    result.push(Location {
+                        uri: phpclass.uri.clone(),
+                        range: declared.range,
+                    });
+                }
+            }
+        }
+        queue.extend(declaration.parents.iter().cloned());
+    }
+    result
+}
+
 pub fn find_const(state: &State, class: &str, constant: &str) -> Option<Location> {
+    let class = resolve_class_alias(state, class);
     let phpclass = get_php_class_from_class_name(state, class)?;
     Some(Location {
         uri: phpclass.uri.clone(),
@@ -35,20 +113,154 @@ pub fn find_const(state: &State, class: &str, constant: &str) -> Option<Location
     })
 }
 
+/// Fixture file of module (`Vendor_Module::path`), or relative to the module of the test
+/// (as well as to the integration tests `testsuite` directory of the workspace).
+pub fn find_data_fixture(
+    state: &State,
+    mod_name: Option<&str>,
+    file_path: &str,
+    path: &Path,
+) -> Vec<Location> {
+    let mut candidates = vec![];
+    match mod_name {
+        Some(mod_name) => candidates.extend(state.get_module_path(mod_name)),
+        None => {
+            candidates.extend(state.module_for_path(path).map(|(_, mod_path)| mod_path));
+            for workspace_path in state.workspace_paths() {
+                candidates.push(workspace_path.append(&[
+                    "dev",
+                    "tests",
+                    "integration",
+                    "testsuite",
+                ]));
+            }
+        }
+    }
+    candidates
+        .into_iter()
+        .filter_map(|dir| path_to_location(&dir.append(&[file_path])))
+        .collect()
+}
+
+/// Class aliased by `class_alias()`, aliases of aliases are not followed.
+fn resolve_class_alias<'a>(state: &'a State, class: &'a str) -> &'a str {
+    state
+        .get_php_class(class)
+        .and_then(|declaration| declaration.alias_of.as_deref())
+        .unwrap_or(class)
+}
+
 fn get_php_class_from_class_name(state: &State, class: &str) -> Option<PHPClass> {
     let module_path = state.split_class_to_path_and_suffix(class);
-    match module_path {
-        None => None,
-        Some((mut file_path, suffix)) => {
+    let psr4_path = module_path
+        .map(|(mut file_path, suffix)| {
             for part in suffix {
                 file_path.push(part);
             }
             file_path.set_extension("php");
-
-            match file_path.try_exists() {
-                Ok(true) => parse_php_file(&file_path),
-                _ => None,
+            file_path
+        })
+        .filter(|file_path| file_path.try_exists().unwrap_or(false));
+    match psr4_path {
+        Some(file_path) => parse_php_file(&file_path, class),
+        // other class of the file, or file not following PSR-4 path
+        None => {
+            let file_path = state
+                .get_php_class(class)?
+                .location
+                .uri
+                .to_path_buf()
+                .ok()?;
+            if file_path.is_file() {
+                parse_php_file(&file_path, class)
+            } else {
+                None
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use lsp_types::Url;
+
+    use crate::xml::DiValue;
+
+    use super::*;
+
+    fn fixture_state() -> State {
+        let mut state = State::new();
+        state.add_module_path(
+            "Some\\Module",
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).append(&[
+                "tests", "fixtures", "project", "app", "code", "Some", "Module",
+            ]),
+        );
+        state
+    }
+
+    fn preference(class: &str) -> DiValue {
+        DiValue {
+            xsi_type: None,
+            value: class.into(),
+            location: Location {
+                uri: Url::parse("file:///a/etc/frontend/di.xml").expect("Url should be valid"),
+                range: lsp_types::Range::default(),
+            },
+        }
+    }
+
+    fn file_line(location: &Location) -> (String, u32) {
+        let path = location.uri.to_path_buf().expect("Uri should be a path");
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        (name.to_string(), location.range.start.line)
+    }
+
+    #[test]
+    fn test_find_injected_method_uses_preference_of_area() {
+        let mut state = fixture_state();
+        state.add_di_preference(
+            &M2Area::Frontend,
+            "Some\\Module\\Api\\ItemInterface",
+            preference("Some\\Module\\Model\\Item"),
+        );
+        let find = |area| {
+            find_injected_method(&state, "Some\\Module\\Api\\ItemInterface", "getName", &area)
+                .as_ref()
+                .map(file_line)
+        };
+
+        assert_eq!(find(M2Area::Frontend), Some(("Item.php".into(), 10)));
+        assert_eq!(
+            find(M2Area::Adminhtml),
+            Some(("ItemInterface.php".into(), 6))
+        );
+    }
+
+    #[test]
+    fn test_find_class_with_preference_of_area() {
+        let mut state = fixture_state();
+        state.add_di_preference(
+            &M2Area::Frontend,
+            "Some\\Module\\Api\\ItemInterface",
+            preference("Some\\Module\\Model\\Item"),
+        );
+        let find = |area| {
+            find_class_with_preference(&state, "Some\\Module\\Api\\ItemInterface", &area)
+                .iter()
+                .map(file_line)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            find(M2Area::Frontend),
+            vec![("ItemInterface.php".into(), 4), ("Item.php".into(), 6)]
+        );
+        assert_eq!(find(M2Area::Base), vec![("ItemInterface.php".into(), 4)]);
+    }
+}
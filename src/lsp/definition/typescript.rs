@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::Location;
+
+use crate::{
+    m2,
+    state::State,
+    typescript::{self, TsExport},
+};
+
+use super::path_to_location;
+
+pub fn find_symbol(state: &State, symbol: &str, source: &str, dir: &Path) -> Vec<Location> {
+    let Some(file) = typescript::resolve_module(dir, source) else {
+        return vec![];
+    };
+    let (declaration, last_file) = follow(symbol, &file, state.settings().reexport_depth);
+    declaration
+        .or_else(|| path_to_location(&last_file))
+        .into_iter()
+        .collect()
+}
+
+/// Follow re-exports of `symbol` starting at `file`, returns declaration location if found
+/// and the last file that could be resolved.
+fn follow(symbol: &str, file: &Path, depth: usize) -> (Option<Location>, PathBuf) {
+    let last_file = file.to_path_buf();
+    if symbol == "*" {
+        return (path_to_location(file), last_file);
+    }
+    let Ok(content) = std::fs::read_to_string(file) else {
+        return (None, last_file);
+    };
+    if let Some(location) = declaration_location(&content, symbol, file) {
+        return (Some(location), last_file);
+    }
+
+    let mut dir = file.to_path_buf();
+    dir.pop();
+    let exports = typescript::get_exports(&content);
+
+    for export in &exports {
+        if let TsExport::Local { name, alias } = export {
+            if alias == symbol {
+                if let Some(location) = declaration_location(&content, name, file) {
+                    return (Some(location), last_file);
+                }
+            }
+        }
+    }
+
+    if depth == 0 {
+        return (None, last_file);
+    }
+
+    for export in &exports {
+        if let TsExport::Named {
+            name,
+            alias,
+            source,
+        } = export
+        {
+            if alias == symbol {
+                if let Some(next_file) = typescript::resolve_module(&dir, source) {
+                    return follow(name, &next_file, depth - 1);
+                }
+            }
+        }
+    }
+
+    for export in &exports {
+        if let TsExport::All { source } = export {
+            if let Some(next_file) = typescript::resolve_module(&dir, source) {
+                if let (Some(location), found_in) = follow(symbol, &next_file, depth - 1) {
+                    return (Some(location), found_in);
+                }
+            }
+        }
+    }
+
+    (None, last_file)
+}
+
+fn declaration_location(content: &str, symbol: &str, file: &Path) -> Option<Location> {
+    let range = typescript::find_declaration(content, symbol)?;
+    Some(Location {
+        uri: m2::path_to_uri(file).ok()?,
+        range,
+    })
+}
@@ -0,0 +1,59 @@
+use lsp_types::Location;
+
+use crate::{
+    m2::{M2Area, M2Path},
+    state::State,
+};
+
+use super::path_to_location;
+
+/// All `ui_component/<name>.xml` files of the component, modules can extend components
+/// of other modules and themes can override them, so every file is returned.
+pub fn find_ui_component(
+    state: &State,
+    mod_name: Option<&str>,
+    name: &str,
+    area: &M2Area,
+) -> Vec<Location> {
+    let file_name = format!("{name}.xml");
+    let mut candidates = vec![];
+    let mut result = vec![];
+    match mod_name {
+        Some(mod_name) => {
+            if let Some(mod_path) = state.get_module_path(mod_name) {
+                for area_path in area.path_candidates() {
+                    candidates.push(mod_path.append(&[
+                        "view",
+                        area_path,
+                        "ui_component",
+                        &file_name,
+                    ]));
+                }
+            }
+            for theme_path in state.list_themes_paths(area) {
+                candidates.push(theme_path.append(&[mod_name, "ui_component", &file_name]));
+            }
+        }
+        None => {
+            result.extend(state.get_ui_component_files(name).iter().cloned());
+            for theme_path in state.list_themes_paths(area) {
+                let pattern = theme_path.append(&["*", "ui_component", &file_name]);
+                candidates.extend(
+                    glob::glob(pattern.to_path_str())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Result::ok),
+                );
+            }
+        }
+    }
+
+    for path in candidates {
+        if let Some(location) = path_to_location(&path) {
+            if !result.contains(&location) {
+                result.push(location);
+            }
+        }
+    }
+    result
+}
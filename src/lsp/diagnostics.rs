@@ -0,0 +1,104 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::{
+    m2::{M2LayoutElement, M2Uri},
+    state::State,
+    xml,
+};
+
+pub fn get_diagnostics_for_file(state: &State, path: &PathBuf) -> Vec<Diagnostic> {
+    xml::get_orphan_layout_references(state, path)
+        .into_iter()
+        .map(|(element, name, range)| {
+            let element = match element {
+                M2LayoutElement::Block => "Block",
+                M2LayoutElement::Container => "Container",
+            };
+            Diagnostic {
+                range,
+                // could be defined in a theme or module that is not indexed
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("magento2-ls".into()),
+                message: format!("{element} `{name}` is not defined in any indexed layout"),
+                ..Diagnostic::default()
+            }
+        })
+        .collect()
+}
+
+/// Preferences for interfaces (or classes) that are not used in any indexed PHP file or
+/// `di.xml` argument, grouped by `di.xml` file declaring them.
+pub fn get_unused_preference_diagnostics(state: &State) -> HashMap<PathBuf, Vec<Diagnostic>> {
+    let allowlist = &state.settings().preference_allowlist;
+    let mut result: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+    for (class, preference) in state.list_di_preferences() {
+        let allowed = allowlist.iter().any(|allowed| {
+            allowed.trim_start_matches('\\') == class
+                || (allowed.ends_with('\\') && class.starts_with(allowed.trim_start_matches('\\')))
+        });
+        if allowed || state.count_class_usages(class) > 0 {
+            continue;
+        }
+        let Ok(path) = preference.location.uri.to_path_buf() else {
+            continue;
+        };
+        result.entry(path).or_default().push(Diagnostic {
+            range: preference.location.range,
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            source: Some("magento2-ls".into()),
+            message: format!("Preference for `{class}` is not used in any indexed file"),
+            ..Diagnostic::default()
+        });
+    }
+    for diagnostics in result.values_mut() {
+        diagnostics.sort_by_key(|d| d.range.start);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::settings::Settings;
+
+    #[test]
+    fn test_unused_preferences_with_allowlist() {
+        let mut state = State::new();
+        state.set_settings(Settings {
+            preference_allowlist: vec!["Other\\Module\\Api\\".into(), "\\A\\Skipped".into()],
+            ..Settings::default()
+        });
+        state.set_file(
+            &PathBuf::from("/a/etc/di.xml"),
+            r#"<config>
+    <preference for="A\UsedInterface" type="A\Used"/>
+    <preference for="A\InjectedInterface" type="A\Injected"/>
+    <preference for="A\UnusedInterface" type="A\Unused"/>
+    <preference for="A\Skipped" type="A\Unused"/>
+    <preference for="Other\Module\Api\FooInterface" type="A\Unused"/>
+    <type name="A\Bar">
+        <arguments>
+            <argument name="injected" xsi:type="object">A\InjectedInterface</argument>
+        </arguments>
+    </type>
+</config>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/Foo.php"),
+            "<?php\nnamespace A;\n\nclass Foo\n{\n    public function __construct(UsedInterface $used) {}\n}\n",
+        );
+
+        let report = get_unused_preference_diagnostics(&state);
+        let messages = report
+            .values()
+            .flatten()
+            .map(|d| d.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec!["Preference for `A\\UnusedInterface` is not used in any indexed file"]
+        );
+    }
+}
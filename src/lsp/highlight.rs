@@ -0,0 +1,32 @@
+use lsp_types::{DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams};
+
+use crate::{m2::M2Uri, state::State, xml};
+
+/// Occurrences of XML identifier under the cursor in the same document, declarations are
+/// highlighted as writes, references as reads.
+pub fn get_highlights_from_params(
+    state: &State,
+    params: &DocumentHighlightParams,
+) -> Option<Vec<DocumentHighlight>> {
+    let path = params
+        .text_document_position_params
+        .text_document
+        .uri
+        .to_path_buf()
+        .ok()?;
+    let pos = params.text_document_position_params.position;
+    let occurrences = xml::get_occurrences_from_position(state, &path, pos)?;
+    Some(
+        occurrences
+            .into_iter()
+            .map(|occurrence| DocumentHighlight {
+                range: occurrence.range,
+                kind: Some(if occurrence.declaration {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::READ
+                }),
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,413 @@
+use std::path::Path;
+
+use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+
+use crate::{
+    m2::{M2Area, M2Item, M2Path, M2Uri},
+    php::{self, PHPConstructorParam},
+    state::State,
+    xml::{self, ViewImage},
+};
+
+/// Pool items shown in the hover, the rest is only counted.
+const MAX_POOL_ITEMS: usize = 20;
+
+/// Areas in the order DI configuration is shown, global one first.
+const DI_AREAS: [(M2Area, &str); 3] = [
+    (M2Area::Base, "global"),
+    (M2Area::Frontend, "frontend"),
+    (M2Area::Adminhtml, "adminhtml"),
+];
+
+pub fn get_hover_from_params(state: &State, params: &HoverParams) -> Option<Hover> {
+    let path = params
+        .text_document_position_params
+        .text_document
+        .uri
+        .to_path_buf()
+        .ok()?;
+    let pos = params.text_document_position_params.position;
+    let value = if let Some(type_name) = xml::get_plugin_type_from_position(state, &path, pos) {
+        plugin_hover(state, &type_name)?
+    } else if let Some(value) = xml::get_pool_type_from_position(state, &path, pos)
+        .and_then(|type_name| pool_hover(state, &path, &type_name))
+    {
+        value
+    } else if let Some(class) = xml::get_console_command_from_position(state, &path, pos) {
+        console_command_hover(state, &path, &class)
+    } else if let Some(value) = xml::get_view_image_from_position(state, &path, pos)
+        .and_then(|(module, id)| view_image_hover(state, &path, &module, &id))
+    {
+        value
+    } else if let Some(param) = php::get_constructor_param_from_position(state, &path, pos) {
+        constructor_param_hover(state, &param)
+    } else if let Some(value) = php::get_declared_class_from_position(state, &path, pos)
+        .and_then(|class| observer_hover(state, &class))
+    {
+        value
+    } else {
+        match state.get_item_from_position(&path, pos)? {
+            M2Item::DeployConfig(config_path) => deploy_config_hover(&config_path),
+            _ => return None,
+        }
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: None,
+    })
+}
+
+fn plugin_hover(state: &State, type_name: &str) -> Option<String> {
+    let implementors = state.get_php_implementors(type_name);
+    if implementors.is_empty() {
+        return None;
+    }
+    let kind = if state
+        .get_php_class(type_name)
+        .is_some_and(|c| c.is_interface)
+    {
+        "implementing"
+    } else {
+        "extending"
+    };
+    let list = implementors
+        .iter()
+        .map(|c| format!("- `{}`", c.fqn))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "Plugin on `{type_name}` applies to all {kind} classes:\n\n{list}"
+    ))
+}
+
+/// Pool items merged from all indexed `di.xml` files of the file area and global ones.
+fn pool_hover(state: &State, path: &Path, type_name: &str) -> Option<String> {
+    let area = xml::di_area(path).unwrap_or(M2Area::Base);
+    let items = state.get_di_pool_items(&area, type_name);
+    if items.is_empty() {
+        return None;
+    }
+    let mut list = items
+        .iter()
+        .take(MAX_POOL_ITEMS)
+        .map(
+            |item| match state.resolve_di_virtual_type(&area, &item.class) {
+                Some(class) => format!(
+                    "- `{}`: `{}` (virtual type of `{class}`)",
+                    item.key, item.class
+                ),
+                None => format!("- `{}`: `{}`", item.key, item.class),
+            },
+        )
+        .collect::<Vec<_>>();
+    if items.len() > MAX_POOL_ITEMS {
+        list.push(format!("- … and {} more", items.len() - MAX_POOL_ITEMS));
+    }
+    Some(format!(
+        "Objects in `{type_name}` pool:\n\n{}",
+        list.join("\n")
+    ))
+}
+
+/// Console command name read from the command class, virtual types are resolved first.
+fn console_command_hover(state: &State, path: &Path, class: &str) -> String {
+    let area = xml::di_area(path).unwrap_or(M2Area::Base);
+    let command_class = state
+        .resolve_di_virtual_type(&area, class)
+        .unwrap_or_else(|| class.to_string());
+    let name = state
+        .get_php_class(&command_class)
+        .and_then(|c| c.location.uri.to_path_buf().ok())
+        .and_then(|class_path| match state.get_file(&class_path) {
+            Some(content) => php::get_console_command_name(content, &command_class),
+            None => std::fs::read_to_string(&class_path)
+                .ok()
+                .and_then(|content| php::get_console_command_name(&content, &command_class)),
+        });
+    match name {
+        Some(name) => format!("Console command `{name}`\n\nDeclared by `{class}`"),
+        None => format!("Console command declared by `{class}`"),
+    }
+}
+
+/// Image configuration merged the way Magento does it, module `view.xml` files first and
+/// then themes, from the top most parent to the theme of the file.
+fn view_image_hover(state: &State, path: &Path, module: &str, id: &str) -> Option<String> {
+    let images = state.get_view_images(module, id);
+    let image_theme = |image: &ViewImage| {
+        let image_path = image.location.uri.to_path_buf().ok()?;
+        state
+            .get_theme_of_path(&image_path)
+            .map(|(name, _)| name.clone())
+    };
+    let mut sources = images
+        .iter()
+        .filter(|image| image_theme(image).is_none())
+        .map(|image| (None, image))
+        .collect::<Vec<_>>();
+    for theme in theme_chain(state, path).into_iter().rev() {
+        sources.extend(
+            images
+                .iter()
+                .filter(|image| image_theme(image).as_ref() == Some(&theme))
+                .map(|image| (Some(theme.clone()), image)),
+        );
+    }
+    if sources.is_empty() {
+        return None;
+    }
+
+    let mut values: Vec<(&str, &str, Option<String>)> = vec![];
+    for (theme, image) in sources {
+        for (key, value) in &image.values {
+            match values.iter_mut().find(|(k, _, _)| k == key) {
+                Some(existing) => *existing = (key, value, theme.clone()),
+                None => values.push((key, value, theme.clone())),
+            }
+        }
+    }
+    let current_theme = state.get_theme_of_path(path).map(|(name, _)| name.clone());
+    let list = values
+        .into_iter()
+        .map(|(key, value, theme)| match theme {
+            _ if theme == current_theme => format!("- `{key}`: `{value}`"),
+            Some(theme) => format!("- `{key}`: `{value}` (from `{theme}`)"),
+            None => format!("- `{key}`: `{value}` (from module `view.xml`)"),
+        })
+        .collect::<Vec<_>>();
+    Some(format!(
+        "Image `{id}` of `{module}`:\n\n{}",
+        list.join("\n")
+    ))
+}
+
+/// Events observed by the class, with `events.xml` files declaring the observers.
+fn observer_hover(state: &State, class: &str) -> Option<String> {
+    let events = state.get_observer_events(class);
+    if events.is_empty() {
+        return None;
+    }
+    let list =
+        events
+            .into_iter()
+            .map(|(event, location)| {
+                let source = location.uri.to_path_buf().ok().map(|path| {
+                    match state.module_for_path(&path) {
+                        Some((module, module_path)) => format!(
+                            "`{module}` `{}`",
+                            path.relative_to(module_path).str_components().join("/")
+                        ),
+                        None => format!("`{}`", path.display()),
+                    }
+                });
+                match source {
+                    Some(source) => format!("- `{event}` ({source})"),
+                    None => format!("- `{event}`"),
+                }
+            })
+            .collect::<Vec<_>>();
+    Some(format!("Observer of events:\n\n{}", list.join("\n")))
+}
+
+/// Theme of the file followed by its parents (from `theme.xml`), themes without
+/// `theme.xml` end the chain.
+fn theme_chain(state: &State, path: &Path) -> Vec<String> {
+    let mut chain: Vec<String> = vec![];
+    let mut theme = state.get_theme_of_path(path);
+    while let Some((name, theme_path)) = theme {
+        if chain.contains(name) {
+            break;
+        }
+        chain.push(name.clone());
+        let area = name.split('/').next().unwrap_or_default();
+        theme = xml::get_theme_parent(theme_path)
+            .and_then(|parent| state.get_theme(&format!("{area}/{parent}")));
+    }
+    chain
+}
+
+fn constructor_param_hover(state: &State, param: &PHPConstructorParam) -> String {
+    let name = &param.name;
+    let mut lines = vec![match &param.declared_type {
+        Some(declared_type) => format!("`${name}`: `{declared_type}`"),
+        None => format!("`${name}`"),
+    }];
+
+    if let Some(declared_type) = &param.declared_type {
+        let global = state.resolve_di_preference(&M2Area::Base, declared_type);
+        for (area, label) in &DI_AREAS {
+            let Some(concrete) = state.resolve_di_preference(area, declared_type) else {
+                continue;
+            };
+            if *area == M2Area::Base || Some(&concrete) != global.as_ref() {
+                lines.push(format!("Injected as `{concrete}` ({label} preference)"));
+            }
+        }
+    }
+
+    for (area, label) in &DI_AREAS {
+        if let Some(argument) = state.get_di_arguments(area, &param.class, name).last() {
+            let xsi_type = argument.xsi_type.as_deref().unwrap_or("string");
+            lines.push(if argument.value.is_empty() {
+                format!("Argument set in {label} `di.xml` as `{xsi_type}`")
+            } else {
+                format!(
+                    "Argument set in {label} `di.xml` to `{}` (`{xsi_type}`)",
+                    argument.value
+                )
+            });
+        }
+    }
+
+    lines.join("\n\n")
+}
+
+fn deploy_config_hover(config_path: &str) -> String {
+    let keys = config_path
+        .split('/')
+        .map(|key| format!("['{key}']"))
+        .collect::<String>();
+    format!(
+        "Deployment config value `{config_path}`\n\nRead from `app/etc/env.php` (or `app/etc/config.php`) as `{keys}`"
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{Location, Range, Url};
+
+    use super::*;
+    use crate::xml::DiValue;
+
+    fn di_value(xsi_type: Option<&str>, value: &str) -> DiValue {
+        DiValue {
+            xsi_type: xsi_type.map(Into::into),
+            value: value.into(),
+            location: Location {
+                uri: Url::parse("file:///a/etc/di.xml").expect("Should be valid Url"),
+                range: Range::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_constructor_param_hover() {
+        let mut state = State::new();
+        state.add_di_preference(&M2Area::Base, "A\\FooInterface", di_value(None, "A\\Foo"));
+        state.add_di_preference(&M2Area::Base, "A\\Foo", di_value(None, "A\\BetterFoo"));
+        state.add_di_preference(&M2Area::Frontend, "A\\Foo", di_value(None, "A\\FrontFoo"));
+        state.add_di_preference(&M2Area::Adminhtml, "A\\Foo", di_value(None, "A\\BetterFoo"));
+        state.add_di_argument(
+            &M2Area::Base,
+            "A\\Bar",
+            "foo",
+            di_value(Some("object"), "A\\CustomFoo"),
+        );
+        let param = PHPConstructorParam {
+            class: "A\\Bar".into(),
+            name: "foo".into(),
+            declared_type: Some("A\\FooInterface".into()),
+        };
+        assert_eq!(
+            constructor_param_hover(&state, &param),
+            "`$foo`: `A\\FooInterface`\n\n\
+            Injected as `A\\BetterFoo` (global preference)\n\n\
+            Injected as `A\\FrontFoo` (frontend preference)\n\n\
+            Argument set in global `di.xml` to `A\\CustomFoo` (`object`)"
+        );
+    }
+
+    #[test]
+    fn test_constructor_param_hover_without_di_config() {
+        let param = PHPConstructorParam {
+            class: "A\\Bar".into(),
+            name: "count".into(),
+            declared_type: Some("int".into()),
+        };
+        assert_eq!(
+            constructor_param_hover(&State::new(), &param),
+            "`$count`: `int`"
+        );
+    }
+
+    #[test]
+    fn test_pool_hover() {
+        let mut state = State::new();
+        state.add_di_virtual_type(
+            &M2Area::Base,
+            "someStockModifier",
+            di_value(None, "A\\StockModifier"),
+        );
+        let object = |class| di_value(Some("object"), class);
+        state.add_di_argument(
+            &M2Area::Base,
+            "A\\Pool",
+            "modifiers/price",
+            object("A\\PriceModifier"),
+        );
+        state.add_di_argument(
+            &M2Area::Base,
+            "A\\Pool",
+            "modifiers/stock",
+            object("A\\StockModifier"),
+        );
+        state.add_di_argument(
+            &M2Area::Frontend,
+            "A\\Pool",
+            "modifiers/stock",
+            object("someStockModifier"),
+        );
+        state.add_di_argument(
+            &M2Area::Adminhtml,
+            "A\\Pool",
+            "modifiers/admin",
+            object("A\\AdminModifier"),
+        );
+        let path = Path::new("/a/etc/frontend/di.xml");
+        assert_eq!(
+            pool_hover(&state, path, "A\\Pool"),
+            Some(
+                "Objects in `A\\Pool` pool:\n\n\
+                - `modifiers/price`: `A\\PriceModifier`\n\
+                - `modifiers/stock`: `someStockModifier` (virtual type of `A\\StockModifier`)"
+                    .into()
+            )
+        );
+        assert_eq!(pool_hover(&state, path, "A\\Other"), None);
+
+        for i in 0..MAX_POOL_ITEMS + 3 {
+            state.add_di_argument(
+                &M2Area::Base,
+                "A\\Big",
+                format!("modifiers/m{i}").as_str(),
+                object("A\\PriceModifier"),
+            );
+        }
+        let hover = pool_hover(&state, path, "A\\Big").expect("Should have hover");
+        assert_eq!(
+            hover.lines().filter(|l| l.starts_with("- ")).count(),
+            MAX_POOL_ITEMS + 1
+        );
+        assert!(hover.ends_with("- … and 3 more"));
+    }
+
+    #[test]
+    fn test_console_command_hover_without_class_file() {
+        let path = Path::new("/a/etc/di.xml");
+        assert_eq!(
+            console_command_hover(&State::new(), path, "A\\Console\\ItemList"),
+            "Console command declared by `A\\Console\\ItemList`"
+        );
+    }
+
+    #[test]
+    fn test_deploy_config_hover() {
+        assert_eq!(
+            deploy_config_hover("cache/frontend/id_prefix"),
+            "Deployment config value `cache/frontend/id_prefix`\n\nRead from `app/etc/env.php` (or `app/etc/config.php`) as `['cache']['frontend']['id_prefix']`"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use lsp_types::{request::GotoImplementationParams, Location};
+
+use crate::{
+    m2::{M2Item, M2Uri},
+    php,
+    state::State,
+};
+
+pub fn get_implementations_from_params(
+    state: &State,
+    params: &GotoImplementationParams,
+) -> Option<Vec<Location>> {
+    let path = params
+        .text_document_position_params
+        .text_document
+        .uri
+        .to_path_buf()
+        .ok()?;
+    let pos = params.text_document_position_params.position;
+    let class = match php::get_declared_class_from_position(state, &path, pos) {
+        Some(class) => class,
+        None => match state.get_item_from_position(&path, pos)? {
+            M2Item::Class(class) | M2Item::ClassOrVirtualType(class, _) => class,
+            _ => return None,
+        },
+    };
+    Some(implementations(state, &class))
+}
+
+/// Concrete classes implementing the interface or extending the class, also through
+/// their parents (like interface implemented by parent class), interfaces extending
+/// the interface and traits are not implementations.
+fn implementations(state: &State, class: &str) -> Vec<Location> {
+    state
+        .get_php_implementors(class)
+        .into_iter()
+        .filter(|c| !c.is_interface && !c.is_trait && !c.is_abstract)
+        .map(|c| c.location.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_implementations() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/Model/Items.php"),
+            r#"<?php
+namespace A;
+
+interface ItemInterface {}
+interface SubInterface extends ItemInterface {}
+abstract class AbstractItem implements ItemInterface {}
+class Item extends AbstractItem {}
+class SpecialItem extends Item {}
+class SubItem implements SubInterface {}
+"#,
+        );
+        let lines = |class| {
+            implementations(&state, class)
+                .into_iter()
+                .map(|l| l.range.start.line)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines("A\\ItemInterface"), vec![6, 7, 8]);
+        assert_eq!(lines("A\\SubInterface"), vec![8]);
+        assert_eq!(lines("A\\AbstractItem"), vec![6, 7]);
+        assert_eq!(lines("A\\SpecialItem"), Vec::<u32>::new());
+    }
+}
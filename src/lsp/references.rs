@@ -0,0 +1,73 @@
+use lsp_types::{Location, ReferenceParams};
+
+use crate::{
+    m2::{M2Area, M2Uri},
+    php,
+    state::State,
+    xml,
+};
+
+pub fn get_references_from_params(
+    state: &State,
+    params: &ReferenceParams,
+) -> Option<Vec<Location>> {
+    let path = params
+        .text_document_position
+        .text_document
+        .uri
+        .to_path_buf()
+        .ok()?;
+    let pos = params.text_document_position.position;
+    let include_declaration = params.context.include_declaration;
+    if let Some(type_name) = xml::get_plugin_type_from_position(state, &path, pos) {
+        return Some(plugin_references(state, &type_name, include_declaration));
+    }
+    let class = php::get_declared_class_from_position(state, &path, pos)?;
+    let mut references = block_class_references(state, &class, include_declaration);
+    references.extend(
+        state
+            .get_observer_events(&class)
+            .into_iter()
+            .map(|(_, location)| location),
+    );
+    Some(references)
+}
+
+fn plugin_references(state: &State, type_name: &str, include_declaration: bool) -> Vec<Location> {
+    let mut result = vec![];
+    if include_declaration {
+        if let Some(declaration) = state.get_php_class(type_name) {
+            result.push(declaration.location.clone());
+        }
+    }
+    result.extend(
+        state
+            .get_php_implementors(type_name)
+            .into_iter()
+            .map(|c| c.location.clone()),
+    );
+    result
+}
+
+/// Layout blocks instantiating the class, grouped by area (frontend, adminhtml, then base).
+/// `Location` has no place for the area, so the grouping is all the client gets of it.
+fn block_class_references(state: &State, class: &str, include_declaration: bool) -> Vec<Location> {
+    let mut usages = state.get_layout_block_usages(class).to_vec();
+    usages.sort_by_key(|(area, location)| {
+        let area_order = match area {
+            M2Area::Frontend => 0,
+            M2Area::Adminhtml => 1,
+            M2Area::Base => 2,
+        };
+        (area_order, location.uri.to_string(), location.range.start)
+    });
+
+    let mut result = vec![];
+    if include_declaration {
+        if let Some(declaration) = state.get_php_class(class) {
+            result.push(declaration.location.clone());
+        }
+    }
+    result.extend(usages.into_iter().map(|(_, location)| location));
+    result
+}
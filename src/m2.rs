@@ -1,7 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use lsp_types::Url;
 
+use crate::less::LessSymbol;
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum M2Item {
@@ -10,11 +15,26 @@ pub enum M2Item {
     ModComponent(String, String, PathBuf),
     RelComponent(String, PathBuf),
     Class(String),
+    ClassOrVirtualType(String, M2Area),
     Method(String, String),
+    InjectedMethod(String, String),
     Const(String, String),
     FrontPhtml(String, String),
     AdminPhtml(String, String),
     BasePhtml(String, String),
+    ConfigPath(String),
+    TsSymbol(String, String, PathBuf),
+    LessImport(String, PathBuf),
+    LessMagentoImport(String, PathBuf),
+    LessSymbol(LessSymbol, PathBuf),
+    Module(String),
+    WebAsset(Option<String>, String, M2Area),
+    DeployConfig(String),
+    LayoutName(M2LayoutElement, String, M2Area),
+    UiDataSource(String, String),
+    UiComponent(Option<String>, String, M2Area),
+    DataFixture(Option<String>, String),
+    DocType(String),
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -42,11 +62,37 @@ impl M2Area {
     }
 }
 
+/// Layout element that can be referenced by name.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum M2LayoutElement {
+    Block,
+    Container,
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub trait M2Uri {
-    fn to_path_buf(&self) -> PathBuf;
+    fn to_path_buf(&self) -> Result<PathBuf, UriError>;
+}
+
+/// Conversion error between `file://` Url and file system path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UriError {
+    NotFile(String),
+    InvalidPath(PathBuf),
 }
 
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFile(uri) => write!(f, "Url `{uri}` is not a local file"),
+            Self::InvalidPath(path) => write!(f, "Path `{}` can not be a file Url", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for UriError {}
+
 #[allow(clippy::module_name_repetitions)]
 pub trait M2Path {
     fn has_components(&self, parts: &[&str]) -> bool;
@@ -55,9 +101,9 @@ pub trait M2Path {
     fn append(&self, parts: &[&str]) -> Self;
     fn append_ext(&self, ext: &str) -> Self;
     fn get_ext(&self) -> String;
-    fn is_frontend(&self) -> bool;
     fn is_test(&self) -> bool;
     fn get_area(&self) -> M2Area;
+    fn try_get_area(&self) -> Option<M2Area>;
     fn to_path_str(&self) -> &str;
 }
 
@@ -96,18 +142,30 @@ impl M2Path for PathBuf {
     }
 
     fn get_area(&self) -> M2Area {
+        self.try_get_area().unwrap_or(M2Area::Base)
+    }
+
+    fn try_get_area(&self) -> Option<M2Area> {
+        let components = self.str_components();
+        let is_theme = |area: &str| {
+            components
+                .iter()
+                .any(|c| c.starts_with(&format!("theme-{area}-")))
+        };
         if self.has_components(&["view", "base"]) || self.has_components(&["design", "base"]) {
-            M2Area::Base
+            Some(M2Area::Base)
         } else if self.has_components(&["view", "frontend"])
             || self.has_components(&["design", "frontend"])
+            || is_theme("frontend")
         {
-            M2Area::Frontend
+            Some(M2Area::Frontend)
         } else if self.has_components(&["view", "adminhtml"])
             || self.has_components(&["design", "adminhtml"])
+            || is_theme("adminhtml")
         {
-            M2Area::Adminhtml
+            Some(M2Area::Adminhtml)
         } else {
-            M2Area::Base
+            None
         }
     }
 
@@ -147,22 +205,42 @@ impl M2Path for PathBuf {
             .to_lowercase()
     }
 
-    fn is_frontend(&self) -> bool {
-        self.has_components(&["view", "frontend"])
-            || self.has_components(&["app", "design", "frontend"])
-    }
-
     fn is_test(&self) -> bool {
         self.has_components(&["dev", "tests"])
     }
 }
 
 impl M2Uri for Url {
-    fn to_path_buf(&self) -> PathBuf {
-        self.to_file_path().expect("Url should convert to PathBuf")
+    /// Percent-encoded drive letter (`file:///c%3A/...`, as sent by VS Code) is decoded
+    /// first, so it is recognized as Windows drive. UNC paths (`file://server/share`)
+    /// are only valid on Windows.
+    fn to_path_buf(&self) -> Result<PathBuf, UriError> {
+        if self.scheme() != "file" {
+            return Err(UriError::NotFile(self.to_string()));
+        }
+        let mut uri = self.clone();
+        let path = uri.path();
+        let drive = path
+            .get(1..2)
+            .filter(|d| d.chars().all(|c| c.is_ascii_alphabetic()));
+        if let Some(drive) = drive {
+            let rest = path.get(2..).unwrap_or_default();
+            if let Some(rest) = rest
+                .strip_prefix("%3A")
+                .or_else(|| rest.strip_prefix("%3a"))
+            {
+                uri.set_path(&format!("/{drive}:{rest}"));
+            }
+        }
+        uri.to_file_path()
+            .map_err(|()| UriError::NotFile(self.to_string()))
     }
 }
 
+pub fn path_to_uri(path: &Path) -> Result<Url, UriError> {
+    Url::from_file_path(path).map_err(|()| UriError::InvalidPath(path.to_path_buf()))
+}
+
 pub fn is_part_of_module_name(text: &str) -> bool {
     for char in text.chars() {
         if !char.is_alphanumeric() && char != '_' {
@@ -182,7 +260,9 @@ pub fn is_part_of_class_name(text: &str) -> bool {
 }
 
 pub(crate) fn try_any_item_from_str(text: &str, area: &M2Area) -> Option<M2Item> {
-    if does_ext_eq(text, "phtml") {
+    if let Some(item) = try_deployed_asset_item_from_str(text) {
+        Some(item)
+    } else if does_ext_eq(text, "phtml") {
         try_phtml_item_from_str(text, area)
     } else if text.contains("::") {
         try_const_item_from_str(text)
@@ -196,7 +276,11 @@ pub(crate) fn try_any_item_from_str(text: &str, area: &M2Area) -> Option<M2Item>
 pub(crate) fn try_const_item_from_str(text: &str) -> Option<M2Item> {
     if text.split("::").count() == 2 {
         let mut parts = text.split("::");
-        Some(M2Item::Const(parts.next()?.into(), parts.next()?.into()))
+        let class = parts.next()?;
+        match parts.next()? {
+            "class" => Some(get_class_item_from_str(class)),
+            constant => Some(M2Item::Const(class.into(), constant.into())),
+        }
     } else {
         None
     }
@@ -228,15 +312,99 @@ pub(crate) fn try_phtml_item_from_str(text: &str, area: &M2Area) -> Option<M2Ite
     }
 }
 
+/// Asset path like `Vendor_Module::images/logo.svg` or theme relative `images/logo.svg`.
+pub(crate) fn try_web_asset_item_from_str(text: &str, area: &M2Area) -> Option<M2Item> {
+    if let Some(item) = try_deployed_asset_item_from_str(text) {
+        return Some(item);
+    }
+    if text.is_empty() || text.contains("://") {
+        return None;
+    }
+    match text.split_once("::") {
+        Some((mod_name, path)) if is_part_of_module_name(mod_name) && !path.is_empty() => Some(
+            M2Item::WebAsset(Some(mod_name.into()), path.into(), area.clone()),
+        ),
+        Some(_) => None,
+        None => Some(M2Item::WebAsset(None, text.into(), area.clone())),
+    }
+}
+
+/// UI component name, `Vendor_Module::component_name` or bare `component_name`, only
+/// lowercase names (like the file names of `ui_component/` configs) are accepted.
+pub(crate) fn try_ui_component_item_from_str(text: &str, area: &M2Area) -> Option<M2Item> {
+    let (mod_name, name) = match text.split_once("::") {
+        Some((mod_name, name)) if is_part_of_module_name(mod_name) => (Some(mod_name), name),
+        Some(_) => return None,
+        None => (None, text),
+    };
+    let is_component_name = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    is_component_name
+        .then(|| M2Item::UiComponent(mod_name.map(Into::into), name.into(), area.clone()))
+}
+
+/// Deployed static file path (or url) like
+/// `pub/static/frontend/Vendor/theme/en_US/Vendor_Module/js/file.js`, mapped back to the
+/// module asset. Paths without `Vendor_Module` segment after the locale are not resolved.
+pub(crate) fn try_deployed_asset_item_from_str(text: &str) -> Option<M2Item> {
+    let text = text.split(['?', '#']).next()?;
+    let segments = text.split('/').collect::<Vec<_>>();
+    let start = segments.iter().position(|s| *s == "static")? + 1;
+    let mut rest = &segments[start..];
+    // static content signing adds `version<timestamp>` to urls
+    if rest.first().is_some_and(|s| {
+        s.strip_prefix("version")
+            .is_some_and(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit()))
+    }) {
+        rest = &rest[1..];
+    }
+    let area = match *rest.first()? {
+        "frontend" => M2Area::Frontend,
+        "adminhtml" => M2Area::Adminhtml,
+        "base" => M2Area::Base,
+        _ => return None,
+    };
+    // area is followed by theme vendor, theme name and locale
+    let rest = rest.get(4..)?;
+    let (mod_name, path) = rest.split_first()?;
+    let is_module = mod_name.split_once('_').is_some_and(|(vendor, module)| {
+        vendor.starts_with(char::is_uppercase) && !module.is_empty()
+    }) && is_part_of_module_name(mod_name);
+    let path = path.join("/");
+    if !is_module || path.is_empty() {
+        return None;
+    }
+    Some(M2Item::WebAsset(Some((*mod_name).into()), path, area))
+}
+
+/// Deployment config reference like `%cache/frontend/default/id_prefix%`.
+pub(crate) fn try_deploy_config_item_from_str(text: &str) -> Option<M2Item> {
+    let config_path = text.strip_prefix('%')?.strip_suffix('%')?;
+    if config_path.is_empty() || config_path.contains(['%', ' ']) {
+        None
+    } else {
+        Some(M2Item::DeployConfig(config_path.into()))
+    }
+}
+
 fn does_ext_eq(path: &str, ext: &str) -> bool {
     Path::new(path)
         .extension()
-        .map_or(false, |e| e.eq_ignore_ascii_case(ext))
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
 }
 
 #[cfg(test)]
 mod test {
-    use crate::m2::M2Path;
+    use std::path::{Path, PathBuf};
+
+    use lsp_types::Url;
+
+    use crate::m2::{
+        path_to_uri, try_deployed_asset_item_from_str, try_ui_component_item_from_str, M2Area,
+        M2Item, M2Path, M2Uri, UriError,
+    };
 
     #[test]
     fn test_has_components_when_components_in_the_middle() {
@@ -300,4 +468,145 @@ mod test {
     fn test_is_part_of_module_name_when_only_one_letter() {
         assert!(super::is_part_of_module_name("N"));
     }
+
+    #[test]
+    fn test_try_get_area() {
+        let area = |path: &str| std::path::PathBuf::from(path).try_get_area();
+        assert_eq!(
+            area("/a/Module/view/frontend/layout/default.xml"),
+            Some(M2Area::Frontend)
+        );
+        assert_eq!(
+            area("/a/design/adminhtml/Vendor/theme/Magento_Theme/layout/default.xml"),
+            Some(M2Area::Adminhtml)
+        );
+        assert_eq!(
+            area("/vendor/magento/theme-frontend-luma/Magento_Theme/layout/default.xml"),
+            Some(M2Area::Frontend)
+        );
+        assert_eq!(
+            area("/a/Module/view/base/page_layout/empty.xml"),
+            Some(M2Area::Base)
+        );
+        assert_eq!(area("/a/Module/etc/di.xml"), None);
+    }
+
+    #[test]
+    fn test_try_ui_component_item_from_str() {
+        assert_eq!(
+            try_ui_component_item_from_str("Some_Module::some_listing", &M2Area::Adminhtml),
+            Some(M2Item::UiComponent(
+                Some("Some_Module".into()),
+                "some_listing".into(),
+                M2Area::Adminhtml
+            ))
+        );
+        assert_eq!(
+            try_ui_component_item_from_str("some_form", &M2Area::Base),
+            Some(M2Item::UiComponent(None, "some_form".into(), M2Area::Base))
+        );
+        assert_eq!(
+            try_ui_component_item_from_str("Some\\Module\\Model\\Item::TYPE", &M2Area::Base),
+            None
+        );
+        assert_eq!(
+            try_ui_component_item_from_str("Some_Module::Item", &M2Area::Base),
+            None
+        );
+    }
+
+    #[test]
+    fn test_try_deployed_asset_item_from_str() {
+        assert_eq!(
+            try_deployed_asset_item_from_str(
+                "pub/static/frontend/Magento/luma/en_US/Magento_Theme/js/theme.js"
+            ),
+            Some(M2Item::WebAsset(
+                Some("Magento_Theme".into()),
+                "js/theme.js".into(),
+                M2Area::Frontend
+            ))
+        );
+        assert_eq!(
+            try_deployed_asset_item_from_str(
+                "https://shop.test/static/version1700000000/adminhtml/Magento/backend/en_US/Some_Module/images/logo.svg?v=1"
+            ),
+            Some(M2Item::WebAsset(
+                Some("Some_Module".into()),
+                "images/logo.svg".into(),
+                M2Area::Adminhtml
+            ))
+        );
+    }
+
+    #[test]
+    fn test_try_deployed_asset_item_from_str_without_module() {
+        assert_eq!(
+            try_deployed_asset_item_from_str(
+                "pub/static/frontend/Magento/luma/en_US/css/styles.css"
+            ),
+            None
+        );
+        assert_eq!(
+            try_deployed_asset_item_from_str(
+                "pub/static/frontend/Magento/luma/en_US/requirejs-config.js"
+            ),
+            None
+        );
+        assert_eq!(
+            try_deployed_asset_item_from_str("Some_Module::js/file.js"),
+            None
+        );
+    }
+
+    fn url(uri: &str) -> Url {
+        Url::parse(uri).expect("Should be valid Url")
+    }
+
+    #[test]
+    fn test_uri_to_path_buf_with_percent_encoding() {
+        let expected = if cfg!(windows) {
+            PathBuf::from("c:\\My Project\\di.xml")
+        } else {
+            PathBuf::from("/c:/My Project/di.xml")
+        };
+        assert_eq!(
+            url("file:///c:/My%20Project/di.xml").to_path_buf(),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn test_uri_to_path_buf_with_windows_drive() {
+        for uri in [
+            "file:///c%3A/Project/etc/di.xml",
+            "file:///c:/Project/etc/di.xml",
+        ] {
+            let expected = if cfg!(windows) {
+                PathBuf::from("c:\\Project\\etc\\di.xml")
+            } else {
+                PathBuf::from("/c:/Project/etc/di.xml")
+            };
+            assert_eq!(url(uri).to_path_buf(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_uri_to_path_buf_of_not_local_file() {
+        assert_eq!(
+            url("untitled:Untitled-1").to_path_buf(),
+            Err(UriError::NotFile("untitled:Untitled-1".into()))
+        );
+        if !cfg!(windows) {
+            assert!(url("file://server/share/di.xml").to_path_buf().is_err());
+        }
+    }
+
+    #[test]
+    fn test_path_to_uri_of_relative_path() {
+        assert_eq!(
+            path_to_uri(Path::new("etc/di.xml")),
+            Err(UriError::InvalidPath(PathBuf::from("etc/di.xml")))
+        );
+    }
 }
@@ -1,40 +1,112 @@
+mod composer;
+mod debounce;
+mod html;
+#[cfg(test)]
+mod integration;
 mod js;
+mod less;
+mod logger;
 mod lsp;
 mod m2;
+mod parser;
 mod php;
+mod publish;
 mod queries;
+mod settings;
+mod sourcemap;
 mod state;
+mod timeout;
 mod ts;
+mod typescript;
 mod xml;
 
-use std::error::Error;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::JoinHandle,
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
-use lsp_server::{Connection, ExtractError, Message, Request, RequestId, Response};
+use lsp_server::{
+    Connection, ErrorCode, ExtractError, Message, Notification, Request, RequestId, Response,
+};
 use lsp_types::{
-    request::{Completion, GotoDefinition},
-    CompletionOptions, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, InitializeParams, OneOf, ServerCapabilities,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
-    WorkDoneProgressOptions,
+    notification::{Notification as _, Progress, PublishDiagnostics},
+    request::{
+        Completion, DocumentHighlightRequest, ExecuteCommand, GotoDeclaration,
+        GotoDeclarationResponse, GotoDefinition, GotoImplementation, HoverRequest, References,
+    },
+    CompletionOptions, DeclarationCapability, Diagnostic, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    ExecuteCommandOptions, GotoDefinitionResponse, HoverProviderCapability,
+    ImplementationProviderCapability, InitializeParams, OneOf, ProgressParams, ProgressParamsValue,
+    ProgressToken, PublishDiagnosticsParams, SaveOptions, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, Url,
+    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressOptions,
 };
 
 use crate::{
-    m2::{M2Path, M2Uri},
-    state::State,
+    debounce::Debouncer,
+    logger::log,
+    m2::M2Uri,
+    publish::{Diagnostics, Source},
+    settings::Settings,
+    state::{ArcState, State},
 };
 
+/// How often to check if initial indexing is done while waiting for messages.
+const INDEX_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
 fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
-    // Note that  we must have our logging only write out to stderr.
-    eprintln!("Starting magento2-ls LSP server");
+    let log_file = logger::log_file_from_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    if let Some(path) = log_file {
+        if let Err(err) = logger::set_log_file(&path) {
+            eprintln!("Can not open log file `{}`: {err}", path.display());
+            std::process::exit(1);
+        }
+    }
+
+    // Note that  we must have our logging only write out to stderr (and the log file).
+    log!("Starting magento2-ls LSP server");
 
     // Create the transport. Includes the stdio (stdin and stdout) versions but this could
     // also be implemented to use sockets or HTTP.
     let (connection, io_threads) = Connection::stdio();
 
     // Run the server and wait for the two threads to end (typically by trigger LSP Exit event).
-    let server_capabilities = serde_json::to_value(ServerCapabilities {
+    run_server(&connection)?;
+    io_threads.join()?;
+
+    // Shut down gracefully.
+    log!("shutting down server");
+    Ok(())
+}
+
+fn run_server(connection: &Connection) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let initialization_params = connection.initialize(server_capabilities()?)?;
+    main_loop(connection, initialization_params)
+}
+
+fn server_capabilities() -> Result<serde_json::Value> {
+    serde_json::to_value(ServerCapabilities {
         definition_provider: Some(OneOf::Left(true)),
+        declaration_provider: Some(DeclarationCapability::Simple(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        references_provider: Some(OneOf::Left(true)),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![
+                lsp::UNUSED_PREFERENCES_COMMAND.into(),
+                lsp::REBUILD_INDEX_COMMAND.into(),
+            ],
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        }),
         completion_provider: Some(CompletionOptions {
             resolve_provider: Some(false),
             trigger_characters: Some(vec![
@@ -54,24 +126,15 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
         text_document_sync: Some(TextDocumentSyncCapability::Options(
             TextDocumentSyncOptions {
                 open_close: Some(true),
-                change: Some(TextDocumentSyncKind::FULL), //TODO change to INCREMENTAL
+                change: Some(TextDocumentSyncKind::INCREMENTAL),
                 will_save: None,
                 will_save_wait_until: None,
-                // save: Some(SaveOptions::default().into()),
-                save: None,
+                save: Some(SaveOptions::default().into()),
             },
         )),
         ..Default::default()
     })
-    .context("Deserializing server capabilities")?;
-    let initialization_params = connection.initialize(server_capabilities)?;
-
-    main_loop(&connection, initialization_params)?;
-    io_threads.join()?;
-
-    // Shut down gracefully.
-    eprintln!("shutting down server");
-    Ok(())
+    .context("Deserializing server capabilities")
 }
 
 fn main_loop(
@@ -81,90 +144,274 @@ fn main_loop(
     let params: InitializeParams =
         serde_json::from_value(init_params).context("Deserializing initialize params")?;
 
-    let state = State::new().into_arc();
+    let settings = Settings::from_init_options(params.initialization_options);
+    let mut debouncer = Debouncer::new(Duration::from_millis(settings.reindex_debounce));
+    let request_timeout = Duration::from_millis(settings.request_timeout);
+    let mut state = State::new();
+    state.set_settings(settings);
+    let state = state.into_arc();
     let mut threads = vec![];
+    let mut diagnostics = Diagnostics::new();
+    // index being rebuilt in the background and the thread swapping it in when it is done
+    let mut rebuild: Option<(ArcState, JoinHandle<()>)> = None;
 
-    if let Some(uri) = params.root_uri {
-        let path = uri.to_file_path().expect("Invalid root path");
+    if let Some(path) = params.root_uri.as_ref().and_then(workspace_root) {
         threads.extend(State::update_index(&state, &path));
     };
 
     if let Some(folders) = params.workspace_folders {
-        for folder in folders {
-            let path = folder.uri.to_file_path().expect("Invalid workspace path");
+        for path in folders
+            .iter()
+            .filter_map(|folder| workspace_root(&folder.uri))
+        {
             threads.extend(State::update_index(&state, &path));
         }
     }
 
-    eprintln!("Starting main loop");
-    for msg in &connection.receiver {
+    log!("Starting main loop");
+    let mut indexed = false;
+    loop {
+        // diagnostics before the index is done would report missing entities
+        if !indexed && threads.iter().all(JoinHandle::is_finished) {
+            indexed = true;
+            let state = state.lock();
+            update_diagnostics(&mut diagnostics, &state, state.get_open_files());
+        }
+        if rebuild
+            .as_ref()
+            .is_some_and(|(_, thread)| thread.is_finished())
+        {
+            rebuild = None;
+            let state = state.lock();
+            update_diagnostics(&mut diagnostics, &state, state.get_open_files());
+        }
+        if reindex_files(&state, debouncer.take_due()) && indexed {
+            let state = state.lock();
+            update_diagnostics(&mut diagnostics, &state, state.get_open_files());
+        }
+        for (path, file_diagnostics) in diagnostics.take_due() {
+            send_diagnostics(connection, &path, file_diagnostics)?;
+        }
+        let timeout = [debouncer.timeout(), diagnostics.timeout()]
+            .into_iter()
+            .flatten()
+            .min();
+        let timeout = if indexed && rebuild.is_none() {
+            timeout
+        } else {
+            Some(timeout.map_or(INDEX_CHECK_INTERVAL, |t| t.min(INDEX_CHECK_INTERVAL)))
+        };
+        let msg = match timeout {
+            Some(timeout) => match connection.receiver.recv_timeout(timeout) {
+                Ok(msg) => msg,
+                Err(err) if err.is_timeout() => continue,
+                Err(_) => break,
+            },
+            None => match connection.receiver.recv() {
+                Ok(msg) => msg,
+                Err(_) => break,
+            },
+        };
         match msg {
             Message::Request(req) => {
                 #[cfg(debug_assertions)]
-                eprintln!("request: {:?}", req.method);
+                log!("request: {:?}", req.method);
                 if connection.handle_shutdown(&req)? {
-                    return Ok(());
+                    break;
                 }
                 match req.method.as_str() {
                     "textDocument/completion" => {
-                        let (id, params) = cast::<Completion>(req)?;
+                        let Some((id, params)) = cast::<Completion>(connection, req)? else {
+                            continue;
+                        };
                         let result = lsp::completion_handler(&state.lock(), &params);
                         connection.sender.send(get_response_message(id, result))?;
                     }
                     "textDocument/definition" => {
-                        let (id, params) = cast::<GotoDefinition>(req)?;
-                        let result = lsp::definition_handler(&state.lock(), &params);
+                        let Some((id, params)) = cast::<GotoDefinition>(connection, req)? else {
+                            continue;
+                        };
+                        // worker gets a snapshot (sharing the index), so state is not locked
+                        // by work that timed out
+                        let snapshot = state.lock().clone();
+                        let result = timeout::run_with_timeout(request_timeout, move || {
+                            lsp::definition_handler(&snapshot, &params)
+                        })
+                        .unwrap_or_else(|| {
+                            log!(
+                                "Definition request timed out after {}ms",
+                                request_timeout.as_millis()
+                            );
+                            GotoDefinitionResponse::Array(vec![])
+                        });
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "textDocument/declaration" => {
+                        let Some((id, params)) = cast::<GotoDeclaration>(connection, req)? else {
+                            continue;
+                        };
+                        // same as definition, snapshot shares the index with the state
+                        let snapshot = state.lock().clone();
+                        let result = timeout::run_with_timeout(request_timeout, move || {
+                            lsp::declaration_handler(&snapshot, &params)
+                        })
+                        .unwrap_or_else(|| {
+                            log!(
+                                "Declaration request timed out after {}ms",
+                                request_timeout.as_millis()
+                            );
+                            GotoDeclarationResponse::Array(vec![])
+                        });
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "textDocument/hover" => {
+                        let Some((id, params)) = cast::<HoverRequest>(connection, req)? else {
+                            continue;
+                        };
+                        let result = lsp::hover_handler(&state.lock(), &params);
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "textDocument/references" => {
+                        let Some((id, params)) = cast::<References>(connection, req)? else {
+                            continue;
+                        };
+                        let result = lsp::references_handler(&state.lock(), &params);
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "textDocument/documentHighlight" => {
+                        let Some((id, params)) = cast::<DocumentHighlightRequest>(connection, req)?
+                        else {
+                            continue;
+                        };
+                        let result = lsp::document_highlight_handler(&state.lock(), &params);
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "textDocument/implementation" => {
+                        let Some((id, params)) = cast::<GotoImplementation>(connection, req)?
+                        else {
+                            continue;
+                        };
+                        let result = lsp::implementation_handler(&state.lock(), &params);
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "workspace/executeCommand" => {
+                        let Some((id, params)) = cast::<ExecuteCommand>(connection, req)? else {
+                            continue;
+                        };
+                        let command = params.command.as_str();
+                        let result = if command != lsp::UNUSED_PREFERENCES_COMMAND
+                            && command != lsp::REBUILD_INDEX_COMMAND
+                        {
+                            log!("unknown command: {command:?}");
+                            None
+                        } else if !indexed {
+                            // everything would be unused before the index is done
+                            log!("Index is not ready, command {command:?} is not run");
+                            None
+                        } else if command == lsp::UNUSED_PREFERENCES_COMMAND {
+                            let state = state.lock();
+                            let count = report_unused_preferences(&mut diagnostics, &state);
+                            Some(serde_json::Value::from(count))
+                        } else if rebuild.is_some() {
+                            log!("Index is already being rebuilt");
+                            None
+                        } else {
+                            let token = params.work_done_progress_params.work_done_token;
+                            // response is sent once the new index is swapped in
+                            rebuild = Some(rebuild_index(connection, &state, id, token)?);
+                            continue;
+                        };
+                        connection.sender.send(get_response_message(id, result))?;
+                    }
+                    "magento2-ls/moduleFiles" => {
+                        let Some((id, params)) = cast::<lsp::ModuleFiles>(connection, req)? else {
+                            continue;
+                        };
+                        let result = lsp::module_files_handler(&state.lock(), &params);
                         connection.sender.send(get_response_message(id, result))?;
                     }
                     _ => {
-                        eprintln!("unhandled request: {:?}", req.method);
+                        log!("unhandled request: {:?}", req.method);
+                        connection.sender.send(get_error_message(
+                            req.id,
+                            ErrorCode::MethodNotFound,
+                            format!("Unhandled method {}", req.method),
+                        ))?;
                     }
                 }
             }
             Message::Response(_resp) => {
                 #[cfg(debug_assertions)]
-                eprintln!("response: {_resp:?}");
+                log!("response: {_resp:?}");
             }
             Message::Notification(not) => match not.method.as_str() {
                 "textDocument/didOpen" => {
                     let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)
                         .context("Deserializing notification params")?;
-                    let path = params.text_document.uri.to_path_buf();
-                    state.lock().set_file(&path, params.text_document.text);
+                    let Some(path) = document_path(&params.text_document.uri) else {
+                        continue;
+                    };
+                    let mut state = state.lock();
+                    state.set_file(&path, params.text_document.text);
+                    if indexed {
+                        update_diagnostics(&mut diagnostics, &state, vec![path.clone()]);
+                    }
                     #[cfg(debug_assertions)]
-                    eprintln!("textDocument/didOpen: {path:?}");
+                    log!("textDocument/didOpen: {path:?}");
                 }
                 "textDocument/didChange" => {
                     let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)
                         .context("Deserializing notification params")?;
-                    let path = params.text_document.uri.to_path_buf();
-                    match path.get_ext().as_str() {
-                        "js" | "xml" => state
-                            .lock()
-                            .set_file(&path, &params.content_changes[0].text),
-                        "php" if path.ends_with("registration.php") => state
-                            .lock()
-                            .set_file(&path, &params.content_changes[0].text),
-                        _ => (),
+                    let Some(path) = document_path(&params.text_document.uri) else {
+                        continue;
+                    };
+                    if !state.lock().change_file(&path, &params.content_changes) {
+                        log!("textDocument/didChange for not opened file: {path:?}");
+                        continue;
                     }
+                    debouncer.schedule(&path);
+                    #[cfg(debug_assertions)]
+                    log!("textDocument/didChange: {path:?}");
+                }
+                "textDocument/didSave" => {
+                    let params: DidSaveTextDocumentParams = serde_json::from_value(not.params)
+                        .context("Deserializing notification params")?;
+                    let Some(path) = document_path(&params.text_document.uri) else {
+                        continue;
+                    };
+                    debouncer.schedule(&path);
                     #[cfg(debug_assertions)]
-                    eprintln!("textDocument/didChange: {path:?}");
+                    log!("textDocument/didSave: {path:?}");
                 }
                 "textDocument/didClose" => {
                     let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)
                         .context("Deserializing notification params")?;
-                    let path = params.text_document.uri.to_path_buf();
-                    state.lock().del_file(&path);
+                    let Some(path) = document_path(&params.text_document.uri) else {
+                        continue;
+                    };
+                    {
+                        let mut state = state.lock();
+                        state.del_file(&path);
+                        // entries indexed from unsaved changes are replaced by the file on disk
+                        state.reindex_file(&path);
+                    }
+                    diagnostics.clear(&path);
                     #[cfg(debug_assertions)]
-                    eprintln!("textDocument/didClose: {path:?}");
+                    log!("textDocument/didClose: {path:?}");
                 }
                 _ => {
-                    eprintln!("unhandled notification: {:?}", not.method);
+                    log!("unhandled notification: {:?}", not.method);
                 }
             },
         }
     }
 
+    if let Some((fresh, thread)) = rebuild {
+        fresh.lock().cancel_index();
+        thread.join().ok();
+    }
+    reindex_files(&state, debouncer.take_all());
+    state.lock().cancel_index();
     for thread in threads {
         thread.join().ok();
     }
@@ -172,6 +419,139 @@ fn main_loop(
     Ok(())
 }
 
+/// Returns `true` if any file was reindexed.
+fn reindex_files(state: &ArcState, paths: Vec<PathBuf>) -> bool {
+    if paths.is_empty() {
+        return false;
+    }
+    let mut state = state.lock();
+    for path in paths {
+        state.reindex_file(&path);
+    }
+    true
+}
+
+/// Build fresh index in the background and swap it in once it is done, requests are
+/// served from the current index meanwhile. Response with stats of the new index is sent
+/// after the swap, progress is reported when client gave a token for it.
+fn rebuild_index(
+    connection: &Connection,
+    state: &ArcState,
+    id: RequestId,
+    token: Option<ProgressToken>,
+) -> Result<(ArcState, JoinHandle<()>), Box<dyn Error + Sync + Send>> {
+    let begin = WorkDoneProgress::Begin(WorkDoneProgressBegin {
+        title: "Rebuilding index".into(),
+        ..WorkDoneProgressBegin::default()
+    });
+    if let Some(message) = progress_message(token.as_ref(), begin) {
+        connection.sender.send(message)?;
+    }
+    let (fresh, threads) = State::rebuild_index(state);
+    let sender = connection.sender.clone();
+    let state = Arc::clone(state);
+    let worker_fresh = Arc::clone(&fresh);
+    let thread = std::thread::spawn(move || {
+        for thread in threads {
+            thread.join().ok();
+        }
+        let fresh = std::mem::replace(&mut *worker_fresh.lock(), State::new());
+        if fresh.is_index_cancelled() {
+            return; // server is shutting down
+        }
+        let stats = {
+            let mut state = state.lock();
+            state.replace_index(fresh);
+            state.index_stats()
+        };
+        log!("Index rebuilt: {stats:?}");
+        let end = WorkDoneProgress::End(WorkDoneProgressEnd { message: None });
+        if let Some(message) = progress_message(token.as_ref(), end) {
+            sender.send(message).ok();
+        }
+        sender.send(get_response_message(id, stats)).ok();
+    });
+    Ok((fresh, thread))
+}
+
+fn progress_message(token: Option<&ProgressToken>, progress: WorkDoneProgress) -> Option<Message> {
+    let params = ProgressParams {
+        token: token?.clone(),
+        value: ProgressParamsValue::WorkDone(progress),
+    };
+    Some(Message::Notification(Notification::new(
+        Progress::METHOD.into(),
+        params,
+    )))
+}
+
+fn update_diagnostics(diagnostics: &mut Diagnostics, state: &State, paths: Vec<PathBuf>) {
+    for path in paths {
+        diagnostics.set(&path, Source::File, lsp::file_diagnostics(state, &path));
+    }
+}
+
+/// Replace diagnostics of the previous unused preferences report with the new one,
+/// `di.xml` files get their file diagnostics as well, as they do not have to be opened.
+/// Returns number of reported preferences.
+fn report_unused_preferences(diagnostics: &mut Diagnostics, state: &State) -> usize {
+    diagnostics.clear_source(Source::UnusedPreferences);
+    let mut count = 0;
+    for (path, preferences) in lsp::unused_preferences_report(state) {
+        count += preferences.len();
+        diagnostics.set(&path, Source::File, lsp::file_diagnostics(state, &path));
+        diagnostics.set(&path, Source::UnusedPreferences, preferences);
+    }
+    count
+}
+
+/// Directory of the workspace root (or folder), roots that are not accessible directories
+/// (like not mounted network share) are skipped, the server still runs without them.
+fn workspace_root(uri: &Url) -> Option<PathBuf> {
+    let path = uri
+        .to_path_buf()
+        .map_err(|err| log!("Skipping root: {err}"))
+        .ok()?;
+    if path.is_dir() {
+        Some(path)
+    } else {
+        log!(
+            "Skipping root `{}`: not an accessible directory",
+            path.display()
+        );
+        None
+    }
+}
+
+/// Path of the opened document, documents that are not local files are ignored.
+fn document_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_path_buf()
+        .map_err(|err| log!("Ignoring document: {err}"))
+        .ok()
+}
+
+fn send_diagnostics(
+    connection: &Connection,
+    path: &Path,
+    diagnostics: Vec<Diagnostic>,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let Ok(uri) = m2::path_to_uri(path) else {
+        return Ok(());
+    };
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    connection
+        .sender
+        .send(Message::Notification(Notification::new(
+            PublishDiagnostics::METHOD.into(),
+            params,
+        )))?;
+    Ok(())
+}
+
 fn get_response_message<T>(id: RequestId, result: T) -> Message
 where
     T: serde::Serialize,
@@ -184,10 +564,31 @@ where
     })
 }
 
-fn cast<R>(req: Request) -> Result<(RequestId, R::Params), ExtractError<Request>>
+fn get_error_message(id: RequestId, code: ErrorCode, message: String) -> Message {
+    Message::Response(Response::new_err(id, code as i32, message))
+}
+
+/// Id and params of the request, request with params that can not be deserialized is
+/// answered with `InvalidParams` error, so one bad request does not stop the server.
+fn cast<R>(connection: &Connection, req: Request) -> Result<Option<(RequestId, R::Params)>>
 where
     R: lsp_types::request::Request,
     R::Params: serde::de::DeserializeOwned,
 {
-    req.extract(R::METHOD)
+    let id = req.id.clone();
+    match req.extract(R::METHOD) {
+        Ok(extracted) => Ok(Some(extracted)),
+        Err(ExtractError::JsonError { method, error }) => {
+            log!("Invalid params of {method} request: {error}");
+            connection.sender.send(get_error_message(
+                id,
+                ErrorCode::InvalidParams,
+                format!("Invalid params of {method} request: {error}"),
+            ))?;
+            Ok(None)
+        }
+        Err(ExtractError::MethodMismatch(req)) => {
+            anyhow::bail!("Request {} handled as {}", req.method, R::METHOD)
+        }
+    }
 }
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use lsp_types::Position;
+use serde::Deserialize;
+
+use crate::{html, js, less, m2::M2Item, php, state::State, typescript, xml};
+
+/// Language module used to find Magento item under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parser {
+    Html,
+    Js,
+    Less,
+    Php,
+    TypeScript,
+    Xml,
+    /// Stub that never finishes, for testing request timeouts.
+    #[cfg(test)]
+    Hanging,
+}
+
+impl Parser {
+    /// Parser for the file extension, `overrides` take precedence over the defaults.
+    pub fn for_extension(ext: &str, overrides: &HashMap<String, Self>) -> Option<Self> {
+        if let Some(parser) = overrides.get(ext) {
+            return Some(*parser);
+        }
+        match ext {
+            "js" => Some(Self::Js),
+            "html" => Some(Self::Html),
+            "xml" => Some(Self::Xml),
+            "less" => Some(Self::Less),
+            "php" | "phtml" => Some(Self::Php),
+            "ts" | "tsx" => Some(Self::TypeScript),
+            _ => None,
+        }
+    }
+
+    pub fn get_item_from_position(
+        self,
+        state: &State,
+        path: &PathBuf,
+        pos: Position,
+    ) -> Option<M2Item> {
+        match self {
+            Self::Html => html::get_item_from_position(state, path, pos),
+            Self::Js => js::get_item_from_position(state, path, pos),
+            Self::Less => less::get_item_from_position(state, path, pos),
+            Self::Php => php::get_item_from_position(state, path, pos),
+            Self::TypeScript => typescript::get_item_from_position(state, path, pos),
+            Self::Xml => xml::get_item_from_position(state, path, pos),
+            #[cfg(test)]
+            Self::Hanging => loop {
+                std::thread::park();
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_for_extension_with_defaults() {
+        let overrides = HashMap::new();
+        assert_eq!(
+            Parser::for_extension("phtml", &overrides),
+            Some(Parser::Php)
+        );
+        assert_eq!(
+            Parser::for_extension("tsx", &overrides),
+            Some(Parser::TypeScript)
+        );
+        assert_eq!(
+            Parser::for_extension("less", &overrides),
+            Some(Parser::Less)
+        );
+        assert_eq!(Parser::for_extension("graphqls", &overrides), None);
+    }
+
+    #[test]
+    fn test_for_extension_with_overrides() {
+        let overrides = HashMap::from([
+            ("phtml".to_string(), Parser::Html),
+            ("mjs".to_string(), Parser::Js),
+        ]);
+        assert_eq!(
+            Parser::for_extension("phtml", &overrides),
+            Some(Parser::Html)
+        );
+        assert_eq!(Parser::for_extension("mjs", &overrides), Some(Parser::Js));
+        assert_eq!(Parser::for_extension("php", &overrides), Some(Parser::Php));
+    }
+}
@@ -5,37 +5,90 @@ use std::{
 
 use convert_case::{Case, Casing};
 use glob::glob;
-use lsp_types::{Position, Range, Url};
-use tree_sitter::{Node, QueryCursor};
+use lsp_types::{Location, Position, Range, Url};
+use tree_sitter::{Node, Point, QueryCursor};
 
 use crate::{
-    m2::M2Path,
+    composer,
+    m2::{self, M2Item, M2Path},
     queries,
     state::{ArcState, State},
-    ts::{self, get_range_from_node},
+    ts::{self, get_range_from_node, node_at_position},
 };
 
+/// Methods taking asset path (`Vendor_Module::path/to/file`) as first argument.
+const ASSET_URL_METHODS: [&str; 3] = ["getViewFileUrl", "createAsset", "getUrlWithParams"];
+
+/// Methods of `ScopeConfigInterface` taking config path (`section/group/field`) as first argument.
+const SCOPE_CONFIG_METHODS: [&str; 2] = ["getValue", "isSetFlag"];
+
+/// Parameter types that are not classes, so they are not resolved against the namespace.
+const BUILTIN_TYPES: [&str; 16] = [
+    "array", "bool", "callable", "false", "float", "int", "iterable", "mixed", "null", "object",
+    "parent", "self", "static", "string", "true", "void",
+];
+
+/// Docblock tags of integration (and api-functional) tests taking data fixture.
+const DATA_FIXTURE_TAGS: [&str; 3] = [
+    "@magentoDataFixture",
+    "@magentoDataFixtureBeforeTransaction",
+    "@magentoApiDataFixture",
+];
+
+/// Docblock tags documenting type of returned value or parameter.
+const TYPE_TAGS: [&str; 2] = ["@return", "@param"];
+
+/// Docblock tags referencing class or its method (`@see \\Foo\\Bar::baz()`).
+const REFERENCE_TAGS: [&str; 2] = ["@see", "@uses"];
+
+/// Keywords of promoted constructor properties, grammar does not parse them as part of
+/// the parameter, so they are skipped in the parameter text.
+const PROMOTION_MODIFIERS: [&str; 4] = ["public", "protected", "private", "readonly"];
+
 #[derive(Debug, Clone)]
 pub struct PHPClass {
-    pub fqn: String,
     pub uri: Url,
     pub range: Range,
     pub methods: HashMap<String, PHPMethod>,
+    /// Methods documented with `@method` tags of the class docblock, like `DataObject`
+    /// magic getters and setters, range is the name in the annotation.
+    pub magic_methods: HashMap<String, PHPMethod>,
     pub constants: HashMap<String, PHPConst>,
 }
 
 #[derive(Debug, Clone)]
 pub struct PHPMethod {
-    pub name: String,
     pub range: Range,
 }
 
 #[derive(Debug, Clone)]
 pub struct PHPConst {
-    pub name: String,
     pub range: Range,
 }
 
+/// Class, interface or trait declaration with fully qualified names of classes it extends
+/// and interfaces it implements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PHPClassDeclaration {
+    pub fqn: String,
+    pub is_interface: bool,
+    pub is_trait: bool,
+    pub is_abstract: bool,
+    pub parents: Vec<String>,
+    pub location: Location,
+    /// Class aliased by `class_alias()` call, location is the aliased class declaration
+    /// when it is in the same file, the call otherwise.
+    pub alias_of: Option<String>,
+}
+
+/// Parameter of the class constructor, class names are fully qualified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PHPConstructorParam {
+    pub class: String,
+    pub name: String,
+    pub declared_type: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 enum M2Module {
     Module(String),
@@ -81,13 +134,13 @@ fn register_param_to_module(param: &str) -> Option<M2Module> {
 }
 
 pub fn update_index(state: &ArcState, path: &PathBuf) {
+    let vendor_packages = composer::indexed_vendor_packages(state, path);
     // if current workspace is magento module
     process_glob(state, &path.append(&["registration.php"]));
     // if current workspace is magento installation
-    process_glob(
-        state,
-        &path.append(&["vendor", "*", "*", "registration.php"]),
-    ); // vendor modules / themes
+    for package in &vendor_packages {
+        process_glob(state, &package.append(&["registration.php"])); // vendor modules / themes
+    }
     process_glob(
         state,
         &path.append(&["app", "code", "*", "*", "registration.php"]),
@@ -96,25 +149,342 @@ pub fn update_index(state: &ArcState, path: &PathBuf) {
         state,
         &path.append(&["app", "design", "*", "*", "*", "registration.php"]),
     ); // local themes
-    process_glob(
-        state,
-        &path.append(&[
-            "vendor",
-            "magento",
-            "magento2-base",
-            "setup",
-            "src",
-            "Magento",
-            "Setup",
-            "registration.php",
-        ]),
-    ); // magento2-base setup module
+    let base_package = path.append(&["vendor", "magento", "magento2-base"]);
+    if vendor_packages.contains(&base_package) {
+        process_glob(
+            state,
+            &base_package.append(&["setup", "src", "Magento", "Setup", "registration.php"]),
+        ); // magento2-base setup module
+    }
+
+    index_classes(state, path);
+}
+
+/// Scan all PHP files of the modules found in the workspace to build class hierarchy.
+fn index_classes(state: &ArcState, path: &Path) {
+    let index_tests = state.lock().settings().index_tests;
+    let mut module_paths = state
+        .lock()
+        .module_paths()
+        .into_iter()
+        .filter(|p| p.starts_with(path))
+        .collect::<Vec<_>>();
+    if index_tests {
+        // test frameworks and fixtures of integration, api-functional and other tests
+        module_paths.push(path.to_path_buf().append(&["dev", "tests"]));
+    }
+    module_paths.sort_unstable();
+    module_paths.dedup();
+
+    let mut last_path: Option<&PathBuf> = None;
+    for module_path in &module_paths {
+        if last_path.is_some_and(|p| module_path.starts_with(p)) {
+            continue; // already scanned with parent module
+        }
+        last_path = Some(module_path);
+
+        let files = glob(module_path.append(&["**", "*.php"]).to_path_str())
+            .expect("Failed to read glob pattern")
+            .filter_map(Result::ok);
+
+        for file_path in files {
+            if state.lock().is_index_cancelled() {
+                return;
+            }
+            if !index_tests && (file_path.is_test() || file_path.has_components(&["Test"])) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&file_path) else {
+                continue;
+            };
+            update_index_from_class_file(&mut state.lock(), &content, &file_path);
+        }
+    }
 }
 
 pub fn maybe_index_file(state: &mut State, content: &str, file_path: &PathBuf) {
     if file_path.to_path_str().ends_with("registration.php") {
         update_index_from_registration(state, content, file_path);
+    } else if file_path.get_ext() == "php" {
+        update_index_from_class_file(state, content, file_path);
+    }
+}
+
+fn update_index_from_class_file(state: &mut State, content: &str, file_path: &Path) {
+    state.set_source_file(file_path);
+    let tree = tree_sitter_parsers::parse(content, "php");
+    let declarations = class_declarations(tree.root_node(), content, file_path);
+    let mut usages = get_class_usages(tree.root_node(), content);
+    usages.retain(|class| !declarations.iter().any(|d| &d.fqn == class));
+    usages.sort_unstable();
+    usages.dedup();
+    for class in usages {
+        state.add_class_usage(class);
+    }
+    for declaration in declarations {
+        state.add_php_class(declaration);
+    }
+}
+
+pub fn get_class_declarations(content: &str, file_path: &Path) -> Vec<PHPClassDeclaration> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    class_declarations(tree.root_node(), content, file_path)
+}
+
+fn class_declarations(root: Node, content: &str, file_path: &Path) -> Vec<PHPClassDeclaration> {
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return vec![];
+    };
+    let mut namespace = "";
+    let mut imports = HashMap::new();
+    let mut result = vec![];
+    let mut aliases = vec![];
+
+    // braced namespace (`namespace Foo { ... }`) keeps its declarations in the body
+    let nodes = children(root).into_iter().flat_map(|node| {
+        let body = node
+            .child_by_field_name("body")
+            .filter(|_| node.kind() == "namespace_definition");
+        std::iter::once(node).chain(body.map(children).unwrap_or_default())
+    });
+
+    for node in nodes {
+        match node.kind() {
+            "namespace_definition" => {
+                namespace = node
+                    .child_by_field_name("name")
+                    .map_or("", |n| ts::get_node_str(n, content));
+                imports.clear();
+            }
+            "namespace_use_declaration" => add_use_imports(node, content, &mut imports),
+            "class_declaration" | "interface_declaration" | "trait_declaration" => {
+                let Some(name_node) = node.child_by_field_name("name") else {
+                    continue;
+                };
+                let name = ts::get_node_str(name_node, content);
+                let parents = children(node)
+                    .into_iter()
+                    .filter(|n| n.kind() == "base_clause" || n.kind() == "class_interface_clause")
+                    .flat_map(children)
+                    .filter(|n| n.kind() == "qualified_name" || n.kind() == "name")
+                    .map(|n| resolve_class_name(node_text(n, content), namespace, &imports))
+                    .collect();
+                result.push(PHPClassDeclaration {
+                    fqn: resolve_class_name(&format!("namespace\\{name}"), namespace, &imports),
+                    is_interface: node.kind() == "interface_declaration",
+                    is_trait: node.kind() == "trait_declaration",
+                    is_abstract: children(node).into_iter().any(|n| {
+                        n.kind() == "class_modifier" && node_text(n, content) == "abstract"
+                    }),
+                    parents,
+                    location: Location {
+                        uri: uri.clone(),
+                        range: get_range_from_node(name_node),
+                    },
+                    alias_of: None,
+                });
+            }
+            _ => {
+                for call in class_alias_calls(node, content) {
+                    if let Some(alias) = get_class_alias(call, content, namespace, &imports) {
+                        aliases.push(alias);
+                    }
+                }
+            }
+        }
+    }
+
+    for (alias, original, range) in aliases {
+        let location = result
+            .iter()
+            .find(|d| d.alias_of.is_none() && d.fqn == original)
+            .map_or_else(
+                || Location {
+                    uri: uri.clone(),
+                    range,
+                },
+                |d| d.location.clone(),
+            );
+        result.push(PHPClassDeclaration {
+            fqn: alias,
+            is_interface: false,
+            is_trait: false,
+            is_abstract: false,
+            parents: vec![],
+            location,
+            alias_of: Some(original),
+        });
+    }
+    result
+}
+
+/// Classes used in type declarations, `new Foo()` and `Foo::` access (like `Foo::class`),
+/// `use` imports and `implements` alone do not count as usage.
+fn get_class_usages(root: Node, content: &str) -> Vec<String> {
+    let (namespace, imports) = get_file_scope(root, content);
+    let mut result = vec![];
+    let mut nodes = vec![root];
+    while let Some(node) = nodes.pop() {
+        let name = match node.kind() {
+            "type_name" => Some(node),
+            "object_creation_expression"
+            | "class_constant_access_expression"
+            | "scoped_call_expression" => node
+                .named_child(0)
+                .filter(|n| n.kind() == "qualified_name" || n.kind() == "name"),
+            _ => None,
+        };
+        if let Some(name) = name.map(|n| node_text(n, content)) {
+            if !BUILTIN_TYPES.contains(&name.to_lowercase().as_str()) {
+                result.push(resolve_class_name(name, namespace, &imports));
+            }
+        }
+        nodes.extend(children(node));
+    }
+    result
+}
+
+/// `class_alias()` calls in the statement, also the conditional ones (inside `if`).
+fn class_alias_calls<'a>(node: Node<'a>, content: &str) -> Vec<Node<'a>> {
+    if node.kind() == "function_call_expression" {
+        let is_class_alias = node
+            .child_by_field_name("function")
+            .is_some_and(|f| node_text(f, content).trim_start_matches('\\') == "class_alias");
+        return if is_class_alias { vec![node] } else { vec![] };
+    }
+    if matches!(
+        node.kind(),
+        "class_declaration" | "interface_declaration" | "trait_declaration"
+    ) {
+        return vec![];
+    }
+    children(node)
+        .into_iter()
+        .flat_map(|child| class_alias_calls(child, content))
+        .collect()
+}
+
+/// Alias name, aliased class (both fully qualified) and range of the alias argument
+/// of `class_alias(Original::class, 'Alias')` call.
+fn get_class_alias(
+    call: Node,
+    content: &str,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> Option<(String, String, Range)> {
+    let arguments = children(call.child_by_field_name("arguments")?)
+        .into_iter()
+        .filter(|n| n.is_named())
+        .map(|n| match n.kind() {
+            "argument" => n.named_child(0),
+            _ => Some(n),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let class_name = |node: Node| -> Option<String> {
+        match node.kind() {
+            // class names in strings are always fully qualified
+            "string" | "encapsed_string" => Some(
+                get_string_text(node, content)
+                    .replace("\\\\", "\\")
+                    .trim_start_matches('\\')
+                    .to_string(),
+            ),
+            "class_constant_access_expression" => {
+                let class = node.named_child(0)?;
+                (ts::get_node_str(node.named_child(1)?, content) == "class")
+                    .then(|| resolve_class_name(node_text(class, content), namespace, imports))
+            }
+            _ => None,
+        }
+    };
+    let original = class_name(*arguments.first()?)?;
+    let alias_node = *arguments.get(1)?;
+    let alias = class_name(alias_node)?;
+    if original.is_empty() || alias.is_empty() {
+        return None;
     }
+    Some((alias, original, get_range_from_node(alias_node)))
+}
+
+/// Collect aliases of `use` statements, `use A\B as C, D\E;` and `use A\{B, C\D};`.
+fn add_use_imports(node: Node, content: &str, imports: &mut HashMap<String, String>) {
+    let mut prefix = String::new();
+    for child in children(node) {
+        match child.kind() {
+            "namespace_name" => prefix = format!("{}\\", ts::get_node_str(child, content)),
+            "namespace_use_clause" => add_use_clause(child, content, "", imports),
+            "namespace_use_group" => {
+                for clause in children(child) {
+                    if clause.kind() == "namespace_use_group_clause" {
+                        add_use_clause(clause, content, &prefix, imports);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn add_use_clause(
+    clause: Node,
+    content: &str,
+    prefix: &str,
+    imports: &mut HashMap<String, String>,
+) {
+    let clause_children = children(clause);
+    let Some(name) = clause_children
+        .iter()
+        .find(|n| matches!(n.kind(), "qualified_name" | "namespace_name" | "name"))
+    else {
+        return;
+    };
+    let fqn = format!("{prefix}{}", ts::get_node_str(*name, content));
+    let fqn = fqn.trim_start_matches('\\');
+    let alias = clause_children
+        .iter()
+        .find(|n| n.kind() == "namespace_aliasing_clause")
+        .and_then(|n| n.named_child(0))
+        .map_or_else(
+            || fqn.rsplit('\\').next().unwrap_or(fqn),
+            |n| ts::get_node_str(n, content),
+        );
+    imports.insert(alias.into(), fqn.into());
+}
+
+/// Resolve class name used in the file to fully qualified name (without leading `\\`).
+pub fn resolve_class_name(
+    name: &str,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> String {
+    if let Some(fqn) = name.strip_prefix('\\') {
+        return fqn.into();
+    }
+    let relative = name.strip_prefix("namespace\\");
+    let (first, rest) = name
+        .split_once('\\')
+        .map_or((name, None), |(f, r)| (f, Some(r)));
+    if relative.is_none() {
+        if let Some(import) = imports.get(first) {
+            return rest.map_or_else(|| import.clone(), |rest| format!("{import}\\{rest}"));
+        }
+    }
+    let name = relative.unwrap_or(name);
+    if namespace.is_empty() {
+        name.into()
+    } else {
+        format!("{namespace}\\{name}")
+    }
+}
+
+/// Node text with leading `\` kept, unlike `ts::get_node_str`.
+fn node_text<'a>(node: Node, content: &'a str) -> &'a str {
+    node.utf8_text(content.as_bytes()).unwrap_or("")
+}
+
+fn children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).collect()
 }
 
 fn update_index_from_registration(state: &mut State, content: &str, file_path: &Path) {
@@ -159,20 +529,587 @@ fn process_glob(state: &ArcState, glob_path: &PathBuf) {
         .filter_map(Result::ok);
 
     for file_path in modules {
-        if file_path.is_test() {
+        if file_path.is_test() || state.lock().is_index_cancelled() {
             return;
         }
 
-        let content =
-            std::fs::read_to_string(&file_path).expect("Should have been able to read the file");
+        let Ok(content) = std::fs::read_to_string(&file_path) else {
+            continue;
+        };
 
         update_index_from_registration(&mut state.lock(), &content, &file_path);
     }
 }
 
-pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
-    let content =
-        std::fs::read_to_string(file_path).expect("Should have been able to read the file");
+pub fn get_item_from_position(state: &State, path: &PathBuf, pos: Position) -> Option<M2Item> {
+    let content = state.get_file(path)?;
+    get_item_from_pos(content, path, pos)
+}
+
+fn get_item_from_pos(content: &str, path: &PathBuf, pos: Position) -> Option<M2Item> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    if let Some(item) = get_doc_tag_item(tree.root_node(), content, pos) {
+        return Some(item);
+    }
+    let query = queries::php_item_from_pos();
+    let mut cursor = QueryCursor::new();
+    let matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+
+    for m in matches {
+        if m.pattern_index == 0 {
+            let method = ts::get_node_str(m.captures[0].node, content);
+            let arg = m.captures[1].node;
+            if ASSET_URL_METHODS.contains(&method) && node_at_position(arg, pos) {
+                return m2::try_web_asset_item_from_str(
+                    get_string_text(arg, content),
+                    &path.get_area(),
+                );
+            }
+            if SCOPE_CONFIG_METHODS.contains(&method) && node_at_position(arg, pos) {
+                return config_path_item(get_string_text(arg, content));
+            }
+        }
+        if m.pattern_index == 1 {
+            let class = m.captures[0].node;
+            let constant = m.captures[1].node;
+            let constant_name = ts::get_node_str(constant, content);
+            let on_constant = node_at_position(constant, pos);
+            if node_at_position(class, pos) || on_constant {
+                let (namespace, imports) = get_file_scope(tree.root_node(), content);
+                let class = resolve_class_name(node_text(class, content), namespace, &imports);
+                // `Trait::method` of trait conflict resolution (`insteadof` and `as`)
+                let in_trait_use = m.captures[0]
+                    .node
+                    .parent()
+                    .and_then(|n| n.parent())
+                    .is_some_and(|n| {
+                        n.kind() == "use_instead_of_clause" || n.kind() == "use_as_clause"
+                    });
+                return Some(if on_constant && in_trait_use {
+                    M2Item::Method(class, constant_name.into())
+                } else if on_constant && constant_name != "class" {
+                    M2Item::Const(class, constant_name.into())
+                } else {
+                    M2Item::Class(class)
+                });
+            }
+        }
+        if m.pattern_index == 3 || m.pattern_index == 4 {
+            let name = m.captures[0].node;
+            if node_at_position(name, pos) {
+                let (namespace, imports) = get_file_scope(tree.root_node(), content);
+                return Some(M2Item::Class(resolve_class_name(
+                    node_text(name, content),
+                    namespace,
+                    &imports,
+                )));
+            }
+        }
+        if m.pattern_index == 5 {
+            let method = m.captures[2].node;
+            if node_at_position(method, pos) {
+                let property = ts::get_node_str(m.captures[1].node, content);
+                let (namespace, imports) = get_file_scope(tree.root_node(), content);
+                if let Some(declared_type) = enclosing_class(method)
+                    .and_then(|class| property_type(class, property, content, namespace, &imports))
+                {
+                    return Some(M2Item::InjectedMethod(
+                        declared_type,
+                        ts::get_node_str(method, content).into(),
+                    ));
+                }
+            }
+        }
+        if m.pattern_index == 2 {
+            let method = m.captures[1].node;
+            if node_at_position(method, pos) {
+                let class = enclosing_class_name(method, tree.root_node(), content)?;
+                return Some(M2Item::Method(
+                    class,
+                    ts::get_node_str(method, content).into(),
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+/// Item of docblock tag under the cursor, data fixture of test or `@see` (`@uses`) reference.
+fn get_doc_tag_item(root: Node, content: &str, pos: Position) -> Option<M2Item> {
+    let line = content.lines().nth(pos.line as usize)?;
+    let cursor = utf16_column_to_byte(line, pos.character);
+    let point = Point {
+        row: pos.line as usize,
+        column: cursor,
+    };
+    let comment = root.descendant_for_point_range(point, point)?;
+    if comment.kind() != "comment" {
+        return None;
+    }
+    if let Some((_, fixture)) = doc_tag_value(line, &DATA_FIXTURE_TAGS, cursor) {
+        return data_fixture_item(fixture);
+    }
+    let (namespace, imports) = get_file_scope(root, content);
+    if let Some((start, type_text)) = doc_tag_value(line, &TYPE_TAGS, cursor) {
+        return doc_type_item(type_text, cursor - start, namespace, &imports);
+    }
+    let (_, reference) = doc_tag_value(line, &REFERENCE_TAGS, cursor)?;
+    doc_reference_item(reference, namespace, &imports)
+}
+
+/// Byte offset in the line of LSP position character (counted in UTF-16 code units).
+fn utf16_column_to_byte(line: &str, character: u32) -> usize {
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        if column >= character as usize {
+            return i;
+        }
+        column += c.len_utf16();
+    }
+    line.len()
+}
+
+/// First word after one of the tags (with its offset in the line), when the cursor is on it.
+fn doc_tag_value<'a>(line: &'a str, tags: &[&str], cursor: usize) -> Option<(usize, &'a str)> {
+    tags.iter().find_map(|tag| {
+        let tag_end = line.find(tag)? + tag.len();
+        let rest = line.get(tag_end..)?;
+        let start = tag_end + rest.len() - rest.trim_start().len();
+        if start == tag_end {
+            return None; // longer tag with the same prefix
+        }
+        let end = line[start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |e| start + e);
+        (start < end && start <= cursor && cursor <= end).then(|| (start, &line[start..end]))
+    })
+}
+
+/// Class of the type (in union, intersection or array of types, like `?Foo|Bar[]`)
+/// under the cursor, builtin types are skipped.
+fn doc_type_item(
+    type_text: &str,
+    cursor: usize,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> Option<M2Item> {
+    let mut start = 0;
+    for part in type_text.split(['|', '&']) {
+        let end = start + part.len();
+        if start <= cursor && cursor <= end {
+            let type_name = part.trim_start_matches('?').trim_end_matches("[]");
+            if type_name.trim_start_matches('\\').is_empty()
+                || type_name.starts_with('$')
+                || !m2::is_part_of_class_name(type_name)
+                || !is_class_type(type_name)
+            {
+                return None;
+            }
+            return Some(M2Item::DocType(resolve_class_name(
+                type_name, namespace, imports,
+            )));
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// Config path of `system.xml` field, other `getValue()` calls (like `DataObject` ones)
+/// take keys without sections and groups.
+fn config_path_item(text: &str) -> Option<M2Item> {
+    let parts = text.split('/').collect::<Vec<_>>();
+    if parts.len() < 3
+        || parts.iter().any(|part| {
+            part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    {
+        return None;
+    }
+    Some(M2Item::ConfigPath(text.into()))
+}
+
+/// Data fixture can be a class (or its static method), module file
+/// (`Vendor_Module::path/to/fixture.php`) or path of fixture file.
+fn data_fixture_item(fixture: &str) -> Option<M2Item> {
+    match fixture.split_once("::") {
+        Some((mod_name, file)) if !mod_name.contains('\\') && mod_name.contains('_') => {
+            Some(M2Item::DataFixture(Some(mod_name.into()), file.into()))
+        }
+        // classes of annotations are always fully qualified
+        Some((class, method)) => Some(M2Item::Method(
+            class.trim_start_matches('\\').into(),
+            method.into(),
+        )),
+        None if fixture.ends_with(".php") => Some(M2Item::DataFixture(None, fixture.into())),
+        None => Some(M2Item::Class(fixture.trim_start_matches('\\').into())),
+    }
+}
+
+/// Class or method (`Foo::bar()` or `Foo::bar`) of `@see` tag, resolved like class names
+/// in the code, urls and other references are skipped.
+fn doc_reference_item(
+    reference: &str,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> Option<M2Item> {
+    let (class, method) = reference
+        .split_once("::")
+        .map_or((reference, None), |(c, m)| {
+            (c, Some(m.trim_end_matches("()")))
+        });
+    if class.trim_start_matches('\\').is_empty() || !m2::is_part_of_class_name(class) {
+        return None;
+    }
+    let class = resolve_class_name(class, namespace, imports);
+    match method {
+        Some(method)
+            if !method.is_empty() && method.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            Some(M2Item::Method(class, method.into()))
+        }
+        Some(_) => None,
+        None => Some(M2Item::Class(class)),
+    }
+}
+
+pub fn get_constructor_param_from_position(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<PHPConstructorParam> {
+    let content = state.get_file(path)?;
+    get_constructor_param_at_pos(content, pos)
+}
+
+fn get_constructor_param_at_pos(content: &str, pos: Position) -> Option<PHPConstructorParam> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let mut param = tree.root_node().descendant_for_point_range(point, point)?;
+    while param.kind() != "simple_parameter" {
+        param = param.parent()?;
+    }
+    let method = param.parent()?.parent()?;
+    if method.kind() != "method_declaration"
+        || method
+            .child_by_field_name("name")
+            .is_none_or(|n| ts::get_node_str(n, content) != "__construct")
+    {
+        return None;
+    }
+    let class = method.parent()?.parent()?;
+    if class.kind() != "class_declaration" {
+        return None;
+    }
+    let variable = children(param)
+        .into_iter()
+        .find(|n| n.kind() == "variable_name")?;
+    let (namespace, imports) = get_file_scope(tree.root_node(), content);
+    let class_name = ts::get_node_str(class.child_by_field_name("name")?, content);
+
+    let type_text = content.get(param.start_byte()..variable.start_byte())?;
+    let declared_type = declared_type(type_text, namespace, &imports);
+
+    Some(PHPConstructorParam {
+        class: resolve_class_name(&format!("namespace\\{class_name}"), namespace, &imports),
+        name: ts::get_node_str(variable, content)
+            .trim_start_matches('$')
+            .into(),
+        declared_type,
+    })
+}
+
+/// Type from the text before parameter (or property) variable, class names are resolved,
+/// builtin, union and intersection types are returned as they are.
+fn declared_type(
+    type_text: &str,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> Option<String> {
+    type_text
+        .split_whitespace()
+        // variadic (`Foo ...$foo`) and by reference (`Foo &$foo`) parameters
+        .map(|part| part.trim_end_matches("...").trim_end_matches('&'))
+        .rfind(|part| !part.is_empty() && !PROMOTION_MODIFIERS.contains(part))
+        .map(|type_name| {
+            let type_name = type_name.trim_start_matches('?');
+            if is_class_type(type_name) {
+                resolve_class_name(type_name, namespace, imports)
+            } else {
+                type_name.into()
+            }
+        })
+}
+
+fn is_class_type(type_name: &str) -> bool {
+    !type_name.contains(['|', '&']) && !BUILTIN_TYPES.contains(&type_name.to_lowercase().as_str())
+}
+
+/// Class type of the property declared in the class body (typed properties are parsed as
+/// errors, so the type is read from the declaration text) or constructor parameter with
+/// the same name (promoted, or assigned to the property by convention).
+fn property_type(
+    class: Node,
+    property: &str,
+    content: &str,
+    namespace: &str,
+    imports: &HashMap<String, String>,
+) -> Option<String> {
+    let variable = format!("${property}");
+    let body = class.child_by_field_name("body")?;
+    let mut declarations = vec![];
+    for member in children(body) {
+        match member.kind() {
+            "property_declaration" => declarations.extend(
+                children(member)
+                    .into_iter()
+                    .filter(|n| n.kind() == "property_element")
+                    .filter_map(|element| {
+                        children(element)
+                            .into_iter()
+                            .find(|n| n.kind() == "variable_name")
+                    })
+                    .map(|name| (member, name)),
+            ),
+            "method_declaration"
+                if member
+                    .child_by_field_name("name")
+                    .is_some_and(|n| ts::get_node_str(n, content) == "__construct") =>
+            {
+                let Some(parameters) = member.child_by_field_name("parameters") else {
+                    continue;
+                };
+                for param in children(parameters) {
+                    if let Some(name) = children(param)
+                        .into_iter()
+                        .find(|n| n.kind() == "variable_name")
+                    {
+                        declarations.push((param, name));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    declarations.into_iter().find_map(|(declaration, name)| {
+        if node_text(name, content).trim() != variable {
+            return None;
+        }
+        let type_text = content.get(declaration.start_byte()..name.start_byte())?;
+        declared_type(type_text, namespace, imports).filter(|t| is_class_type(t))
+    })
+}
+
+/// Fully qualified name of the class (or interface) when position is on its declared name.
+pub fn get_declared_class_from_position(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<String> {
+    let content = state.get_file(path)?;
+    get_class_declarations(content, path)
+        .into_iter()
+        .find(|c| c.location.range.start <= pos && pos <= c.location.range.end)
+        .map(|c| c.fqn)
+}
+
+/// Name of the console command class, set by `$this->setName()` call in its `configure()`
+/// method, or by `$defaultName` static property of the class.
+pub fn get_console_command_name(content: &str, fqn: &str) -> Option<String> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    let name = fqn.rsplit('\\').next()?;
+    let mut nodes = children(tree.root_node());
+    if let Some(body) = nodes
+        .iter()
+        .find(|n| n.kind() == "namespace_definition")
+        .and_then(|n| n.child_by_field_name("body"))
+    {
+        nodes = children(body);
+    }
+    let class = nodes.into_iter().find(|n| {
+        n.kind() == "class_declaration"
+            && n.child_by_field_name("name")
+                .is_some_and(|n| ts::get_node_str(n, content) == name)
+    })?;
+
+    let mut default_name = None;
+    for member in children(class.child_by_field_name("body")?) {
+        match member.kind() {
+            "method_declaration"
+                if member
+                    .child_by_field_name("name")
+                    .is_some_and(|n| ts::get_node_str(n, content) == "configure") =>
+            {
+                if let Some(name) = member
+                    .child_by_field_name("body")
+                    .and_then(|body| set_name_argument(body, content))
+                {
+                    return Some(name);
+                }
+            }
+            "property_declaration" => {
+                for element in children(member) {
+                    let is_default_name = children(element).into_iter().any(|n| {
+                        n.kind() == "variable_name" && node_text(n, content) == "$defaultName"
+                    });
+                    if !is_default_name {
+                        continue;
+                    }
+                    default_name = children(element)
+                        .into_iter()
+                        .find(|n| n.kind() == "property_initializer")
+                        .and_then(|n| string_child(n, content));
+                }
+            }
+            _ => (),
+        }
+    }
+    default_name
+}
+
+/// First string argument of `setName()` call in the node, including chained calls.
+fn set_name_argument(node: Node, content: &str) -> Option<String> {
+    if node.kind() == "member_call_expression"
+        && node
+            .child_by_field_name("name")
+            .is_some_and(|n| ts::get_node_str(n, content) == "setName")
+    {
+        if let Some(name) = node
+            .child_by_field_name("arguments")
+            .and_then(|arguments| string_child(arguments, content))
+        {
+            return Some(name);
+        }
+    }
+    children(node)
+        .into_iter()
+        .find_map(|child| set_name_argument(child, content))
+}
+
+fn string_child(node: Node, content: &str) -> Option<String> {
+    children(node)
+        .into_iter()
+        .find(|n| matches!(n.kind(), "string" | "encapsed_string"))
+        .map(|n| get_string_text(n, content).to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Range inside the quotes of event name, when position is in the first string argument
+/// of `dispatch()` call (like `$this->eventManager->dispatch('event_name', [...])`).
+pub fn get_event_name_range(content: &str, pos: Position) -> Option<Range> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    let query = queries::php_item_from_pos();
+    let mut cursor = QueryCursor::new();
+    let matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+
+    for m in matches {
+        if m.pattern_index != 0 || ts::get_node_str(m.captures[0].node, content) != "dispatch" {
+            continue;
+        }
+        let range = get_range_from_node(m.captures[1].node);
+        if range.start.line != range.end.line || range.end.character < range.start.character + 2 {
+            continue;
+        }
+        let inner = Range {
+            start: Position {
+                line: range.start.line,
+                character: range.start.character + 1,
+            },
+            end: Position {
+                line: range.end.line,
+                character: range.end.character - 1,
+            },
+        };
+        if inner.start <= pos && pos <= inner.end {
+            return Some(inner);
+        }
+    }
+
+    None
+}
+
+/// Class declaration containing the node.
+fn enclosing_class(node: Node) -> Option<Node> {
+    let mut class = node.parent()?;
+    while class.kind() != "class_declaration" {
+        class = class.parent()?;
+    }
+    Some(class)
+}
+
+/// Fully qualified name of the class declaration containing the node.
+fn enclosing_class_name(node: Node, root: Node, content: &str) -> Option<String> {
+    let class = enclosing_class(node)?;
+    let name = ts::get_node_str(class.child_by_field_name("name")?, content);
+    let (namespace, imports) = get_file_scope(root, content);
+    Some(resolve_class_name(
+        &format!("namespace\\{name}"),
+        namespace,
+        &imports,
+    ))
+}
+
+/// Namespace and `use` imports of the file, for resolving class names.
+fn get_file_scope<'a>(root: Node, content: &'a str) -> (&'a str, HashMap<String, String>) {
+    let mut namespace = "";
+    let mut imports = HashMap::new();
+    for node in children(root) {
+        match node.kind() {
+            "namespace_definition" => {
+                namespace = node
+                    .child_by_field_name("name")
+                    .map_or("", |n| ts::get_node_str(n, content));
+            }
+            "namespace_use_declaration" => add_use_imports(node, content, &mut imports),
+            _ => (),
+        }
+    }
+    (namespace, imports)
+}
+
+fn get_string_text<'a>(node: Node, content: &'a str) -> &'a str {
+    node.utf8_text(content.as_bytes())
+        .unwrap_or("")
+        .trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Find range of the nested array key in the returned config array, like `env.php`
+/// (`return ['db' => ['host' => ...]]` for `["db", "host"]`).
+pub fn find_config_key_range(content: &str, keys: &[&str]) -> Option<Range> {
+    let tree = tree_sitter_parsers::parse(content, "php");
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let mut array = root
+        .children(&mut cursor)
+        .find(|n| n.kind() == "return_statement")?
+        .named_child(0)?;
+    let mut range = None;
+    for key in keys {
+        if array.kind() != "array_creation_expression" {
+            return None;
+        }
+        let mut cursor = array.walk();
+        let element = array
+            .named_children(&mut cursor)
+            .filter(|n| n.kind() == "array_element_initializer")
+            .find(|n| {
+                n.child_count() == 3
+                    && n.child(0)
+                        .is_some_and(|k| get_string_text(k, content) == *key)
+            })?;
+        range = Some(get_range_from_node(element.child(0)?));
+        array = element.child(2)?;
+    }
+    range
+}
+
+/// Parse the class (interface or trait) declared in the file, when the file declares
+/// more classes only members of the one with `fqn` name are collected.
+pub fn parse_php_file(file_path: &PathBuf, fqn: &str) -> Option<PHPClass> {
+    let content = std::fs::read_to_string(file_path).ok()?;
     let tree = tree_sitter_parsers::parse(&content, "php");
     let query = queries::php_class();
 
@@ -180,25 +1117,43 @@ pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
     let matches = cursor.matches(query, tree.root_node(), content.as_bytes());
 
     let mut ns: Option<Node> = None;
-    let mut cls: Option<Node> = None;
+    let mut cls: Option<(Node, &str)> = None;
     let mut methods: HashMap<String, PHPMethod> = HashMap::new();
     let mut constants: HashMap<String, PHPConst> = HashMap::new();
+    let fqn = fqn.trim_start_matches('\\');
+    let in_class = |node: Node, cls: Option<(Node, &str)>| {
+        cls.and_then(|(c, _)| c.parent())
+            .is_some_and(|c| c.start_byte() <= node.start_byte() && node.end_byte() <= c.end_byte())
+    };
 
     for m in matches {
         if m.pattern_index == 0 {
             ns = Some(m.captures[0].node);
         }
-        if m.pattern_index == 1 || m.pattern_index == 2 {
-            cls = Some(m.captures[0].node);
+        if m.pattern_index == 1 || m.pattern_index == 2 || m.pattern_index == 5 {
+            let cls_node = m.captures[0].node;
+            let ns_text = ns.map_or("", |n| n.utf8_text(content.as_bytes()).unwrap_or(""));
+            let cls_text = cls_node.utf8_text(content.as_bytes()).unwrap_or("");
+            // the first class wins when none of them has the name, like before
+            let is_match = format!("{ns_text}\\{cls_text}").eq_ignore_ascii_case(fqn);
+            if is_match || cls.is_none() {
+                if is_match && cls.is_some() {
+                    methods.clear();
+                    constants.clear();
+                }
+                cls = Some((cls_node, ns_text));
+            }
         }
         if m.pattern_index == 3 {
             let method_node = m.captures[1].node;
+            if !in_class(method_node, cls) {
+                continue;
+            }
             let method_name = ts::get_node_str(method_node, &content);
             if !method_name.is_empty() {
                 methods.insert(
                     method_name.into(),
                     PHPMethod {
-                        name: method_name.into(),
                         range: get_range_from_node(method_node),
                     },
                 );
@@ -206,12 +1161,14 @@ pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
         }
         if m.pattern_index == 4 {
             let const_node = m.captures[0].node;
+            if !in_class(const_node, cls) {
+                continue;
+            }
             let const_name = const_node.utf8_text(content.as_bytes()).unwrap_or("");
             if !const_name.is_empty() {
                 constants.insert(
                     const_name.into(),
                     PHPConst {
-                        name: const_name.into(),
                         range: get_range_from_node(const_node),
                     },
                 );
@@ -219,13 +1176,10 @@ pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
         }
     }
 
-    if ns.is_none() || cls.is_none() {
+    let (cls_node, ns_text) = cls?;
+    if ns_text.is_empty() {
         return None;
     }
-
-    let ns_node = ns.expect("ns is some");
-    let cls_node = cls.expect("cls is some");
-    let ns_text = ns_node.utf8_text(content.as_bytes()).unwrap_or("");
     let cls_text = cls_node.utf8_text(content.as_bytes()).unwrap_or("");
 
     let fqn = ns_text.to_string() + "\\" + cls_text;
@@ -233,7 +1187,7 @@ pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
         return None;
     }
 
-    let uri = Url::from_file_path(file_path.clone()).expect("Path can not be converted to Url");
+    let uri = m2::path_to_uri(file_path).ok()?;
     let range = Range {
         start: Position {
             line: cls_node.start_position().row as u32,
@@ -245,11 +1199,934 @@ pub fn parse_php_file(file_path: &PathBuf) -> Option<PHPClass> {
         },
     };
 
+    let magic_methods = cls_node
+        .parent()
+        .map(|class| get_magic_methods(class, &content))
+        .unwrap_or_default();
+
     Some(PHPClass {
-        fqn,
         uri,
         range,
         methods,
+        magic_methods,
         constants,
     })
 }
+
+/// Methods from `@method [static] [type] name(...)` tags in the docblock before the class.
+fn get_magic_methods(class: Node, content: &str) -> HashMap<String, PHPMethod> {
+    let mut result = HashMap::new();
+    let mut comment = class.prev_sibling();
+    while let Some(node) = comment.filter(|n| n.kind() == "comment") {
+        comment = node.prev_sibling();
+        let text = node.utf8_text(content.as_bytes()).unwrap_or("");
+        for (i, line) in text.lines().enumerate() {
+            let Some(tag) = line.find("@method") else {
+                continue;
+            };
+            let Some(paren) = line[tag..].find('(').map(|p| p + tag) else {
+                continue;
+            };
+            let name = line[..paren]
+                .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or_default();
+            if name.is_empty() || paren - name.len() <= tag + "@method".len() {
+                continue;
+            }
+            let row = node.start_position().row + i;
+            let offset = if i == 0 {
+                node.start_position().column
+            } else {
+                0
+            };
+            let column = offset + paren - name.len();
+            result.insert(
+                name.into(),
+                PHPMethod {
+                    range: Range {
+                        start: Position {
+                            line: row as u32,
+                            character: column as u32,
+                        },
+                        end: Position {
+                            line: row as u32,
+                            character: (column + name.len()) as u32,
+                        },
+                    },
+                },
+            );
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{m2::M2Area, settings::Settings};
+
+    fn get_test_item(php: &str, path: &str) -> Option<M2Item> {
+        let win_path = format!("c:{}", path.replace('/', "\\"));
+        let mut character = 0;
+        let mut line = 0;
+        for l in php.lines() {
+            if l.contains('|') {
+                character = l.find('|').expect("Test has to have a | character") as u32;
+                break;
+            }
+            line += 1;
+        }
+        let pos = Position { line, character };
+        let uri = PathBuf::from(if cfg!(windows) { &win_path } else { path });
+        get_item_from_pos(&php.replace('|', ""), &uri, pos)
+    }
+
+    #[test]
+    fn test_get_class_declarations() {
+        let content = r#"<?php
+namespace A\B;
+
+use X\Y\Iface;
+use X\Z as Zed;
+use X\{Grp, Sub\Two};
+
+class Foo extends \Base\Cls implements Iface, Zed\Inner, Local, Two {}
+interface Bar extends Grp {}
+"#;
+        let declarations = get_class_declarations(content, &PathBuf::from("/a/Foo.php"));
+        assert_eq!(
+            declarations
+                .iter()
+                .map(|d| (d.fqn.as_str(), d.is_interface, d.parents.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "A\\B\\Foo",
+                    false,
+                    vec![
+                        "Base\\Cls".to_string(),
+                        "X\\Y\\Iface".to_string(),
+                        "X\\Z\\Inner".to_string(),
+                        "A\\B\\Local".to_string(),
+                        "X\\Sub\\Two".to_string(),
+                    ]
+                ),
+                ("A\\B\\Bar", true, vec!["X\\Grp".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_console_command_name() {
+        let content = r#"<?php
+namespace A\Console;
+
+class ItemList extends Command
+{
+    protected static $defaultName = 'item:default';
+
+    protected function configure()
+    {
+        $this->setDescription('List items')->setName('item:list');
+        parent::configure();
+    }
+}
+"#;
+        assert_eq!(
+            get_console_command_name(content, "A\\Console\\ItemList"),
+            Some("item:list".into())
+        );
+        assert_eq!(get_console_command_name(content, "A\\Console\\Other"), None);
+    }
+
+    #[test]
+    fn test_get_console_command_name_from_default_name() {
+        let content = r#"<?php
+namespace A\Console;
+
+class ItemList extends Command
+{
+    protected static $defaultName = 'item:default';
+
+    protected function configure()
+    {
+        $this->setName($this->name);
+    }
+}
+"#;
+        assert_eq!(
+            get_console_command_name(content, "A\\Console\\ItemList"),
+            Some("item:default".into())
+        );
+    }
+
+    #[test]
+    fn test_get_class_declarations_with_traits_and_aliases() {
+        let content = r#"<?php
+namespace A;
+
+use X\Y;
+
+trait Helper {}
+class Foo {}
+class Bar extends Foo {}
+
+class_alias(Bar::class, 'A\OldBar');
+if (!class_exists('A\\Legacy')) {
+    class_alias("\\X\\Y", Y\Legacy::class);
+}
+"#;
+        let declarations = get_class_declarations(content, &PathBuf::from("/a/Foo.php"));
+        assert_eq!(
+            declarations
+                .iter()
+                .map(|d| (
+                    d.fqn.as_str(),
+                    d.alias_of.as_deref(),
+                    d.location.range.start.line
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("A\\Helper", None, 5),
+                ("A\\Foo", None, 6),
+                ("A\\Bar", None, 7),
+                ("A\\OldBar", Some("A\\Bar"), 7),
+                ("X\\Y\\Legacy", Some("X\\Y"), 11),
+            ]
+        );
+    }
+
+    fn get_test_constructor_param(php: &str) -> Option<PHPConstructorParam> {
+        let mut character = 0;
+        let mut line = 0;
+        for l in php.lines() {
+            if l.contains('|') {
+                character = l.find('|').expect("Test has to have a | character") as u32;
+                break;
+            }
+            line += 1;
+        }
+        let pos = Position { line, character };
+        get_constructor_param_at_pos(&php.replace('|', ""), pos)
+    }
+
+    #[test]
+    fn test_get_constructor_param_with_imported_type() {
+        let param = get_test_constructor_param(
+            r#"<?php
+            namespace Some\Module\Model;
+            use Some\Module\Api\ItemInterface;
+            class Item {
+                public function __construct(?ItemInterface $it|em = null, int $count) {}
+            }
+            "#,
+        );
+        assert_eq!(
+            param,
+            Some(PHPConstructorParam {
+                class: "Some\\Module\\Model\\Item".into(),
+                name: "item".into(),
+                declared_type: Some("Some\\Module\\Api\\ItemInterface".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_constructor_param_with_promoted_property() {
+        let param = get_test_constructor_param(
+            r#"<?php
+            namespace Some\Module\Model;
+            class Item {
+                public function __construct(private readonly \Other\Fo|o $foo) {}
+            }
+            "#,
+        );
+        assert_eq!(
+            param.and_then(|p| p.declared_type),
+            Some("Other\\Foo".into())
+        );
+    }
+
+    #[test]
+    fn test_get_constructor_param_with_builtin_type() {
+        let param = get_test_constructor_param(
+            r#"<?php
+            class Item {
+                public function __construct(array $da|ta, $untyped) {}
+            }
+            "#,
+        );
+        assert_eq!(
+            param,
+            Some(PHPConstructorParam {
+                class: "Item".into(),
+                name: "data".into(),
+                declared_type: Some("array".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_constructor_param_of_other_method() {
+        let param = get_test_constructor_param(
+            r#"<?php
+            class Item {
+                public function setData(array $da|ta) {}
+            }
+            "#,
+        );
+        assert_eq!(param, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_method_called_on_promoted_property() {
+        let item = get_test_item(
+            r#"<?php
+            namespace Some\Module\Model;
+            use Some\Module\Api\ItemInterface;
+            class ItemRepository {
+                public function __construct(private ItemInterface $item) {}
+                public function getName() {
+                    return $this->item->getNa|me();
+                }
+            }
+            "#,
+            "/a/b/c/Model/ItemRepository.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::InjectedMethod(
+                "Some\\Module\\Api\\ItemInterface".into(),
+                "getName".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_method_called_on_typed_property() {
+        let item = get_test_item(
+            r#"<?php
+            namespace Some\Module\Model;
+            class ItemRepository {
+                private ?Item $item = null;
+                public function getName() {
+                    return $this->item->getNa|me();
+                }
+            }
+            "#,
+            "/a/b/c/Model/ItemRepository.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::InjectedMethod(
+                "Some\\Module\\Model\\Item".into(),
+                "getName".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_method_called_on_untyped_property() {
+        let item = get_test_item(
+            r#"<?php
+            class ItemRepository {
+                private $item;
+                public function getName() {
+                    return $this->item->getNa|me();
+                }
+            }
+            "#,
+            "/a/b/c/Model/ItemRepository.php",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_data_fixture_of_module() {
+        let item = get_test_item(
+            r#"<?php
+            class ItemTest {
+                /**
+                 * @magentoDataFixture Some_Module::Test/_files/it|em.php
+                 */
+                public function testItem() {}
+            }
+            "#,
+            "/a/b/c/Test/Integration/ItemTest.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::DataFixture(
+                Some("Some_Module".into()),
+                "Test/_files/item.php".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_data_fixture_file() {
+        let item = get_test_item(
+            r#"<?php
+            class ItemTest {
+                /**
+                 * @magentoDataFixtureBeforeTransaction Magento/Catalog/_files/prod|uct.php
+                 */
+                public function testItem() {}
+            }
+            "#,
+            "/a/b/c/Test/Integration/ItemTest.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::DataFixture(
+                None,
+                "Magento/Catalog/_files/product.php".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_data_fixture_class_method() {
+        let item = get_test_item(
+            r#"<?php
+            namespace Some\Module\Test\Integration;
+            class ItemTest {
+                /**
+                 * @magentoApiDataFixture \Some\Module\Test\Fixture\ItemFix|ture::createItem
+                 */
+                public function testItem() {}
+            }
+            "#,
+            "/a/b/c/Test/Integration/ItemTest.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Method(
+                "Some\\Module\\Test\\Fixture\\ItemFixture".into(),
+                "createItem".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_other_docblock_tag() {
+        let item = get_test_item(
+            r#"<?php
+            class ItemTest {
+                /**
+                 * @magentoConfigFixture current_store some/it|em/path 1
+                 */
+                public function testItem() {}
+            }
+            "#,
+            "/a/b/c/Test/Integration/ItemTest.php",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_see_method() {
+        for reference in [
+            "Item::getNa|me()",
+            "Item::getNa|me",
+            "\\Some\\Module\\Model\\Item::getNa|me()",
+        ] {
+            let item = get_test_item(
+                &format!(
+                    r#"<?php
+                    namespace Some\Module\Model;
+                    /**
+                     * @see {reference} for the name
+                     */
+                    class ItemName {{}}
+                    "#
+                ),
+                "/a/b/c/Model/ItemName.php",
+            );
+            assert_eq!(
+                item,
+                Some(M2Item::Method(
+                    "Some\\Module\\Model\\Item".into(),
+                    "getName".into()
+                )),
+                "{reference}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_item_from_pos_doc_types() {
+        for (tag, expected) in [
+            (
+                "@return \\Some\\Module\\Api\\ItemInter|face",
+                Some("Some\\Module\\Api\\ItemInterface"),
+            ),
+            ("@return ?Ite|m", Some("Some\\Module\\Model\\Item")),
+            (
+                "@return ItemInterface&Ite|m[]",
+                Some("Some\\Module\\Model\\Item"),
+            ),
+            ("@return nu|ll", None),
+            (
+                "@param ItemInter|face $item",
+                Some("Some\\Module\\Api\\ItemInterface"),
+            ),
+            ("@param ItemInterface $it|em", None),
+        ] {
+            let item = get_test_item(
+                &format!(
+                    r#"<?php
+                    namespace Some\Module\Model;
+                    use Some\Module\Api\ItemInterface;
+                    class ItemRepository {{
+                        /**
+                         * {tag}
+                         */
+                        public function get($item) {{}}
+                    }}
+                    "#
+                ),
+                "/a/b/c/Model/ItemRepository.php",
+            );
+            assert_eq!(item, expected.map(|c| M2Item::DocType(c.into())), "{tag}");
+        }
+    }
+
+    #[test]
+    fn test_get_item_from_pos_uses_class() {
+        let item = get_test_item(
+            r#"<?php
+            namespace Some\Module\Model;
+            use Some\Module\Api\ItemInterface;
+            class ItemName {
+                /**
+                 * @uses ItemInter|face
+                 */
+                public function getName() {}
+            }
+            "#,
+            "/a/b/c/Model/ItemName.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class("Some\\Module\\Api\\ItemInterface".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_doc_tag_after_multibyte_characters() {
+        let php = r#"<?php
+            namespace Some\Module\Model;
+            use Some\Module\Api\ItemInterface;
+            class ItemName {
+                /**
+                 * Żółć 😀 @uses It|emInterface
+                 */
+                public function getName() {}
+            }
+            "#;
+        let line = php.lines().nth(5).expect("Test has 6th line");
+        let character = line[..line.find('|').expect("Test has a | character")]
+            .encode_utf16()
+            .count() as u32;
+        let item = get_item_from_pos(
+            &php.replace('|', ""),
+            &PathBuf::from("/a/b/c/Model/ItemName.php"),
+            Position { line: 5, character },
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class("Some\\Module\\Api\\ItemInterface".into()))
+        );
+    }
+
+    #[test]
+    fn test_utf16_column_to_byte() {
+        assert_eq!(utf16_column_to_byte("a😀b", 0), 0);
+        assert_eq!(utf16_column_to_byte("a😀b", 3), "a😀".len());
+        assert_eq!(utf16_column_to_byte("a😀b", 10), "a😀b".len());
+    }
+
+    #[test]
+    fn test_get_item_from_pos_see_url() {
+        let item = get_test_item(
+            r#"<?php
+            /**
+             * @see https://developer.adobe.com/commerce/php/develop/|
+             */
+            class ItemName {}
+            "#,
+            "/a/b/c/Model/ItemName.php",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_index_test_classes_only_when_enabled() {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).append(&["tests", "fixtures", "project"]);
+        for index_tests in [false, true] {
+            let mut state = State::new();
+            state.set_settings(Settings {
+                index_tests,
+                ..Settings::default()
+            });
+            let state = state.into_arc();
+            update_index(&state, &path);
+
+            let state = state.lock();
+            assert!(state.get_php_class("Some\\Module\\Model\\Item").is_some());
+            assert_eq!(
+                state
+                    .get_php_class("Some\\Module\\Test\\Fixture\\ItemFixture")
+                    .is_some(),
+                index_tests
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_item_from_pos_method_called_on_this() {
+        let item = get_test_item(
+            r#"<?php
+            namespace Some\Module\Model;
+            class Item {
+                public function getTitle() {
+                    return $this->getLa|bel();
+                }
+            }
+            "#,
+            "/a/b/c/Model/Item.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Method(
+                "Some\\Module\\Model\\Item".into(),
+                "getLabel".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_event_name_range_in_dispatch() {
+        let content = "<?php\n$this->eventManager->dispatch('sales_', ['order' => $order]);\n";
+        assert_eq!(
+            get_event_name_range(content, Position::new(1, 36)),
+            Some(Range {
+                start: Position::new(1, 31),
+                end: Position::new(1, 37),
+            })
+        );
+        assert_eq!(get_event_name_range(content, Position::new(1, 45)), None);
+        assert_eq!(
+            get_event_name_range("<?php\n$this->load('sales_');\n", Position::new(1, 13)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_magic_methods() {
+        let content = r#"<?php
+/**
+ * @method string getLabel()
+ * @method static $this setLabel(string $label)
+ * @param not a method()
+ */
+class Item {}
+"#;
+        let tree = tree_sitter_parsers::parse(content, "php");
+        let class = children(tree.root_node())
+            .into_iter()
+            .find(|n| n.kind() == "class_declaration")
+            .expect("Should have class");
+        let methods = get_magic_methods(class, content);
+
+        let mut names = methods.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["getLabel", "setLabel"]);
+        assert_eq!(
+            methods["setLabel"].range,
+            Range {
+                start: Position {
+                    line: 3,
+                    character: 24
+                },
+                end: Position {
+                    line: 3,
+                    character: 32
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_config_key_range() {
+        let content =
+            "<?php\nreturn [\n    'db' => [\n        \"host\" => 'localhost',\n    ],\n];\n";
+        assert_eq!(
+            find_config_key_range(content, &["db", "host"]),
+            Some(Range {
+                start: Position {
+                    line: 3,
+                    character: 8
+                },
+                end: Position {
+                    line: 3,
+                    character: 14
+                },
+            })
+        );
+        assert_eq!(
+            find_config_key_range(content, &["db", "host", "name"]),
+            None
+        );
+        assert_eq!(find_config_key_range(content, &["host"]), None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_view_file_url_in_template() {
+        let item = get_test_item(
+            r#"<img src="<?= $block->getViewFileUrl('Some_Module::images/lo|go.svg') ?>"/>"#,
+            "/a/view/frontend/templates/logo.phtml",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::WebAsset(
+                Some("Some_Module".into()),
+                "images/logo.svg".into(),
+                M2Area::Frontend
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_scope_config_path() {
+        let item = get_test_item(
+            r#"<?php $this->scopeConfig->getValue('web/secure/base|_url', ScopeInterface::SCOPE_STORE);"#,
+            "/a/Model/Config.php",
+        );
+        assert_eq!(item, Some(M2Item::ConfigPath("web/secure/base_url".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_data_object_get_value() {
+        let item = get_test_item(r#"<?php $item->getValue('na|me');"#, "/a/Model/Config.php");
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_view_file_url_without_module() {
+        let item = get_test_item(
+            r#"<?php $url = $this->assetRepo->createAsset("images/lo|go.svg");"#,
+            "/a/Block/Logo.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::WebAsset(
+                None,
+                "images/logo.svg".into(),
+                M2Area::Base
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_class_constant_as_array_key() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+use Other\Module\Api;
+
+$map = [
+    Api\FooInterface::cl|ass => Foo::class,
+];"#,
+            "/a/Model/Map.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class("Other\\Module\\Api\\FooInterface".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_class_constant_in_namespace() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+$map = [
+    Api\FooInterface::class => F|oo::class,
+    \Fully\Qualified::class,
+];"#,
+            "/a/Model/Map.php",
+        );
+        assert_eq!(item, Some(M2Item::Class("Some\\Module\\Model\\Foo".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_fully_qualified_class_constant() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+$map = [\Fully\Quali|fied::class => 1];"#,
+            "/a/Model/Map.php",
+        );
+        assert_eq!(item, Some(M2Item::Class("Fully\\Qualified".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_class_constant_access() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+use Magento\Framework\App\Filesystem\DirectoryList;
+
+$path = $directoryList->getPath(DirectoryList::VAR_|DIR);"#,
+            "/a/Model/Export.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Const(
+                "Magento\\Framework\\App\\Filesystem\\DirectoryList".into(),
+                "VAR_DIR".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_patch_dependency() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Setup\Patch\Data;
+
+use Other\Module\Setup\Patch\Data\InstallOther;
+
+class UpdateItems implements DataPatchInterface
+{
+    public static function getDependencies()
+    {
+        return [
+            InstallItems::class,
+            InstallOt|her::class,
+        ];
+    }
+}"#,
+            "/a/Setup/Patch/Data/UpdateItems.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class(
+                "Other\\Module\\Setup\\Patch\\Data\\InstallOther".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_trait_used_in_class() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+use Other\Module\Logger;
+
+class Export
+{
+    use Logger\Lo|ggerTrait;
+}"#,
+            "/a/Model/Export.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class("Other\\Module\\Logger\\LoggerTrait".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_trait_after_insteadof() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+class Export
+{
+    use FirstTrait, SecondTrait {
+        FirstTrait::log insteadof SecondTr|ait;
+    }
+}"#,
+            "/a/Model/Export.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Class("Some\\Module\\Model\\SecondTrait".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_trait_method_in_conflict_resolution() {
+        let item = get_test_item(
+            r#"<?php
+namespace Some\Module\Model;
+
+class Export
+{
+    use FirstTrait, SecondTrait {
+        SecondTrait::lo|g as logSecond;
+    }
+}"#,
+            "/a/Model/Export.php",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::Method(
+                "Some\\Module\\Model\\SecondTrait".into(),
+                "log".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_class_usages() {
+        let content = r#"<?php
+namespace Some\Module\Model;
+
+use Other\Module\Api\FooInterface;
+use Other\Module\Api\ImportedOnly;
+
+class Foo implements FooInterface
+{
+    public function __construct(?FooInterface $foo, int $count, self $self) {}
+
+    public function create(): Bar
+    {
+        $map = [\Fully\Qualified::class => Baz::create()];
+        return new Bar();
+    }
+}"#;
+        let tree = tree_sitter_parsers::parse(content, "php");
+        let mut usages = get_class_usages(tree.root_node(), content);
+        usages.sort_unstable();
+        assert_eq!(
+            usages,
+            vec![
+                "Fully\\Qualified",
+                "Other\\Module\\Api\\FooInterface",
+                "Some\\Module\\Model\\Bar",
+                "Some\\Module\\Model\\Bar",
+                "Some\\Module\\Model\\Baz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_string_in_other_method() {
+        let item = get_test_item(
+            r#"<?php $url = $block->getUrl('customer/acc|ount');"#,
+            "/a/Block/Logo.php",
+        );
+        assert_eq!(item, None);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use lsp_types::Diagnostic;
+
+use crate::debounce::Debouncer;
+
+/// How long to wait for more diagnostics of a file before publishing them.
+const PUBLISH_DELAY: Duration = Duration::from_millis(50);
+
+/// What produced diagnostics of the file, each source replaces only its own diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    File,
+    UnusedPreferences,
+}
+
+/// Current diagnostics of files, merged from all sources and published once per file
+/// after short delay, so changes from multiple sources end up in a single notification.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    sources: HashMap<PathBuf, HashMap<Source, Vec<Diagnostic>>>,
+    published: HashMap<PathBuf, Vec<Diagnostic>>,
+    debouncer: Debouncer,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+            published: HashMap::new(),
+            debouncer: Debouncer::new(PUBLISH_DELAY),
+        }
+    }
+
+    /// Replace diagnostics of the file from the source.
+    pub fn set(&mut self, path: &Path, source: Source, diagnostics: Vec<Diagnostic>) {
+        let sources = self.sources.entry(path.to_path_buf()).or_default();
+        if diagnostics.is_empty() {
+            sources.remove(&source);
+        } else {
+            sources.insert(source, diagnostics);
+        }
+        self.schedule(path);
+    }
+
+    /// Remove diagnostics of all files from the source.
+    pub fn clear_source(&mut self, source: Source) {
+        let paths = self
+            .sources
+            .iter()
+            .filter(|(_, sources)| sources.contains_key(&source))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        for path in paths {
+            self.set(&path, source, vec![]);
+        }
+    }
+
+    /// Remove diagnostics of the file from all sources.
+    pub fn clear(&mut self, path: &Path) {
+        self.sources.remove(path);
+        self.schedule(path);
+    }
+
+    /// Time left until diagnostics of the first file are due, `None` if nothing to publish.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.debouncer.timeout()
+    }
+
+    /// Merged diagnostics of files that are due, files with diagnostics same as already
+    /// published (or without any diagnostics published so far) are skipped.
+    pub fn take_due(&mut self) -> Vec<(PathBuf, Vec<Diagnostic>)> {
+        let mut result = vec![];
+        for path in self.debouncer.take_due() {
+            let diagnostics = self.merged(&path);
+            let published = self.published.get(&path);
+            if published.map_or(diagnostics.is_empty(), |p| *p == diagnostics) {
+                continue;
+            }
+            if diagnostics.is_empty() {
+                self.sources.remove(&path);
+                self.published.remove(&path);
+            } else {
+                self.published.insert(path.clone(), diagnostics.clone());
+            }
+            result.push((path, diagnostics));
+        }
+        result.sort_by(|(a, _), (b, _)| a.cmp(b));
+        result
+    }
+
+    fn schedule(&mut self, path: &Path) {
+        if self.merged(path).is_empty() {
+            // clearing is not delayed, so fixed problems disappear right away
+            self.debouncer.schedule_now(path);
+        } else {
+            self.debouncer.schedule(path);
+        }
+    }
+
+    fn merged(&self, path: &Path) -> Vec<Diagnostic> {
+        let Some(sources) = self.sources.get(path) else {
+            return vec![];
+        };
+        let mut sources = sources.iter().collect::<Vec<_>>();
+        sources.sort_by_key(|(source, _)| **source);
+        sources
+            .into_iter()
+            .flat_map(|(_, diagnostics)| diagnostics.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn diagnostic(message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::default(),
+            message: message.into(),
+            ..Diagnostic::default()
+        }
+    }
+
+    fn take_all(diagnostics: &mut Diagnostics) -> Vec<(PathBuf, Vec<Diagnostic>)> {
+        std::thread::sleep(PUBLISH_DELAY);
+        diagnostics.take_due()
+    }
+
+    #[test]
+    fn test_diagnostics_of_sources_are_merged() {
+        let path = Path::new("/a/etc/di.xml");
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.set(path, Source::UnusedPreferences, vec![diagnostic("unused")]);
+        diagnostics.set(path, Source::File, vec![diagnostic("missing")]);
+
+        assert!(diagnostics.take_due().is_empty());
+        assert_eq!(
+            take_all(&mut diagnostics),
+            vec![(
+                path.to_path_buf(),
+                vec![diagnostic("missing"), diagnostic("unused")]
+            )]
+        );
+        assert_eq!(diagnostics.timeout(), None);
+    }
+
+    #[test]
+    fn test_same_diagnostics_are_not_published_again() {
+        let path = Path::new("/a/etc/di.xml");
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.set(path, Source::File, vec![diagnostic("missing")]);
+        assert_eq!(take_all(&mut diagnostics).len(), 1);
+
+        diagnostics.set(path, Source::File, vec![diagnostic("missing")]);
+        assert!(take_all(&mut diagnostics).is_empty());
+    }
+
+    #[test]
+    fn test_clearing_is_published_right_away() {
+        let path = Path::new("/a/etc/di.xml");
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.set(path, Source::File, vec![diagnostic("missing")]);
+        diagnostics.set(path, Source::UnusedPreferences, vec![diagnostic("unused")]);
+        assert_eq!(take_all(&mut diagnostics).len(), 1);
+
+        diagnostics.clear_source(Source::UnusedPreferences);
+        diagnostics.clear(path);
+        assert_eq!(diagnostics.timeout(), Some(Duration::ZERO));
+        assert_eq!(diagnostics.take_due(), vec![(path.to_path_buf(), vec![])]);
+    }
+
+    #[test]
+    fn test_clearing_of_unpublished_file_is_skipped() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.set(Path::new("/a/etc/di.xml"), Source::File, vec![]);
+
+        assert!(diagnostics.take_due().is_empty());
+    }
+}
@@ -2,12 +2,15 @@ use std::sync::OnceLock;
 
 use tree_sitter::{Language, Query};
 
+use crate::logger::log;
+
 pub static JS_REQUIRE_CONFIG: OnceLock<Query> = OnceLock::new();
 pub static JS_ITEM_FROM_POS: OnceLock<Query> = OnceLock::new();
 pub static JS_COMPLETION_ITEM_DEFINITION: OnceLock<Query> = OnceLock::new();
 
 pub static PHP_REGISTRATION: OnceLock<Query> = OnceLock::new();
 pub static PHP_CLASS: OnceLock<Query> = OnceLock::new();
+pub static PHP_ITEM_FROM_POS: OnceLock<Query> = OnceLock::new();
 
 pub static XML_TAG_AT_POS: OnceLock<Query> = OnceLock::new();
 pub static XML_CURRENT_POSITION_PATH: OnceLock<Query> = OnceLock::new();
@@ -100,6 +103,36 @@ pub fn php_class() -> &'static Query {
         ((method_declaration (visibility_modifier)
           @_vis (name) @name) (#eq? @_vis "public"))       ; pattern: 3
         (const_element (name) @const)                      ; pattern: 4
+        (trait_declaration (name) @class)                  ; pattern: 5
+        "#,
+        "php",
+    )
+}
+
+pub fn php_item_from_pos() -> &'static Query {
+    query(
+        &PHP_ITEM_FROM_POS,
+        r#"
+        (member_call_expression
+            name: (name) @method
+            arguments: (arguments . (string) @arg)) ; pattern: 0
+        (class_constant_access_expression
+            [(qualified_name) (name)] @class
+            (name) @const)                         ; pattern: 1
+        ((member_call_expression
+            object: (variable_name (name) @_object)
+            name: (name) @method)
+            (#eq? @_object "this"))                ; pattern: 2
+        (use_declaration
+            [(qualified_name) (name)] @trait)      ; pattern: 3
+        (use_instead_of_clause
+            (name) @trait)                         ; pattern: 4
+        ((member_call_expression
+            object: (member_access_expression
+                object: (variable_name (name) @_object)
+                name: (name) @property)
+            name: (name) @method)
+            (#eq? @_object "this"))                ; pattern: 5
         "#,
         "php",
     )
@@ -161,7 +194,7 @@ pub fn js_item_from_pos() -> &'static Query {
 fn query(static_query: &'static OnceLock<Query>, query: &str, lang: &str) -> &'static Query {
     static_query.get_or_init(|| {
         Query::new(get_language(lang), query)
-            .map_err(|e| eprintln!("Error creating query: {:?}", e))
+            .map_err(|e| log!("Error creating query: {:?}", e))
             .expect("Error creating query")
     })
 }
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{logger::log, parser::Parser};
+
+/// Server settings, passed by the client as `initializationOptions`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// How many `export ... from` statements are followed when resolving TypeScript imports.
+    pub reexport_depth: usize,
+    /// How long (in milliseconds) to wait for more changes of a file before reindexing it.
+    pub reindex_debounce: u64,
+    /// How long (in milliseconds) goto definition can take before empty result is returned.
+    pub request_timeout: u64,
+    /// Parser used for file extension, overrides the defaults (like `"phtml": "php"`).
+    pub parsers: HashMap<String, Parser>,
+    /// Vendors of `vendor/` packages to index (composer package vendor, like `magento`),
+    /// compared case insensitively, all packages are indexed when empty.
+    pub index_vendors: Vec<String>,
+    /// Interfaces (or namespaces, ending with `\`) skipped by unused preferences report,
+    /// for interfaces resolved dynamically, where their usages can not be found.
+    pub preference_allowlist: Vec<String>,
+    /// Index PHP classes of `Test/` directories and `dev/tests`, like data fixtures.
+    pub index_tests: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            reexport_depth: 5,
+            reindex_debounce: 200,
+            request_timeout: 5000,
+            parsers: HashMap::new(),
+            index_vendors: vec![],
+            preference_allowlist: vec!["Magento\\Framework\\".into()],
+            index_tests: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_init_options(options: Option<serde_json::Value>) -> Self {
+        options.map_or_else(Self::default, |options| {
+            serde_json::from_value(options).unwrap_or_else(|err| {
+                log!("Invalid initialization options: {err}");
+                Self::default()
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_init_options_when_empty() {
+        assert_eq!(Settings::from_init_options(None), Settings::default());
+    }
+
+    #[test]
+    fn test_from_init_options_with_partial_options() {
+        let settings = Settings::from_init_options(Some(serde_json::json!({
+            "reexportDepth": 2,
+        })));
+        assert_eq!(settings.reexport_depth, 2);
+        assert_eq!(settings.reindex_debounce, 200);
+    }
+
+    #[test]
+    fn test_from_init_options_with_parsers() {
+        let settings = Settings::from_init_options(Some(serde_json::json!({
+            "parsers": { "mjs": "js", "phtml": "php" },
+        })));
+        assert_eq!(
+            settings.parsers,
+            HashMap::from([("mjs".into(), Parser::Js), ("phtml".into(), Parser::Php)])
+        );
+    }
+
+    #[test]
+    fn test_from_init_options_with_index_vendors() {
+        let settings = Settings::from_init_options(Some(serde_json::json!({
+            "indexVendors": ["Magento", "MyCompany"],
+        })));
+        assert_eq!(settings.index_vendors, vec!["Magento", "MyCompany"]);
+    }
+
+    #[test]
+    fn test_from_init_options_with_index_tests() {
+        let settings = Settings::from_init_options(Some(serde_json::json!({
+            "indexTests": true,
+        })));
+        assert!(settings.index_tests);
+        assert!(!Settings::default().index_tests);
+    }
+
+    #[test]
+    fn test_from_init_options_when_invalid() {
+        let settings = Settings::from_init_options(Some(serde_json::json!({
+            "reexportDepth": "deep",
+        })));
+        assert_eq!(settings, Settings::default());
+    }
+}
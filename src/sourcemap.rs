@@ -0,0 +1,188 @@
+use lsp_types::Position;
+use serde::Deserialize;
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<String>,
+    mappings: String,
+}
+
+/// Segment of the generated line mapped to position in one of the sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping {
+    generated_column: u32,
+    source: usize,
+    line: u32,
+    column: u32,
+}
+
+/// Source map (version 3), only mappings to original positions are kept, names and
+/// index maps (with `sections`) are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    pub source_root: Option<String>,
+    pub sources: Vec<String>,
+    lines: Vec<Vec<Mapping>>,
+}
+
+impl SourceMap {
+    pub fn parse(json: &str) -> Option<Self> {
+        let raw: RawSourceMap = serde_json::from_str(json).ok()?;
+        Some(Self {
+            lines: decode_mappings(&raw.mappings, raw.sources.len())?,
+            source_root: raw.source_root.filter(|root| !root.is_empty()),
+            sources: raw.sources,
+        })
+    }
+
+    /// Source and position in it that generated the position, the closest segment
+    /// starting before the position on the same line is used.
+    pub fn original_position(&self, pos: Position) -> Option<(&str, Position)> {
+        let mapping = self
+            .lines
+            .get(pos.line as usize)?
+            .iter()
+            .take_while(|m| m.generated_column <= pos.character)
+            .last()?;
+        Some((
+            self.sources.get(mapping.source)?,
+            Position {
+                line: mapping.line,
+                character: mapping.column,
+            },
+        ))
+    }
+}
+
+/// Url of `//# sourceMappingURL=` comment (or deprecated `//@` one), last one wins.
+pub fn source_mapping_url(content: &str) -> Option<&str> {
+    content.lines().rev().find_map(|line| {
+        let line = line.trim();
+        line.strip_prefix("//# sourceMappingURL=")
+            .or_else(|| line.strip_prefix("//@ sourceMappingURL="))
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+    })
+}
+
+fn decode_mappings(mappings: &str, sources_count: usize) -> Option<Vec<Vec<Mapping>>> {
+    let mut lines = vec![];
+    // all fields except generated column are relative to the previous segment in the map
+    let (mut source, mut line, mut column) = (0_i64, 0_i64, 0_i64);
+    for generated_line in mappings.split(';') {
+        let mut generated_column = 0_i64;
+        let mut segments = vec![];
+        for segment in generated_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            generated_column += fields.first()?;
+            if fields.len() < 4 {
+                continue;
+            }
+            source += fields[1];
+            line += fields[2];
+            column += fields[3];
+            if source < 0 || source as usize >= sources_count || line < 0 || column < 0 {
+                return None;
+            }
+            segments.push(Mapping {
+                generated_column: u32::try_from(generated_column).ok()?,
+                source: source as usize,
+                line: u32::try_from(line).ok()?,
+                column: u32::try_from(column).ok()?,
+            });
+        }
+        segments.sort_by_key(|m| m.generated_column);
+        lines.push(segments);
+    }
+    Some(lines)
+}
+
+/// Decode base64 VLQ encoded segment to its signed fields.
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    let mut result = vec![];
+    let mut value = 0_i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = BASE64_CHARS.iter().position(|c| *c == byte)? as i64;
+        value += (digit & 0b1_1111) << shift;
+        if digit & 0b10_0000 == 0 {
+            let magnitude = value >> 1;
+            result.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+            if shift > 60 {
+                return None;
+            }
+        }
+    }
+    if shift == 0 {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("AAAA"), Some(vec![0, 0, 0, 0]));
+        assert_eq!(decode_vlq("SAAQD"), Some(vec![9, 0, 0, 8, -1]));
+        assert_eq!(decode_vlq("2HAAA"), Some(vec![123, 0, 0, 0]));
+        assert_eq!(decode_vlq("A!"), None);
+        assert_eq!(decode_vlq("g"), None);
+    }
+
+    #[test]
+    fn test_original_position() {
+        let map = SourceMap::parse(
+            r#"{"version":3,"sources":["a.js","b.js"],"names":[],"mappings":"AAAA,KACE;ACDF,IAAM"}"#,
+        )
+        .expect("Should parse source map");
+
+        assert_eq!(
+            map.original_position(Position::new(0, 3)),
+            Some(("a.js", Position::new(0, 0)))
+        );
+        assert_eq!(
+            map.original_position(Position::new(0, 7)),
+            Some(("a.js", Position::new(1, 2)))
+        );
+        assert_eq!(
+            map.original_position(Position::new(1, 4)),
+            Some(("b.js", Position::new(0, 6)))
+        );
+        assert_eq!(map.original_position(Position::new(2, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_source_map() {
+        assert_eq!(SourceMap::parse("not a map"), None);
+        assert_eq!(
+            SourceMap::parse(r#"{"version":3,"sources":[],"mappings":"AAAA"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_source_mapping_url() {
+        assert_eq!(
+            source_mapping_url("a();\n//# sourceMappingURL=a.min.js.map\n"),
+            Some("a.min.js.map")
+        );
+        assert_eq!(source_mapping_url("a();\n"), None);
+    }
+}
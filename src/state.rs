@@ -1,18 +1,23 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
     sync::Arc,
     thread::{spawn, JoinHandle},
     time::SystemTime,
 };
 
-use lsp_types::Position;
+use lsp_types::{Location, Position, TextDocumentContentChangeEvent};
 use parking_lot::Mutex;
+use serde::Serialize;
 
 use crate::{
     js,
-    m2::{M2Area, M2Item, M2Path},
-    php, xml,
+    logger::log,
+    m2::{self, M2Area, M2Item, M2LayoutElement, M2Path},
+    parser::Parser,
+    php::{self, PHPClassDeclaration},
+    settings::Settings,
+    xml::{self, DiPoolItem, DiValue, EventObserver, ViewImage},
 };
 
 trait HashMapId {
@@ -37,6 +42,19 @@ enum Trackee {
     JsMixin(M2Area, String),
     JsPaths(M2Area, String),
     Themes(M2Area, String),
+    SystemConfig(String),
+    PhpClass(String),
+    LayoutName(M2Area, M2LayoutElement, String),
+    DiPreference(M2Area, String),
+    DiArgument(M2Area, String, String),
+    DiVirtualType(M2Area, String),
+    Event(String),
+    ObserverEvent(String),
+    LayoutBlockClass(String),
+    UiDataSource(String, String),
+    UiComponentFile(String),
+    ViewImage(String, String),
+    ClassUsage(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -48,10 +66,7 @@ impl TrackingList {
     }
 
     pub fn track(&mut self, source_path: &Path, trackee: Trackee) {
-        self.0
-            .entry(source_path.into())
-            .or_insert_with(Vec::new)
-            .push(trackee);
+        self.0.entry(source_path.into()).or_default().push(trackee);
     }
 
     pub fn maybe_track(&mut self, source_path: Option<&PathBuf>, trackee: Trackee) {
@@ -67,9 +82,20 @@ impl TrackingList {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct State {
+    settings: Settings,
     source_file: Option<PathBuf>,
-    track_entities: TrackingList,
     buffers: HashMap<PathBuf, String>,
+    index: Arc<Index>,
+    workspaces: Vec<PathBuf>,
+    index_cancelled: bool,
+}
+
+/// Entities indexed from files of workspaces, shared by clones of the state (like snapshot
+/// of request worker), so cloning is cheap and the index is copied only when it is updated
+/// while a clone still uses it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Index {
+    track_entities: TrackingList,
     modules: Vec<String>,
     module_paths: HashMap<String, PathBuf>,
     front_themes: HashMap<String, PathBuf>,
@@ -77,18 +103,42 @@ pub struct State {
     js_maps: [HashMap<String, String>; 3],
     js_mixins: [HashMap<String, Vec<String>>; 3],
     js_paths: [HashMap<String, String>; 3],
-    workspaces: Vec<PathBuf>,
+    system_config: HashMap<String, Vec<Location>>,
+    /// Declarations by name, more files can declare the same class (like generated copies).
+    php_classes: HashMap<String, Vec<PHPClassDeclaration>>,
+    php_children: HashMap<String, Vec<String>>,
+    class_usages: HashMap<String, usize>,
+    layout_names: [HashMap<(M2LayoutElement, String), Vec<Location>>; 3],
+    di_preferences: [HashMap<String, Vec<DiValue>>; 3],
+    di_arguments: [HashMap<(String, String), Vec<DiValue>>; 3],
+    di_virtual_types: [HashMap<String, Vec<DiValue>>; 3],
+    events: HashMap<String, Vec<EventObserver>>,
+    observer_events: HashMap<String, Vec<(String, Location)>>,
+    ui_data_sources: HashMap<(String, String), Vec<Location>>,
+    ui_component_files: HashMap<String, Vec<Location>>,
+    view_images: HashMap<(String, String), Vec<ViewImage>>,
+    layout_block_classes: HashMap<String, Vec<(M2Area, Location)>>,
 }
 
 #[allow(clippy::module_name_repetitions)]
 pub type ArcState = Arc<Mutex<State>>;
 
-impl State {
-    pub fn new() -> Self {
+/// Number of indexed entities, reported when the index is rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub modules: usize,
+    pub themes: usize,
+    pub classes: usize,
+    pub di_preferences: usize,
+    pub events: usize,
+    pub layout_names: usize,
+}
+
+impl Index {
+    fn new() -> Self {
         Self {
-            source_file: None,
             track_entities: TrackingList::new(),
-            buffers: HashMap::new(),
             modules: vec![],
             module_paths: HashMap::new(),
             front_themes: HashMap::new(),
@@ -96,45 +146,176 @@ impl State {
             js_maps: [HashMap::new(), HashMap::new(), HashMap::new()],
             js_mixins: [HashMap::new(), HashMap::new(), HashMap::new()],
             js_paths: [HashMap::new(), HashMap::new(), HashMap::new()],
+            system_config: HashMap::new(),
+            php_classes: HashMap::new(),
+            php_children: HashMap::new(),
+            class_usages: HashMap::new(),
+            layout_names: [HashMap::new(), HashMap::new(), HashMap::new()],
+            di_preferences: [HashMap::new(), HashMap::new(), HashMap::new()],
+            di_arguments: [HashMap::new(), HashMap::new(), HashMap::new()],
+            di_virtual_types: [HashMap::new(), HashMap::new(), HashMap::new()],
+            events: HashMap::new(),
+            observer_events: HashMap::new(),
+            layout_block_classes: HashMap::new(),
+            ui_data_sources: HashMap::new(),
+            ui_component_files: HashMap::new(),
+            view_images: HashMap::new(),
+        }
+    }
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            settings: Settings::default(),
+            source_file: None,
+            buffers: HashMap::new(),
+            index: Arc::new(Index::new()),
             workspaces: vec![],
+            index_cancelled: false,
         }
     }
 
+    pub const fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    /// Ask background index workers to stop, they check it before indexing next file.
+    pub fn cancel_index(&mut self) {
+        self.index_cancelled = true;
+    }
+
+    pub const fn is_index_cancelled(&self) -> bool {
+        self.index_cancelled
+    }
+
     pub fn set_source_file(&mut self, path: &Path) {
         self.source_file = Some(path.to_owned());
     }
 
     pub fn clear_from_source(&mut self, path: &Path) {
-        if let Some(list) = self.track_entities.untrack(path) {
+        let index = Arc::make_mut(&mut self.index);
+        if let Some(list) = index.track_entities.untrack(path) {
+            let uri = m2::path_to_uri(path).ok();
             for trackee in list {
                 match trackee {
                     Trackee::JsMap(area, name) => {
-                        self.js_maps[area.id()].remove(&name);
+                        index.js_maps[area.id()].remove(&name);
                     }
                     Trackee::JsMixin(area, name) => {
-                        self.js_mixins[area.id()].remove(&name);
+                        index.js_mixins[area.id()].remove(&name);
                     }
                     Trackee::JsPaths(area, name) => {
-                        self.js_paths[area.id()].remove(&name);
+                        index.js_paths[area.id()].remove(&name);
                     }
                     Trackee::Module(module) => {
-                        self.modules.retain(|m| m != &module);
+                        index.modules.retain(|m| m != &module);
                     }
                     Trackee::ModulePath(module) => {
-                        self.module_paths.remove(&module);
+                        index.module_paths.remove(&module);
                     }
                     Trackee::Themes(area, module) => match area {
                         M2Area::Frontend => {
-                            self.front_themes.remove(&module);
+                            index.front_themes.remove(&module);
                         }
                         M2Area::Adminhtml => {
-                            self.admin_themes.remove(&module);
+                            index.admin_themes.remove(&module);
                         }
                         M2Area::Base => {
-                            self.front_themes.remove(&module);
-                            self.admin_themes.remove(&module);
+                            index.front_themes.remove(&module);
+                            index.admin_themes.remove(&module);
                         }
                     },
+                    Trackee::SystemConfig(config_path) => {
+                        if let Some(fields) = index.system_config.get_mut(&config_path) {
+                            fields.retain(|l| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::LayoutName(area, element, name) => {
+                        if let Some(locations) =
+                            index.layout_names[area.id()].get_mut(&(element, name))
+                        {
+                            locations.retain(|l| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::DiPreference(area, class) => {
+                        if let Some(values) = index.di_preferences[area.id()].get_mut(&class) {
+                            values.retain(|v| Some(&v.location.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::DiArgument(area, class, argument) => {
+                        if let Some(values) =
+                            index.di_arguments[area.id()].get_mut(&(class, argument))
+                        {
+                            values.retain(|v| Some(&v.location.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::DiVirtualType(area, name) => {
+                        if let Some(values) = index.di_virtual_types[area.id()].get_mut(&name) {
+                            values.retain(|v| Some(&v.location.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::Event(event) => {
+                        if let Some(observers) = index.events.get_mut(&event) {
+                            observers.retain(|o| Some(&o.location.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::ObserverEvent(class) => {
+                        if let Some(events) = index.observer_events.get_mut(&class) {
+                            events.retain(|(_, l)| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::LayoutBlockClass(class) => {
+                        if let Some(usages) = index.layout_block_classes.get_mut(&class) {
+                            usages.retain(|(_, l)| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::UiDataSource(component, name) => {
+                        if let Some(locations) = index.ui_data_sources.get_mut(&(component, name)) {
+                            locations.retain(|l| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::UiComponentFile(component) => {
+                        if let Some(files) = index.ui_component_files.get_mut(&component) {
+                            files.retain(|l| Some(&l.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::ViewImage(module, id) => {
+                        if let Some(images) = index.view_images.get_mut(&(module, id)) {
+                            images.retain(|i| Some(&i.location.uri) != uri.as_ref());
+                        }
+                    }
+                    Trackee::ClassUsage(class) => {
+                        if let Some(count) = index.class_usages.get_mut(&class) {
+                            *count = count.saturating_sub(1);
+                        }
+                    }
+                    Trackee::PhpClass(fqn) => {
+                        let Some(declarations) = index.php_classes.get_mut(&fqn) else {
+                            continue;
+                        };
+                        let (removed, kept): (Vec<_>, Vec<_>) = declarations
+                            .drain(..)
+                            .partition(|d| Some(&d.location.uri) == uri.as_ref());
+                        for parent in removed.iter().flat_map(|d| &d.parents) {
+                            // other declaration of the class can still have the parent
+                            if kept.iter().any(|d| d.parents.contains(parent)) {
+                                continue;
+                            }
+                            if let Some(children) = index.php_children.get_mut(parent) {
+                                children.retain(|c| c != &fqn);
+                            }
+                        }
+                        if kept.is_empty() {
+                            index.php_classes.remove(&fqn);
+                        } else {
+                            *declarations = kept;
+                        }
+                    }
                 }
             }
         }
@@ -144,24 +325,51 @@ impl State {
     where
         S: Into<String>,
     {
-        let content = content.into();
+        self.buffers.insert(path.to_owned(), content.into());
+        self.reindex_file(path);
+    }
+
+    /// Apply changes from `textDocument/didChange` to the buffer of opened file,
+    /// change without a range replaces whole content. Index is not updated,
+    /// call `reindex_file` when changes settle down.
+    ///
+    /// Returns `false` when the file is not opened, changes are ignored then.
+    pub fn change_file(&mut self, path: &Path, changes: &[TextDocumentContentChangeEvent]) -> bool {
+        let Some(content) = self.buffers.get_mut(path) else {
+            return false;
+        };
+        for change in changes {
+            apply_content_change(content, change);
+        }
+        true
+    }
+
+    /// Update index entries coming from the file, uses opened buffer if there is one.
+    pub fn reindex_file(&mut self, path: &Path) {
+        let content = match self.buffers.get(path) {
+            Some(content) => content.clone(),
+            None => std::fs::read_to_string(path).unwrap_or_default(),
+        };
         self.clear_from_source(path);
         js::maybe_index_file(self, &content, &path.to_owned());
         php::maybe_index_file(self, &content, &path.to_owned());
-
-        self.buffers.insert(path.to_owned(), content);
+        xml::maybe_index_file(self, &content, &path.to_owned());
     }
 
     pub fn get_file(&self, path: &PathBuf) -> Option<&String> {
         self.buffers.get(path)
     }
 
+    pub fn get_open_files(&self) -> Vec<PathBuf> {
+        self.buffers.keys().cloned().collect()
+    }
+
     pub fn del_file(&mut self, path: &PathBuf) {
         self.buffers.remove(path);
     }
 
     pub fn get_modules(&self) -> Vec<String> {
-        let mut modules = self.modules.clone();
+        let mut modules = self.index.modules.clone();
         modules.sort_unstable();
         modules.dedup();
         modules
@@ -174,15 +382,31 @@ impl State {
             .collect()
     }
 
+    pub fn module_paths(&self) -> Vec<PathBuf> {
+        self.index.module_paths.values().cloned().collect()
+    }
+
     pub fn get_module_path(&self, module: &str) -> Option<PathBuf> {
-        self.module_paths.get(module).cloned()
+        self.index.module_paths.get(module).cloned()
+    }
+
+    /// Find module that given file belongs to, returns module name and its path.
+    pub fn module_for_path(&self, path: &Path) -> Option<(String, PathBuf)> {
+        self.index
+            .modules
+            .iter()
+            .filter_map(|m| Some((m.clone(), self.index.module_paths.get(m)?.clone())))
+            .filter(|(_, mod_path)| path.starts_with(mod_path))
+            .max_by_key(|(_, mod_path)| mod_path.components().count())
     }
 
     pub fn add_module(&mut self, module: &str) -> &mut Self {
-        self.track_entities
+        let index = Arc::make_mut(&mut self.index);
+        index
+            .track_entities
             .maybe_track(self.source_file.as_ref(), Trackee::Module(module.into()));
 
-        self.modules.push(module.into());
+        index.modules.push(module.into());
         self
     }
 
@@ -190,13 +414,14 @@ impl State {
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let module = module.into();
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::ModulePath(module.clone()),
         );
 
-        self.module_paths.insert(module, path);
+        index.module_paths.insert(module, path);
         self
     }
 
@@ -204,34 +429,36 @@ impl State {
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let name = name.into();
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::Themes(M2Area::Adminhtml, name.clone()),
         );
 
-        self.admin_themes.insert(name, path);
+        index.admin_themes.insert(name, path);
     }
 
     pub fn add_front_theme_path<S>(&mut self, name: S, path: PathBuf)
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let name = name.into();
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::Themes(M2Area::Frontend, name.clone()),
         );
 
-        self.front_themes.insert(name, path);
+        index.front_themes.insert(name, path);
     }
 
     pub fn get_component_map(&self, name: &str, area: &M2Area) -> Option<&String> {
-        self.js_maps[area.id()].get(name)
+        self.index.js_maps[area.id()].get(name)
     }
 
     pub fn get_component_maps_for_area(&self, area: &M2Area) -> Vec<String> {
-        self.js_maps[area.id()]
+        self.index.js_maps[area.id()]
             .keys()
             .map(ToString::to_string)
             .collect()
@@ -241,30 +468,32 @@ impl State {
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let name = name.into();
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::JsMap(area.clone(), name.clone()),
         );
 
-        self.js_maps[area.id()].insert(name, val.into());
+        index.js_maps[area.id()].insert(name, val.into());
     }
 
     pub fn add_component_mixin<S>(&mut self, name: S, val: S, area: &M2Area)
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let name = name.into();
         let val = val.into();
 
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::JsMixin(area.clone(), name.clone()),
         );
 
-        self.js_mixins[area.id()]
+        index.js_mixins[area.id()]
             .entry(name)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(val);
     }
 
@@ -273,7 +502,7 @@ impl State {
         S: Into<String>,
     {
         let empty_path = Path::new("");
-        self.js_mixins[area.id()]
+        self.index.js_mixins[area.id()]
             .get(&name.into())
             .unwrap_or(&vec![])
             .iter()
@@ -285,46 +514,525 @@ impl State {
     where
         S: Into<String>,
     {
+        let index = Arc::make_mut(&mut self.index);
         let name = name.into();
-        self.track_entities.maybe_track(
+        index.track_entities.maybe_track(
             self.source_file.as_ref(),
             Trackee::JsPaths(area.clone(), name.clone()),
         );
 
-        self.js_paths[area.id()].insert(name, val.into());
+        index.js_paths[area.id()].insert(name, val.into());
     }
 
     pub fn get_component_path(&self, name: &str, area: &M2Area) -> Option<&String> {
-        self.js_paths[area.id()].get(name)
+        self.index.js_paths[area.id()].get(name)
     }
 
     pub fn get_component_paths_for_area(&self, area: &M2Area) -> Vec<String> {
-        self.js_paths[area.id()]
+        self.index.js_paths[area.id()]
             .keys()
             .map(ToString::to_string)
             .collect()
     }
 
+    pub fn add_system_config_field<S>(&mut self, config_path: S, location: Location)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let config_path = config_path.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::SystemConfig(config_path.clone()),
+        );
+
+        index
+            .system_config
+            .entry(config_path)
+            .or_default()
+            .push(location);
+    }
+
+    pub fn get_system_config_fields(&self, config_path: &str) -> Vec<Location> {
+        self.index
+            .system_config
+            .get(config_path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Count the file (current source file) as using the class.
+    pub fn add_class_usage<S>(&mut self, class: S)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let class = class.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::ClassUsage(class.clone()),
+        );
+
+        *index.class_usages.entry(class).or_default() += 1;
+    }
+
+    /// Number of indexed PHP files using the class, or injecting it by `di.xml` object
+    /// argument or virtual type. XML references (`di.xml` type names and plugins, layout
+    /// blocks, layout and UI component object arguments, `webapi.xml` services) count as
+    /// usages as well.
+    pub fn count_class_usages(&self, class: &str) -> usize {
+        let di_usages = self
+            .index
+            .di_arguments
+            .iter()
+            .flat_map(HashMap::values)
+            .flatten()
+            .filter(|argument| {
+                argument.xsi_type.as_deref() == Some("object") && argument.value == class
+            })
+            .count()
+            + self
+                .index
+                .di_virtual_types
+                .iter()
+                .flat_map(HashMap::values)
+                .flatten()
+                .filter(|virtual_type| virtual_type.value == class)
+                .count();
+        self.index
+            .class_usages
+            .get(class)
+            .copied()
+            .unwrap_or_default()
+            + di_usages
+    }
+
+    /// `di.xml` preferences of all areas, with the class (or interface) they are for.
+    pub fn list_di_preferences(&self) -> Vec<(&String, &DiValue)> {
+        self.index
+            .di_preferences
+            .iter()
+            .flat_map(|preferences| preferences.iter())
+            .flat_map(|(class, values)| values.iter().map(move |value| (class, value)))
+            .collect()
+    }
+
+    pub fn add_php_class(&mut self, declaration: PHPClassDeclaration) {
+        let index = Arc::make_mut(&mut self.index);
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::PhpClass(declaration.fqn.clone()),
+        );
+
+        for parent in &declaration.parents {
+            let children = index.php_children.entry(parent.clone()).or_default();
+            if !children.contains(&declaration.fqn) {
+                children.push(declaration.fqn.clone());
+            }
+        }
+        index
+            .php_classes
+            .entry(declaration.fqn.clone())
+            .or_default()
+            .push(declaration);
+    }
+
+    /// Declaration of the class, the last indexed one when more files declare it.
+    pub fn get_php_class(&self, fqn: &str) -> Option<&PHPClassDeclaration> {
+        self.index.php_classes.get(fqn)?.last()
+    }
+
+    /// All known classes that extend or implement given class or interface, directly
+    /// or through their parents, sorted by name.
+    pub fn get_php_implementors(&self, fqn: &str) -> Vec<&PHPClassDeclaration> {
+        let mut visited = HashSet::new();
+        let mut queue = vec![fqn.trim_start_matches('\\')];
+        let mut result = vec![];
+        while let Some(current) = queue.pop() {
+            for child in self.index.php_children.get(current).into_iter().flatten() {
+                if !visited.insert(child.as_str()) {
+                    continue;
+                }
+                queue.push(child);
+                if let Some(declaration) = self.get_php_class(child) {
+                    if !declaration.is_interface {
+                        result.push(declaration);
+                    }
+                }
+            }
+        }
+        result.sort_unstable_by(|a, b| a.fqn.cmp(&b.fqn));
+        result
+    }
+
+    pub fn add_layout_name<S>(
+        &mut self,
+        area: &M2Area,
+        element: M2LayoutElement,
+        name: S,
+        location: Location,
+    ) where
+        S: Into<String>,
+    {
+        let name = name.into();
+        let index = Arc::make_mut(&mut self.index);
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::LayoutName(area.clone(), element, name.clone()),
+        );
+
+        index.layout_names[area.id()]
+            .entry((element, name))
+            .or_default()
+            .push(location);
+    }
+
+    /// Locations where layout block or container is defined, for `Base` area all areas
+    /// are checked, otherwise given area and `Base`.
+    pub fn get_layout_name_locations(
+        &self,
+        area: &M2Area,
+        element: M2LayoutElement,
+        name: &str,
+    ) -> Vec<Location> {
+        let key = (element, name.to_string());
+        layout_areas(area)
+            .iter()
+            .filter_map(|area| self.index.layout_names[area.id()].get(&key))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Names of layout blocks (or containers) visible in the area, the same way as in
+    /// `get_layout_name_locations`, with the first location each name is defined at.
+    pub fn get_layout_names(
+        &self,
+        area: &M2Area,
+        element: M2LayoutElement,
+    ) -> Vec<(String, Location)> {
+        let mut names: HashMap<&String, &Location> = HashMap::new();
+        for area in layout_areas(area) {
+            for ((el, name), locations) in &self.index.layout_names[area.id()] {
+                if *el != element {
+                    continue;
+                }
+                if let Some(location) = locations.first() {
+                    names.entry(name).or_insert(location);
+                }
+            }
+        }
+        let mut result: Vec<(String, Location)> = names
+            .into_iter()
+            .map(|(name, location)| (name.clone(), location.clone()))
+            .collect();
+        result.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
+    pub fn add_ui_component_file<S>(&mut self, component: S, location: Location)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let component = component.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::UiComponentFile(component.clone()),
+        );
+
+        index
+            .ui_component_files
+            .entry(component)
+            .or_default()
+            .push(location);
+    }
+
+    /// Indexed `ui_component/<component>.xml` files of all modules.
+    pub fn get_ui_component_files(&self, component: &str) -> &[Location] {
+        self.index
+            .ui_component_files
+            .get(component)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add_view_image(&mut self, image: ViewImage) {
+        let index = Arc::make_mut(&mut self.index);
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::ViewImage(image.module.clone(), image.id.clone()),
+        );
+
+        index
+            .view_images
+            .entry((image.module.clone(), image.id.clone()))
+            .or_default()
+            .push(image);
+    }
+
+    /// Configurations of the module image in all indexed `etc/view.xml` files.
+    pub fn get_view_images(&self, module: &str, id: &str) -> &[ViewImage] {
+        self.index
+            .view_images
+            .get(&(module.to_string(), id.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add_ui_data_source<S>(&mut self, component: S, name: S, location: Location)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let key = (component.into(), name.into());
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::UiDataSource(key.0.clone(), key.1.clone()),
+        );
+
+        index.ui_data_sources.entry(key).or_default().push(location);
+    }
+
+    /// Declarations of `<dataSource/>` (and its `<dataProvider/>`) in all files of the UI
+    /// component.
+    pub fn get_ui_data_sources(&self, component: &str, name: &str) -> &[Location] {
+        self.index
+            .ui_data_sources
+            .get(&(component.to_string(), name.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add_di_preference<S>(&mut self, area: &M2Area, class: S, preference: DiValue)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let class = class.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::DiPreference(area.clone(), class.clone()),
+        );
+
+        index.di_preferences[area.id()]
+            .entry(class)
+            .or_default()
+            .push(preference);
+    }
+
+    /// Follow preference chain of the class (or interface) to the class DI would create,
+    /// area preferences take precedence over global (`Base`) ones.
+    pub fn resolve_di_preference(&self, area: &M2Area, class: &str) -> Option<String> {
+        follow_di_chain(&self.index.di_preferences, area, class)
+    }
+
+    pub fn add_di_virtual_type<S>(&mut self, area: &M2Area, name: S, virtual_type: DiValue)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let name = name.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::DiVirtualType(area.clone(), name.clone()),
+        );
+
+        index.di_virtual_types[area.id()]
+            .entry(name)
+            .or_default()
+            .push(virtual_type);
+    }
+
+    /// Class backing the virtual type (through other virtual types), `None` for classes.
+    pub fn resolve_di_virtual_type(&self, area: &M2Area, name: &str) -> Option<String> {
+        follow_di_chain(&self.index.di_virtual_types, area, name)
+    }
+
+    /// Declarations (`name` attributes) of the virtual type in the area and global `di.xml`.
+    pub fn get_di_virtual_type_locations(&self, area: &M2Area, name: &str) -> Vec<Location> {
+        let name = name.trim_start_matches('\\');
+        let mut areas = vec![area];
+        if *area != M2Area::Base {
+            areas.push(&M2Area::Base);
+        }
+        areas
+            .into_iter()
+            .filter_map(|area| self.index.di_virtual_types[area.id()].get(name))
+            .flatten()
+            .map(|virtual_type| virtual_type.location.clone())
+            .collect()
+    }
+
+    pub fn add_di_argument<S>(&mut self, area: &M2Area, class: S, argument: S, value: DiValue)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let class = class.into();
+        let argument = argument.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::DiArgument(area.clone(), class.clone(), argument.clone()),
+        );
+
+        index.di_arguments[area.id()]
+            .entry((class, argument))
+            .or_default()
+            .push(value);
+    }
+
+    /// Arguments declared for constructor parameter of the class in the area.
+    pub fn get_di_arguments(&self, area: &M2Area, class: &str, argument: &str) -> &[DiValue] {
+        self.index.di_arguments[area.id()]
+            .get(&(class.to_string(), argument.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// `xsi:type="object"` items of the type array arguments, merged like Magento does it:
+    /// items of the area replace global ones with the same key (like `argument/item`).
+    pub fn get_di_pool_items(&self, area: &M2Area, class: &str) -> Vec<DiPoolItem> {
+        let mut areas = vec![&M2Area::Base];
+        if *area != M2Area::Base {
+            areas.push(area);
+        }
+        let mut items = BTreeMap::new();
+        for area in areas {
+            for ((item_class, key), values) in &self.index.di_arguments[area.id()] {
+                if item_class != class || !key.contains('/') {
+                    continue;
+                }
+                if let Some(value) = values.last() {
+                    items.insert(key.clone(), value.value.clone());
+                }
+            }
+        }
+        items
+            .into_iter()
+            .map(|(key, class)| DiPoolItem { key, class })
+            .collect()
+    }
+
+    pub fn add_layout_block_class<S>(&mut self, area: &M2Area, class: S, location: Location)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let class = class.into();
+        index.track_entities.maybe_track(
+            self.source_file.as_ref(),
+            Trackee::LayoutBlockClass(class.clone()),
+        );
+
+        index
+            .layout_block_classes
+            .entry(class)
+            .or_default()
+            .push((area.clone(), location));
+    }
+
+    /// Layout `<block class=""/>` attributes instantiating the class, with area of the layout.
+    pub fn get_layout_block_usages(&self, class: &str) -> &[(M2Area, Location)] {
+        self.index
+            .layout_block_classes
+            .get(class.trim_start_matches('\\'))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add_event_observer<S>(&mut self, event: S, observer: EventObserver)
+    where
+        S: Into<String>,
+    {
+        let index = Arc::make_mut(&mut self.index);
+        let event = event.into();
+        index
+            .track_entities
+            .maybe_track(self.source_file.as_ref(), Trackee::Event(event.clone()));
+
+        let class = observer.instance.trim_start_matches('\\');
+        if !class.is_empty() {
+            index.track_entities.maybe_track(
+                self.source_file.as_ref(),
+                Trackee::ObserverEvent(class.into()),
+            );
+            index
+                .observer_events
+                .entry(class.into())
+                .or_default()
+                .push((event.clone(), observer.location.clone()));
+        }
+
+        index.events.entry(event).or_default().push(observer);
+    }
+
+    /// Events observed by the class, with `<observer/>` declarations, sorted by event name.
+    pub fn get_observer_events(&self, class: &str) -> Vec<(String, Location)> {
+        let mut events = self
+            .index
+            .observer_events
+            .get(class.trim_start_matches('\\'))
+            .cloned()
+            .unwrap_or_default();
+        events.sort_by(|(a, a_location), (b, b_location)| {
+            (a, a_location.uri.as_str(), a_location.range.start).cmp(&(
+                b,
+                b_location.uri.as_str(),
+                b_location.range.start,
+            ))
+        });
+        events
+    }
+
+    pub fn get_event_observers(&self, event: &str) -> &[EventObserver] {
+        self.index.events.get(event).map_or(&[], Vec::as_slice)
+    }
+
+    /// Names of events with at least one observer declared in indexed `events.xml`.
+    pub fn get_event_names(&self) -> Vec<&str> {
+        self.index
+            .events
+            .iter()
+            .filter(|(_, observers)| !observers.is_empty())
+            .map(|(event, _)| event.as_str())
+            .collect()
+    }
+
     pub fn list_front_themes_paths(&self) -> Vec<&PathBuf> {
-        self.front_themes.values().collect::<Vec<&PathBuf>>()
+        self.index.front_themes.values().collect::<Vec<&PathBuf>>()
     }
 
     pub fn list_admin_themes_paths(&self) -> Vec<&PathBuf> {
-        self.admin_themes.values().collect::<Vec<&PathBuf>>()
+        self.index.admin_themes.values().collect::<Vec<&PathBuf>>()
     }
 
     pub fn list_themes_paths(&self, area: &M2Area) -> Vec<&PathBuf> {
         match area {
             M2Area::Base => self
+                .index
                 .admin_themes
                 .values()
-                .chain(self.front_themes.values())
+                .chain(self.index.front_themes.values())
                 .collect::<Vec<&PathBuf>>(),
-            M2Area::Adminhtml => self.admin_themes.values().collect::<Vec<&PathBuf>>(),
-            M2Area::Frontend => self.front_themes.values().collect::<Vec<&PathBuf>>(),
+            M2Area::Adminhtml => self.index.admin_themes.values().collect::<Vec<&PathBuf>>(),
+            M2Area::Frontend => self.index.front_themes.values().collect::<Vec<&PathBuf>>(),
         }
     }
 
+    /// Name (like `frontend/Vendor/theme`) and directory of registered theme by name.
+    pub fn get_theme(&self, name: &str) -> Option<(&String, &PathBuf)> {
+        self.index
+            .front_themes
+            .get_key_value(name)
+            .or_else(|| self.index.admin_themes.get_key_value(name))
+    }
+
+    /// Name and directory of registered theme the file belongs to.
+    pub fn get_theme_of_path(&self, path: &Path) -> Option<(&String, &PathBuf)> {
+        self.index
+            .front_themes
+            .iter()
+            .chain(self.index.admin_themes.iter())
+            .filter(|(_, theme_path)| path.starts_with(theme_path))
+            .max_by_key(|(_, theme_path)| theme_path.components().count())
+    }
+
     pub fn workspace_paths(&self) -> Vec<PathBuf> {
         self.workspaces.clone()
     }
@@ -337,18 +1045,64 @@ impl State {
         self.workspaces.contains(&path.to_path_buf())
     }
 
+    pub fn get_parser(&self, path: &Path) -> Option<Parser> {
+        Parser::for_extension(&path.to_path_buf().get_ext(), &self.settings.parsers)
+    }
+
     pub fn get_item_from_position(&self, path: &PathBuf, pos: Position) -> Option<M2Item> {
-        match path.get_ext().as_str() {
-            "js" => js::get_item_from_position(self, path, pos),
-            "xml" => xml::get_item_from_position(self, path, pos),
-            _ => None,
-        }
+        self.get_parser(path)?
+            .get_item_from_position(self, path, pos)
     }
 
     pub fn into_arc(self) -> ArcState {
         Arc::new(Mutex::new(self))
     }
 
+    /// Start building fresh index of the same workspaces from files on disk, the current
+    /// index is not touched, so it can be used until the new one is swapped in with
+    /// `replace_index` (after all returned threads are done).
+    pub fn rebuild_index(arc_state: &ArcState) -> (ArcState, Vec<JoinHandle<()>>) {
+        let (settings, workspaces) = {
+            let state = arc_state.lock();
+            (state.settings.clone(), state.workspaces.clone())
+        };
+        let mut fresh = Self::new();
+        fresh.set_settings(settings);
+        let fresh = fresh.into_arc();
+        let threads = workspaces
+            .iter()
+            .flat_map(|path| Self::update_index(&fresh, path))
+            .collect();
+        (fresh, threads)
+    }
+
+    /// Replace the index with rebuilt one, opened files are indexed again from their
+    /// buffers, as they can have unsaved changes.
+    pub fn replace_index(&mut self, mut fresh: Self) {
+        for (path, content) in std::mem::take(&mut self.buffers) {
+            fresh.set_file(&path, content);
+        }
+        fresh.index_cancelled = self.index_cancelled;
+        *self = fresh;
+    }
+
+    pub fn index_stats(&self) -> IndexStats {
+        IndexStats {
+            modules: self.get_modules().len(),
+            themes: self.index.front_themes.len() + self.index.admin_themes.len(),
+            classes: self.index.php_classes.len(),
+            di_preferences: self.index.di_preferences.iter().map(HashMap::len).sum(),
+            events: self.get_event_names().len(),
+            layout_names: self
+                .index
+                .layout_names
+                .iter()
+                .flat_map(HashMap::values)
+                .filter(|locations| !locations.is_empty())
+                .count(),
+        }
+    }
+
     pub fn update_index(arc_state: &ArcState, path: &Path) -> Vec<JoinHandle<()>> {
         let mut state = arc_state.lock();
         if state.has_workspace_path(path) {
@@ -358,6 +1112,7 @@ impl State {
             vec![
                 spawn_index(arc_state, path, php::update_index, "PHP Indexing"),
                 spawn_index(arc_state, path, js::update_index, "JS Indexing"),
+                spawn_index(arc_state, path, xml::update_index, "XML Indexing"),
             ]
         }
     }
@@ -382,6 +1137,71 @@ impl State {
     }
 }
 
+fn apply_content_change(content: &mut String, change: &TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = position_to_offset(content, range.start);
+            let end = position_to_offset(content, range.end).max(start);
+            content.replace_range(start..end, &change.text);
+        }
+        None => content.clone_from(&change.text),
+    }
+}
+
+/// Convert LSP position (with UTF-16 based character offset) to byte offset in the content.
+fn position_to_offset(content: &str, pos: Position) -> usize {
+    let mut offset = 0;
+    for (line_number, line) in content.split_inclusive('\n').enumerate() {
+        if line_number == pos.line as usize {
+            let mut character = 0;
+            for (i, c) in line.char_indices() {
+                if character >= pos.character as usize || c == '\n' || c == '\r' {
+                    return offset + i;
+                }
+                character += c.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+    content.len()
+}
+
+/// Areas which layout names are visible in the area, `Base` sees names of all areas.
+fn layout_areas(area: &M2Area) -> Vec<M2Area> {
+    match area {
+        M2Area::Base => vec![M2Area::Frontend, M2Area::Adminhtml, M2Area::Base],
+        _ => vec![area.clone(), M2Area::Base],
+    }
+}
+
+/// Follow `di.xml` declarations (last one wins) from the name, area declarations take
+/// precedence over global ones. Returns `None` when nothing is declared for the name.
+fn follow_di_chain(
+    maps: &[HashMap<String, Vec<DiValue>>; 3],
+    area: &M2Area,
+    name: &str,
+) -> Option<String> {
+    let name = name.trim_start_matches('\\');
+    let mut current = name.to_string();
+    let mut visited = HashSet::new();
+    while visited.insert(current.clone()) {
+        let next = [area, &M2Area::Base]
+            .into_iter()
+            .find_map(|area| maps[area.id()].get(&current)?.last())
+            .map(|v| v.value.clone());
+        match next {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    if current == name {
+        None
+    } else {
+        Some(current)
+    }
+}
+
 fn spawn_index(
     state: &ArcState,
     path: &Path,
@@ -393,12 +1213,343 @@ fn spawn_index(
     let msg = msg.to_owned();
 
     spawn(move || {
-        eprintln!("Start {}", msg);
+        log!("Start {}", msg);
         let index_start = SystemTime::now();
         callback(&state, &path);
         index_start.elapsed().map_or_else(
-            |_| eprintln!("{} done", msg),
-            |d| eprintln!("{} done in {:?}", msg, d),
+            |_| log!("{} done", msg),
+            |d| log!("{} done in {:?}", msg, d),
         );
     })
 }
+
+#[cfg(test)]
+mod test {
+    use lsp_types::Range;
+
+    use super::*;
+    use crate::m2::M2Uri;
+
+    fn change(
+        range: Option<((u32, u32), (u32, u32))>,
+        text: &str,
+    ) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: range.map(|(start, end)| Range {
+                start: Position {
+                    line: start.0,
+                    character: start.1,
+                },
+                end: Position {
+                    line: end.0,
+                    character: end.1,
+                },
+            }),
+            range_length: None,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_change_file_with_incremental_changes() {
+        let path = PathBuf::from("/a/etc/di.xml");
+        let mut state = State::new();
+        state.set_file(&path, "<config>\n    <type/>\n</config>\n");
+        state.change_file(
+            &path,
+            &[
+                change(Some(((1, 5), (1, 9))), "preference"),
+                change(Some(((2, 9), (2, 9))), "\n"),
+                change(Some(((0, 0), (0, 0))), "<?xml?>"),
+            ],
+        );
+        assert_eq!(
+            state.get_file(&path).map(String::as_str),
+            Some("<?xml?><config>\n    <preference/>\n</config>\n\n")
+        );
+    }
+
+    #[test]
+    fn test_change_file_updates_index_only_after_reindex() {
+        let path = PathBuf::from("/a/app/code/Some/Module/registration.php");
+        let registration = |name| {
+            format!("<?php\n\\Magento\\Framework\\Component\\ComponentRegistrar::register(\\Magento\\Framework\\Component\\ComponentRegistrar::MODULE, '{name}', __DIR__);\n")
+        };
+        let mut state = State::new();
+        state.set_file(&path, registration("Some_Module"));
+        state.change_file(&path, &[change(None, &registration("Other_Module"))]);
+        assert_eq!(state.get_modules(), vec!["Some_Module"]);
+
+        state.reindex_file(&path);
+        assert_eq!(state.get_modules(), vec!["Other_Module"]);
+    }
+
+    #[test]
+    fn test_change_file_with_full_change() {
+        let path = PathBuf::from("/a/etc/di.xml");
+        let mut state = State::new();
+        state.set_file(&path, "<config/>");
+        state.change_file(&path, &[change(None, "<config></config>")]);
+        assert_eq!(
+            state.get_file(&path).map(String::as_str),
+            Some("<config></config>")
+        );
+    }
+
+    #[test]
+    fn test_change_file_ignores_not_opened_file() {
+        let path = PathBuf::from("/a/etc/di.xml");
+        let mut state = State::new();
+        assert!(!state.change_file(&path, &[change(Some(((0, 0), (0, 0))), "<config/>")]));
+        assert_eq!(state.get_file(&path), None);
+    }
+
+    #[test]
+    fn test_reindex_closed_file_drops_unsaved_entries() {
+        let path = PathBuf::from("/a/app/code/Some/Module/registration.php");
+        let mut state = State::new();
+        state.set_file(
+            &path,
+            "<?php\n\\Magento\\Framework\\Component\\ComponentRegistrar::register(\\Magento\\Framework\\Component\\ComponentRegistrar::MODULE, 'Some_Module', __DIR__);\n",
+        );
+        assert_eq!(state.get_modules(), vec!["Some_Module"]);
+
+        state.del_file(&path);
+        state.reindex_file(&path);
+        assert!(state.get_modules().is_empty());
+    }
+
+    #[test]
+    fn test_position_to_offset_counts_utf16_characters() {
+        let content = "a😀b\r\nżc";
+        let pos = |line, character| Position { line, character };
+        assert_eq!(position_to_offset(content, pos(0, 3)), "a😀".len());
+        assert_eq!(position_to_offset(content, pos(0, 10)), "a😀b".len());
+        assert_eq!(position_to_offset(content, pos(1, 1)), "a😀b\r\nż".len());
+        assert_eq!(position_to_offset(content, pos(5, 0)), content.len());
+    }
+
+    #[test]
+    fn test_module_for_path_picks_most_specific_module() {
+        let mut state = State::new();
+        state
+            .add_module("Some_Module")
+            .add_module_path("Some_Module", PathBuf::from("/a/app/code/Some/Module"));
+        state.add_module("Some_Nested").add_module_path(
+            "Some_Nested",
+            PathBuf::from("/a/app/code/Some/Module/Nested"),
+        );
+
+        assert_eq!(
+            state.module_for_path(Path::new("/a/app/code/Some/Module/Nested/etc/di.xml")),
+            Some((
+                "Some_Nested".into(),
+                PathBuf::from("/a/app/code/Some/Module/Nested")
+            ))
+        );
+        assert_eq!(
+            state.module_for_path(Path::new("/a/app/code/Some/Module/Model/Foo.php")),
+            Some((
+                "Some_Module".into(),
+                PathBuf::from("/a/app/code/Some/Module")
+            ))
+        );
+        assert_eq!(
+            state.module_for_path(Path::new("/a/app/code/Other/X.php")),
+            None
+        );
+    }
+
+    fn implementor_names(state: &State, fqn: &str) -> Vec<String> {
+        state
+            .get_php_implementors(fqn)
+            .iter()
+            .map(|c| c.fqn.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_get_php_implementors_through_parents_and_interfaces() {
+        let mut state = State::new();
+        state.set_file(
+            Path::new("/a/Model.php"),
+            r#"<?php
+namespace A;
+interface Base {}
+interface Child extends Base {}
+class Impl implements Child {}
+class Sub extends Impl {}
+class Other {}
+"#,
+        );
+
+        assert_eq!(
+            implementor_names(&state, "A\\Base"),
+            vec!["A\\Impl", "A\\Sub"]
+        );
+        assert_eq!(implementor_names(&state, "\\A\\Impl"), vec!["A\\Sub"]);
+        assert!(implementor_names(&state, "A\\Other").is_empty());
+
+        state.set_file(
+            Path::new("/a/Model.php"),
+            "<?php\nnamespace A;\ninterface Base {}\n",
+        );
+        assert!(implementor_names(&state, "A\\Base").is_empty());
+    }
+
+    #[test]
+    fn test_clone_shares_index_until_updated() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/app/code/A/Item.php"),
+            "<?php\nnamespace A;\nclass Item {}\n",
+        );
+        let snapshot = state.clone();
+        assert!(Arc::ptr_eq(&state.index, &snapshot.index));
+
+        state.set_file(
+            &PathBuf::from("/a/app/code/A/Other.php"),
+            "<?php\nnamespace A;\nclass Other {}\n",
+        );
+        assert!(!Arc::ptr_eq(&state.index, &snapshot.index));
+        assert!(state.get_php_class("A\\Other").is_some());
+        assert!(snapshot.get_php_class("A\\Other").is_none());
+        assert!(snapshot.get_php_class("A\\Item").is_some());
+    }
+
+    #[test]
+    fn test_reindex_one_of_files_declaring_same_class() {
+        let mut state = State::new();
+        let generated = Path::new("/a/generated/code/A/Item.php");
+        let original = Path::new("/a/app/code/A/Item.php");
+        let item = "<?php\nnamespace A;\nclass Item implements ItemInterface {}\n";
+        state.set_file(original, item);
+        state.set_file(generated, item);
+        state.set_file(generated, item);
+        assert_eq!(
+            state.index.php_children["A\\ItemInterface"],
+            vec!["A\\Item"]
+        );
+
+        state.set_file(generated, "<?php\n");
+        assert_eq!(
+            implementor_names(&state, "A\\ItemInterface"),
+            vec!["A\\Item"]
+        );
+        assert_eq!(
+            state
+                .get_php_class("A\\Item")
+                .and_then(|c| c.location.uri.to_path_buf().ok()),
+            Some(original.to_path_buf())
+        );
+
+        state.set_file(original, "<?php\n");
+        assert!(implementor_names(&state, "A\\ItemInterface").is_empty());
+        assert_eq!(state.get_php_class("A\\Item"), None);
+    }
+
+    #[test]
+    fn test_resolve_di_preference_by_area() {
+        let mut state = State::new();
+        state.set_file(
+            Path::new("/a/etc/di.xml"),
+            r#"<config>
+    <preference for="A\FooInterface" type="A\Foo"/>
+    <preference for="A\Foo" type="A\BetterFoo"/>
+    <preference for="A\Loop" type="A\Loop"/>
+</config>"#,
+        );
+        state.set_file(
+            Path::new("/a/etc/frontend/di.xml"),
+            r#"<config><preference for="A\FooInterface" type="\A\FrontFoo"/></config>"#,
+        );
+
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Base, "\\A\\FooInterface"),
+            Some("A\\BetterFoo".into())
+        );
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Frontend, "A\\FooInterface"),
+            Some("A\\FrontFoo".into())
+        );
+        assert_eq!(state.resolve_di_preference(&M2Area::Base, "A\\Loop"), None);
+
+        state.set_file(Path::new("/a/etc/frontend/di.xml"), "<config></config>");
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Frontend, "A\\FooInterface"),
+            Some("A\\BetterFoo".into())
+        );
+    }
+
+    #[test]
+    fn test_get_observer_events_after_reindex() {
+        let path = PathBuf::from("/a/app/code/Some/Module/etc/events.xml");
+        let events = |event| {
+            format!(
+                r#"<config>
+                    <event name="{event}">
+                        <observer name="log" instance="\Some\Module\Observer\Log"/>
+                        <observer name="other" instance="Some\Module\Observer\Other"/>
+                    </event>
+                </config>"#
+            )
+        };
+        let mut state = State::new();
+        state.set_file(&path, events("some_event"));
+        let observer_events = |state: &State| {
+            state
+                .get_observer_events("Some\\Module\\Observer\\Log")
+                .into_iter()
+                .map(|(event, _)| event)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(observer_events(&state), vec!["some_event"]);
+
+        state.change_file(&path, &[change(None, &events("other_event"))]);
+        state.reindex_file(&path);
+        assert_eq!(observer_events(&state), vec!["other_event"]);
+    }
+
+    #[test]
+    fn test_replace_index_keeps_opened_files() {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).append(&["tests", "fixtures", "project"]);
+        let events_xml = path.append(&["app", "code", "Some", "Module", "etc", "events.xml"]);
+        let state = State::new().into_arc();
+        for thread in State::update_index(&state, &path) {
+            thread.join().expect("Index thread should not panic");
+        }
+        state.lock().set_file(
+            &events_xml,
+            r#"<config><event name="unsaved_event"><observer name="a" instance="A"/></event></config>"#,
+        );
+        let stats = state.lock().index_stats();
+
+        let (fresh, threads) = State::rebuild_index(&state);
+        for thread in threads {
+            thread.join().expect("Index thread should not panic");
+        }
+        let fresh = std::mem::replace(&mut *fresh.lock(), State::new());
+        let mut state = state.lock();
+        state.replace_index(fresh);
+
+        assert_eq!(state.index_stats(), stats);
+        assert!(state.get_file(&events_xml).is_some());
+        assert_eq!(state.get_event_observers("unsaved_event").len(), 1);
+        assert!(state.get_event_observers("some_item_save_after").is_empty());
+    }
+
+    #[test]
+    fn test_cancelled_index_releases_state() {
+        let state = State::new().into_arc();
+        state.lock().cancel_index();
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).append(&["tests", "fixtures", "project"]);
+        for thread in State::update_index(&state, &path) {
+            thread.join().expect("Index thread should not panic");
+        }
+
+        assert_eq!(Arc::strong_count(&state), 1);
+        assert!(state.lock().get_module_path("Some_Module").is_none());
+    }
+}
@@ -0,0 +1,49 @@
+use std::{sync::mpsc::channel, thread::spawn, time::Duration};
+
+/// Run the work on separate thread and wait for its result at most `timeout`, `None`
+/// when it took longer. Work that timed out is not stopped (threads can not be killed),
+/// it keeps running in the background and its result is dropped.
+pub fn run_with_timeout<T, F>(timeout: Duration, work: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = channel();
+    spawn(move || {
+        // receiver is gone when the work timed out
+        sender.send(work()).ok();
+    });
+    receiver.recv_timeout(timeout).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread::sleep, time::Instant};
+
+    use super::*;
+
+    /// Parser stub that takes given time to produce the result.
+    fn slow_parse(duration: Duration) -> impl FnOnce() -> Vec<&'static str> {
+        move || {
+            sleep(duration);
+            vec!["parsed"]
+        }
+    }
+
+    #[test]
+    fn test_run_with_timeout_when_done_in_time() {
+        let result = run_with_timeout(Duration::from_secs(5), slow_parse(Duration::ZERO));
+        assert_eq!(result, Some(vec!["parsed"]));
+    }
+
+    #[test]
+    fn test_run_with_timeout_when_work_is_too_slow() {
+        let start = Instant::now();
+        let result = run_with_timeout(
+            Duration::from_millis(50),
+            slow_parse(Duration::from_secs(5)),
+        );
+        assert_eq!(result, None);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,420 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Position, Range};
+use tree_sitter::{Node, Point};
+
+use crate::{
+    m2::M2Item,
+    state::State,
+    ts::{get_range_from_node, node_at_position},
+};
+
+// TypeScript is parsed with the javascript parser. It handles `import` and `export ... from`
+// statements well, but declarations with type annotations end up as errors, so declarations
+// are found by scanning the source text instead.
+const DECLARATION_KEYWORDS: [&str; 9] = [
+    "class",
+    "interface",
+    "type",
+    "enum",
+    "function",
+    "const",
+    "let",
+    "var",
+    "namespace",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TsImport {
+    local: String,
+    imported: String,
+    source: String,
+    range: Range,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TsExport {
+    /// `export { name as alias } from 'source'`
+    Named {
+        name: String,
+        alias: String,
+        source: String,
+    },
+    /// `export * from 'source'`
+    All { source: String },
+    /// `export { name as alias }`
+    Local { name: String, alias: String },
+}
+
+pub fn get_item_from_position(state: &State, path: &Path, pos: Position) -> Option<M2Item> {
+    let content = state.get_file(&path.to_path_buf())?;
+    get_item_from_pos(content, path, pos)
+}
+
+fn get_item_from_pos(content: &str, path: &Path, pos: Position) -> Option<M2Item> {
+    let tree = tree_sitter_parsers::parse(content, "javascript");
+    let root = tree.root_node();
+    let mut dir = path.to_path_buf();
+    dir.pop();
+
+    let imports = get_imports(root, content);
+    if let Some(import) = imports.iter().find(|i| range_contains(i.range, pos)) {
+        return symbol_item(&import.imported, &import.source, dir);
+    }
+
+    for statement in children(root) {
+        if statement.kind() != "export_statement" || !node_at_position(statement, pos) {
+            continue;
+        }
+        let Some(source) = statement.child_by_field_name("source") else {
+            continue;
+        };
+        for specifier in children_of_kind(statement, "export_clause", "export_specifier") {
+            if node_at_position(specifier, pos) {
+                let name = specifier.child_by_field_name("name")?;
+                return symbol_item(node_text(name, content), string_text(source, content), dir);
+            }
+        }
+    }
+
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let node = root.descendant_for_point_range(point, point)?;
+    if node.kind() != "identifier" {
+        return None;
+    }
+    let text = node_text(node, content);
+    let import = imports.iter().find(|i| i.local == text)?;
+    symbol_item(&import.imported, &import.source, dir)
+}
+
+fn symbol_item(symbol: &str, source: &str, dir: PathBuf) -> Option<M2Item> {
+    if source.starts_with('.') {
+        Some(M2Item::TsSymbol(symbol.into(), source.into(), dir))
+    } else {
+        None
+    }
+}
+
+fn get_imports(root: Node, content: &str) -> Vec<TsImport> {
+    let mut result = vec![];
+    for statement in children(root) {
+        if statement.kind() != "import_statement" {
+            continue;
+        }
+        let Some(source) = statement.child_by_field_name("source") else {
+            continue;
+        };
+        let source = string_text(source, content);
+        for clause in children(statement).filter(|n| n.kind() == "import_clause") {
+            for node in children(clause) {
+                match node.kind() {
+                    "identifier" => result.push(TsImport {
+                        local: node_text(node, content).into(),
+                        imported: "default".into(),
+                        source: source.into(),
+                        range: get_range_from_node(node),
+                    }),
+                    "namespace_import" => {
+                        if let Some(local) = children(node).find(|n| n.kind() == "identifier") {
+                            result.push(TsImport {
+                                local: node_text(local, content).into(),
+                                imported: "*".into(),
+                                source: source.into(),
+                                range: get_range_from_node(node),
+                            });
+                        }
+                    }
+                    "named_imports" => {
+                        for specifier in children(node).filter(|n| n.kind() == "import_specifier") {
+                            let Some(name) = specifier.child_by_field_name("name") else {
+                                continue;
+                            };
+                            let local = specifier.child_by_field_name("alias").unwrap_or(name);
+                            result.push(TsImport {
+                                local: node_text(local, content).into(),
+                                imported: node_text(name, content).into(),
+                                source: source.into(),
+                                range: get_range_from_node(specifier),
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    result
+}
+
+pub fn get_exports(content: &str) -> Vec<TsExport> {
+    let tree = tree_sitter_parsers::parse(content, "javascript");
+    let mut result = vec![];
+    for statement in children(tree.root_node()) {
+        if statement.kind() != "export_statement" {
+            continue;
+        }
+        let source = statement
+            .child_by_field_name("source")
+            .map(|s| string_text(s, content).to_string());
+        let specifiers = children_of_kind(statement, "export_clause", "export_specifier");
+        if specifiers.is_empty() {
+            if let Some(source) = source {
+                if children(statement).any(|n| n.kind() == "*") {
+                    result.push(TsExport::All { source });
+                }
+            }
+            continue;
+        }
+        for specifier in specifiers {
+            let Some(name) = specifier.child_by_field_name("name") else {
+                continue;
+            };
+            let alias = specifier.child_by_field_name("alias").unwrap_or(name);
+            let name = node_text(name, content).to_string();
+            let alias = node_text(alias, content).to_string();
+            result.push(match source {
+                Some(ref source) => TsExport::Named {
+                    name,
+                    alias,
+                    source: source.clone(),
+                },
+                None => TsExport::Local { name, alias },
+            });
+        }
+    }
+    result
+}
+
+/// Find range of the declared `symbol` name, `default` finds the `export default` statement.
+pub fn find_declaration(content: &str, symbol: &str) -> Option<Range> {
+    if symbol == "default" {
+        let offset = content.find("export default")? + "export ".len();
+        return Some(offset_range(content, offset, "default".len()));
+    }
+    for (offset, _) in content.match_indices(symbol) {
+        let end = offset + symbol.len();
+        if content[..offset].ends_with(is_identifier_char)
+            || content[end..].starts_with(is_identifier_char)
+        {
+            continue;
+        }
+        let before = content[..offset].trim_end();
+        if before.len() == offset {
+            continue;
+        }
+        let keyword = before
+            .rsplit(|c: char| !is_identifier_char(c))
+            .next()
+            .unwrap_or_default();
+        if DECLARATION_KEYWORDS.contains(&keyword) {
+            return Some(offset_range(content, offset, symbol.len()));
+        }
+    }
+    None
+}
+
+/// Resolve relative module specifier the same way TypeScript does for relative imports.
+pub fn resolve_module(dir: &Path, source: &str) -> Option<PathBuf> {
+    let base = dir.join(source);
+    let base_str = base.to_str()?;
+    let candidates = [
+        base.clone(),
+        PathBuf::from(format!("{base_str}.ts")),
+        PathBuf::from(format!("{base_str}.tsx")),
+        PathBuf::from(format!("{base_str}.d.ts")),
+        PathBuf::from(format!("{base_str}.js")),
+        base.join("index.ts"),
+        base.join("index.tsx"),
+        base.join("index.d.ts"),
+        base.join("index.js"),
+    ];
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn offset_range(content: &str, offset: usize, len: usize) -> Range {
+    Range {
+        start: offset_to_position(content, offset),
+        end: offset_to_position(content, offset + len),
+    }
+}
+
+fn offset_to_position(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: line as u32,
+        character: before[line_start..].chars().count() as u32,
+    }
+}
+
+fn range_contains(range: Range, pos: Position) -> bool {
+    range.start <= pos && pos <= range.end
+}
+
+fn children(node: Node) -> impl Iterator<Item = Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).collect::<Vec<_>>().into_iter()
+}
+
+fn children_of_kind<'a>(node: Node<'a>, parent_kind: &str, kind: &str) -> Vec<Node<'a>> {
+    children(node)
+        .filter(|n| n.kind() == parent_kind)
+        .flat_map(children)
+        .filter(|n| n.kind() == kind)
+        .collect()
+}
+
+fn node_text<'a>(node: Node, content: &'a str) -> &'a str {
+    node.utf8_text(content.as_bytes()).unwrap_or("")
+}
+
+fn string_text<'a>(node: Node, content: &'a str) -> &'a str {
+    node_text(node, content).trim_matches(|c| c == '\'' || c == '"' || c == '`')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_test_item(ts: &str, path: &str) -> Option<M2Item> {
+        let mut character = 0;
+        let mut line = 0;
+        for l in ts.lines() {
+            if l.contains('|') {
+                character = l.find('|').expect("Test has to have a | character") as u32;
+                break;
+            }
+            line += 1;
+        }
+        let pos = Position { line, character };
+        get_item_from_pos(&ts.replace('|', ""), &PathBuf::from(path), pos)
+    }
+
+    #[test]
+    fn test_get_item_from_pos_on_named_import() {
+        let item = get_test_item(
+            r#"
+            import { Fo|o } from './barrel';
+            "#,
+            "/a/b/file.ts",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::TsSymbol(
+                "Foo".into(),
+                "./barrel".into(),
+                PathBuf::from("/a/b")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_on_usage_of_aliased_import() {
+        let item = get_test_item(
+            r#"
+            import { Foo as Bar } from '../barrel';
+            const x = new Ba|r();
+            "#,
+            "/a/b/file.ts",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::TsSymbol(
+                "Foo".into(),
+                "../barrel".into(),
+                PathBuf::from("/a/b")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_on_usage_in_exported_declaration() {
+        let item = get_test_item(
+            r#"
+            import { Foo } from './barrel';
+            export const x = new Fo|o();
+            "#,
+            "/a/b/file.ts",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::TsSymbol(
+                "Foo".into(),
+                "./barrel".into(),
+                PathBuf::from("/a/b")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_on_package_import() {
+        let item = get_test_item(
+            r#"
+            import { Fo|o } from 'package';
+            "#,
+            "/a/b/file.ts",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_exports() {
+        let exports = get_exports(
+            r#"
+            export { Foo, Bar as Baz } from './foo';
+            export * from './all';
+            export { Local as Public };
+            export interface Skipped { a: string }
+            "#,
+        );
+        assert_eq!(
+            exports,
+            vec![
+                TsExport::Named {
+                    name: "Foo".into(),
+                    alias: "Foo".into(),
+                    source: "./foo".into()
+                },
+                TsExport::Named {
+                    name: "Bar".into(),
+                    alias: "Baz".into(),
+                    source: "./foo".into()
+                },
+                TsExport::All {
+                    source: "./all".into()
+                },
+                TsExport::Local {
+                    name: "Local".into(),
+                    alias: "Public".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_declaration() {
+        let content = "import { FooBar } from './x';\nexport interface Foo {\n  a: FooBar;\n}\n";
+        assert_eq!(
+            find_declaration(content, "Foo"),
+            Some(Range {
+                start: Position {
+                    line: 1,
+                    character: 17
+                },
+                end: Position {
+                    line: 1,
+                    character: 20
+                },
+            })
+        );
+        assert_eq!(find_declaration(content, "FooBar"), None);
+    }
+}
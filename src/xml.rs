@@ -1,15 +1,66 @@
-use lsp_types::{Position, Range};
-use std::{collections::HashMap, path::PathBuf};
-use tree_sitter::{Node, QueryCursor};
+use glob::glob;
+use lsp_types::{Location, Position, Range, Url};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tree_sitter::{Node, Point, QueryCursor, Tree};
 
 use crate::{
-    js,
-    m2::{self, M2Item, M2Path},
+    composer, js,
+    m2::{self, M2Area, M2Item, M2LayoutElement, M2Path},
     queries,
-    state::State,
-    ts::{get_node_str, get_node_text_before_pos, node_at_position, node_last_child},
+    state::{ArcState, State},
+    ts::{
+        get_node_str, get_node_text_before_pos, get_range_from_node, node_at_position,
+        node_last_child,
+    },
 };
 
+/// Directories with layout files, block and container names come from both.
+const LAYOUT_DIRS: [&str; 2] = ["layout", "page_layout"];
+
+/// Module `etc` files, global or in area subdirectory, indexed for DI and events.
+const ETC_FILES: [&str; 3] = ["di.xml", "events.xml", "webapi.xml"];
+
+/// Type collecting console commands from `di.xml` arguments.
+const CONSOLE_COMMAND_LIST: &str = "Magento\\Framework\\Console\\CommandList";
+
+/// Observer of the event declared in `events.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventObserver {
+    pub name: String,
+    pub instance: String,
+    pub location: Location,
+}
+
+/// `xsi:type="object"` item of `xsi:type="array"` argument (pool of objects).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiPoolItem {
+    pub key: String,
+    pub class: String,
+}
+
+/// Preference type, virtual type backing class or constructor argument declared in `di.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiValue {
+    /// `xsi:type` of the argument, `None` for preferences.
+    pub xsi_type: Option<String>,
+    pub value: String,
+    pub location: Location,
+}
+
+/// `<image/>` of module or theme `etc/view.xml` media configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewImage {
+    /// Module the image belongs to, `module` attribute of `<images/>`.
+    pub module: String,
+    pub id: String,
+    /// `type` attribute and child elements (like `width` and `height`) in the file order.
+    pub values: Vec<(String, String)>,
+    pub location: Location,
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum XmlPart {
@@ -33,16 +84,16 @@ impl XmlCompletion {
     }
 
     pub fn attribute_eq(&self, attr: &str, val: &str) -> bool {
-        self.tag.as_ref().map_or(false, |t| {
-            t.attributes.get(attr).map_or(false, |v| v == val)
-        })
+        self.tag
+            .as_ref()
+            .is_some_and(|t| t.attributes.get(attr).is_some_and(|v| v == val))
     }
 
     pub fn attribute_in(&self, attr: &str, vals: &[&str]) -> bool {
-        self.tag.as_ref().map_or(false, |t| {
+        self.tag.as_ref().is_some_and(|t| {
             t.attributes
                 .get(attr)
-                .map_or(false, |v| vals.contains(&v.as_ref()))
+                .is_some_and(|v| vals.contains(&v.as_ref()))
         })
     }
 }
@@ -67,8 +118,1175 @@ impl XmlTag {
     }
 }
 
+/// Void elements of the html grammar, including obsolete ones (like `<image>` of `view.xml`).
+const HTML_VOID_TAGS: [&str; 23] = [
+    "area", "base", "basefont", "bgsound", "br", "col", "command", "embed", "frame", "hr", "image",
+    "img", "input", "isindex", "keygen", "link", "menuitem", "meta", "nextid", "param", "source",
+    "track", "wbr",
+];
+
+/// Parse XML content with the html parser.
+///
+/// Html void elements (like `<base>` website code in `config.xml`) can not have children in
+/// html, so their names are masked before parsing. Masked content has the same length, so
+/// nodes should be read from the original content.
+///
+/// `<!DOCTYPE>` declaration is masked as well, its internal subset (`[<!ENTITY ...>]`)
+/// would be parsed as elements otherwise. Entities are never expanded (so external
+/// entities are never read), references like `&name;` stay in the text as they are.
+fn parse(content: &str) -> Tree {
+    let mut bytes = content.as_bytes().to_vec();
+    mask_doctype(&mut bytes);
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'<' {
+            let start = if bytes.get(i + 1) == Some(&b'/') {
+                i + 2
+            } else {
+                i + 1
+            };
+            let mut end = start;
+            while end < bytes.len() && bytes[end].is_ascii_alphanumeric() {
+                end += 1;
+            }
+            let name = content[start..end].to_ascii_lowercase();
+            let closed = bytes
+                .get(end)
+                .is_none_or(|b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/');
+            if closed && HTML_VOID_TAGS.contains(&name.as_str()) {
+                bytes[start..end].fill(b'x');
+            }
+            i = end.max(i + 1);
+        } else {
+            i += 1;
+        }
+    }
+    let masked = String::from_utf8(bytes).unwrap_or_else(|_| content.to_string());
+    tree_sitter_parsers::parse(&masked, "html")
+}
+
+/// Replace `<!DOCTYPE ...>` declaration (including internal subset) with spaces, new
+/// lines are kept so positions of the rest of the document do not change.
+fn mask_doctype(bytes: &mut [u8]) {
+    let Some(start) = bytes
+        .windows(9)
+        .position(|w| w.eq_ignore_ascii_case(b"<!DOCTYPE"))
+    else {
+        return;
+    };
+    let mut depth = 0;
+    let mut quote = None;
+    let mut end = bytes.len();
+    for (i, byte) in bytes.iter().enumerate().skip(start + 9) {
+        match (quote, *byte) {
+            (Some(q), b) if b == q => quote = None,
+            (Some(_), _) => (),
+            (None, b'"' | b'\'') => quote = Some(*byte),
+            (None, b'[') => depth += 1,
+            (None, b']') => depth -= 1,
+            (None, b'>') if depth <= 0 => {
+                end = i + 1;
+                break;
+            }
+            _ => (),
+        }
+    }
+    for byte in &mut bytes[start..end] {
+        if *byte != b'\n' {
+            *byte = b' ';
+        }
+    }
+}
+
+pub fn update_index(state: &ArcState, path: &PathBuf) {
+    let vendor_packages = composer::indexed_vendor_packages(state, path);
+    // if current workspace is magento module
+    process_glob(state, &path.append(&["etc", "adminhtml", "system.xml"]));
+    // if current workspace is magento installation
+    for package in &vendor_packages {
+        process_glob(state, &package.append(&["etc", "adminhtml", "system.xml"]));
+    }
+    process_glob(
+        state,
+        &path.append(&["app", "code", "*", "*", "etc", "adminhtml", "system.xml"]),
+    );
+
+    for etc_file in ETC_FILES {
+        // if current workspace is magento module
+        process_glob(state, &path.append(&["etc", etc_file]));
+        process_glob(state, &path.append(&["etc", "*", etc_file]));
+        // if current workspace is magento installation
+        for package in &vendor_packages {
+            process_glob(state, &package.append(&["etc", etc_file]));
+            process_glob(state, &package.append(&["etc", "*", etc_file]));
+        }
+        process_glob(
+            state,
+            &path.append(&["app", "code", "*", "*", "etc", etc_file]),
+        );
+        process_glob(
+            state,
+            &path.append(&["app", "code", "*", "*", "etc", "*", etc_file]),
+        );
+    }
+    process_glob(state, &path.append(&["app", "etc", "di.xml"]));
+
+    // if current workspace is magento module
+    process_glob(state, &path.append(&["view", "*", "ui_component", "*.xml"]));
+    // if current workspace is magento installation
+    for package in &vendor_packages {
+        process_glob(
+            state,
+            &package.append(&["view", "*", "ui_component", "*.xml"]),
+        );
+    }
+    process_glob(
+        state,
+        &path.append(&[
+            "app",
+            "code",
+            "*",
+            "*",
+            "view",
+            "*",
+            "ui_component",
+            "*.xml",
+        ]),
+    );
+
+    for layout_dir in LAYOUT_DIRS {
+        // if current workspace is magento module
+        process_glob(state, &path.append(&["view", "*", layout_dir, "*.xml"]));
+        // if current workspace is magento installation
+        for package in &vendor_packages {
+            process_glob(state, &package.append(&["view", "*", layout_dir, "*.xml"])); // modules
+            process_glob(state, &package.append(&["*", layout_dir, "*.xml"])); // themes
+        }
+        process_glob(
+            state,
+            &path.append(&["app", "code", "*", "*", "view", "*", layout_dir, "*.xml"]),
+        ); // local modules
+        process_glob(
+            state,
+            &path.append(&["app", "design", "*", "*", "*", "*", layout_dir, "*.xml"]),
+        ); // local themes
+    }
+
+    // if current workspace is magento module or theme
+    process_glob(state, &path.append(&["etc", "view.xml"]));
+    // if current workspace is magento installation
+    for package in &vendor_packages {
+        process_glob(state, &package.append(&["etc", "view.xml"]));
+    }
+    process_glob(
+        state,
+        &path.append(&["app", "code", "*", "*", "etc", "view.xml"]),
+    );
+    process_glob(
+        state,
+        &path.append(&["app", "design", "*", "*", "*", "etc", "view.xml"]),
+    );
+}
+
+pub fn maybe_index_file(state: &mut State, content: &str, file_path: &PathBuf) {
+    if file_path.has_components(&["etc", "adminhtml", "system.xml"]) {
+        update_index_from_system_config(state, content, file_path);
+    } else if is_layout_file(file_path) {
+        update_index_from_layout(state, content, file_path);
+    } else if let Some(area) = di_area(file_path) {
+        update_index_from_di(state, content, file_path, &area);
+    } else if is_events_file(file_path) {
+        update_index_from_events(state, content, file_path);
+    } else if let Some(component) = ui_component_name(file_path) {
+        update_index_from_ui_component(state, content, file_path, component);
+    } else if is_view_config_file(file_path) {
+        update_index_from_view_config(state, content, file_path);
+    } else if is_webapi_file(file_path) {
+        update_index_from_webapi(state, content, file_path);
+    }
+}
+
+/// Module `etc/webapi.xml`.
+fn is_webapi_file(file_path: &Path) -> bool {
+    file_path
+        .file_name()
+        .is_some_and(|name| name == "webapi.xml")
+        && file_path
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "etc")
+}
+
+/// Module or theme `etc/view.xml`.
+fn is_view_config_file(file_path: &Path) -> bool {
+    file_path.file_name().is_some_and(|name| name == "view.xml")
+        && file_path
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "etc")
+}
+
+/// Parse `view.xml`, its element names (like `aspect_ratio`) contain underscores that
+/// html parser splits tag names on, they are replaced in the parsed content only.
+fn parse_view_config(content: &str) -> Tree {
+    parse(&content.replace('_', "-"))
+}
+
+/// Name of the UI component defined in `view/<area>/ui_component/<name>.xml`, files with
+/// the same name in different modules extend the same component.
+fn ui_component_name(file_path: &Path) -> Option<&str> {
+    if file_path.extension()? != "xml" || file_path.parent()?.file_name()? != "ui_component" {
+        return None;
+    }
+    file_path.file_stem()?.to_str()
+}
+
+/// `etc/events.xml` or `etc/<area>/events.xml`, events of all areas are indexed together.
+fn is_events_file(file_path: &Path) -> bool {
+    file_path
+        .file_name()
+        .is_some_and(|name| name == "events.xml")
+        && file_path
+            .ancestors()
+            .skip(1)
+            .take(2)
+            .any(|dir| dir.file_name().is_some_and(|name| name == "etc"))
+}
+
+/// Area of `etc/di.xml` (global) or `etc/<area>/di.xml`, other areas are not indexed.
+pub fn di_area(file_path: &Path) -> Option<M2Area> {
+    if file_path.file_name()? != "di.xml" {
+        return None;
+    }
+    let dir = file_path.parent()?;
+    match dir.file_name()?.to_str()? {
+        "etc" => Some(M2Area::Base),
+        area @ ("frontend" | "adminhtml") if dir.parent()?.file_name()? == "etc" => {
+            Some(if area == "frontend" {
+                M2Area::Frontend
+            } else {
+                M2Area::Adminhtml
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn is_layout_file(file_path: &PathBuf) -> bool {
+    file_path.get_ext() == "xml"
+        && LAYOUT_DIRS.iter().any(|dir| {
+            file_path
+                .parent()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == *dir)
+        })
+}
+
+fn process_glob(state: &ArcState, glob_path: &PathBuf) {
+    let files = glob(glob_path.to_path_str())
+        .expect("Failed to read glob pattern")
+        .filter_map(Result::ok);
+
+    for file_path in files {
+        if state.lock().is_index_cancelled() {
+            return;
+        }
+        if let Ok(content) = std::fs::read_to_string(&file_path) {
+            let mut state = state.lock();
+            // opened files are indexed from their buffers already
+            if state.get_file(&file_path).is_none() {
+                maybe_index_file(&mut state, &content, &file_path);
+            }
+        }
+    }
+}
+
+fn update_index_from_system_config(state: &mut State, content: &str, file_path: &Path) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    let tree = parse(content);
+    for element in child_elements(tree.root_node()) {
+        index_system_config_element(state, element, content, &uri, &mut vec![]);
+    }
+}
+
+fn index_system_config_element(
+    state: &mut State,
+    element: Node,
+    content: &str,
+    uri: &Url,
+    config_path: &mut Vec<String>,
+) {
+    let Some(tag) = element_tag(element) else {
+        return;
+    };
+    let name = tag_name(tag, content);
+    let id = tag_attribute_value(tag, content, "id");
+    match (name, id) {
+        ("field", Some(id)) => {
+            config_path.push(get_node_str(id, content).into());
+            state.add_system_config_field(
+                config_path.join("/"),
+                Location {
+                    uri: uri.clone(),
+                    range: get_range_from_node(id),
+                },
+            );
+            config_path.pop();
+        }
+        ("section" | "group", Some(id)) => {
+            config_path.push(get_node_str(id, content).into());
+            for child in child_elements(element) {
+                index_system_config_element(state, child, content, uri, config_path);
+            }
+            config_path.pop();
+        }
+        ("config" | "system", _) => {
+            for child in child_elements(element) {
+                index_system_config_element(state, child, content, uri, config_path);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn update_index_from_layout(state: &mut State, content: &str, file_path: &PathBuf) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    let area = file_path.get_area();
+    let tree = parse(content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        if let Some(class) = object_argument_class(element, content) {
+            state.add_class_usage(class);
+            continue;
+        }
+        let layout_element = match tag_name(tag, content) {
+            "block" => M2LayoutElement::Block,
+            "container" => M2LayoutElement::Container,
+            _ => continue,
+        };
+        if let Some(class) = tag_attribute_value(tag, content, "class")
+            .filter(|_| layout_element == M2LayoutElement::Block)
+        {
+            state.add_class_usage(get_node_str(class, content).trim_start_matches('\\'));
+            state.add_layout_block_class(
+                &area,
+                get_node_str(class, content),
+                Location {
+                    uri: uri.clone(),
+                    range: get_range_from_node(class),
+                },
+            );
+        }
+        if let Some(name) = tag_attribute_value(tag, content, "name") {
+            state.add_layout_name(
+                &area,
+                layout_element,
+                get_node_str(name, content),
+                Location {
+                    uri: uri.clone(),
+                    range: get_range_from_node(name),
+                },
+            );
+        }
+    }
+}
+
+fn update_index_from_di(state: &mut State, content: &str, file_path: &Path, area: &M2Area) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    let tree = parse(content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        match tag_name(tag, content) {
+            "config" => elements.extend(child_elements(element)),
+            "preference" => {
+                let (Some(for_class), Some(type_class)) = (
+                    tag_attribute_value(tag, content, "for"),
+                    tag_attribute_value(tag, content, "type"),
+                ) else {
+                    continue;
+                };
+                state.add_di_preference(
+                    area,
+                    get_node_str(for_class, content),
+                    DiValue {
+                        xsi_type: None,
+                        value: get_node_str(type_class, content).into(),
+                        location: Location {
+                            uri: uri.clone(),
+                            range: get_range_from_node(type_class),
+                        },
+                    },
+                );
+            }
+            "type" | "virtualType" => {
+                let Some(class) = tag_attribute_value(tag, content, "name") else {
+                    continue;
+                };
+                if tag_name(tag, content) == "virtualType" {
+                    if let Some(type_class) = tag_attribute_value(tag, content, "type") {
+                        state.add_di_virtual_type(
+                            area,
+                            get_node_str(class, content),
+                            DiValue {
+                                xsi_type: None,
+                                value: get_node_str(type_class, content).into(),
+                                location: Location {
+                                    uri: uri.clone(),
+                                    range: get_range_from_node(class),
+                                },
+                            },
+                        );
+                    }
+                }
+                let class = get_node_str(class, content);
+                if tag_name(tag, content) == "type" {
+                    state.add_class_usage(class.trim_start_matches('\\'));
+                }
+                for child in child_elements(element) {
+                    let Some(child_tag) = element_tag(child) else {
+                        continue;
+                    };
+                    match tag_name(child_tag, content) {
+                        "arguments" => {
+                            for argument in child_elements(child) {
+                                index_di_argument(state, argument, content, &uri, area, class);
+                            }
+                        }
+                        "plugin" => {
+                            if let Some(plugin) = tag_attribute_value(child_tag, content, "type") {
+                                state.add_class_usage(
+                                    get_node_str(plugin, content).trim_start_matches('\\'),
+                                );
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Classes of `<service class="..."/>` of web API routes, counted as class usages.
+fn update_index_from_webapi(state: &mut State, content: &str, file_path: &Path) {
+    state.set_source_file(file_path);
+    let tree = parse(content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        if tag_name(tag, content) != "service" {
+            continue;
+        }
+        if let Some(class) = tag_attribute_value(tag, content, "class") {
+            state.add_class_usage(get_node_str(class, content).trim_start_matches('\\'));
+        }
+    }
+}
+
+/// Class of `<argument/>` or `<item/>` with `xsi:type="object"` (like layout block
+/// arguments and their nested items).
+fn object_argument_class<'a>(element: Node, content: &'a str) -> Option<&'a str> {
+    let tag = element_tag(element)?;
+    if !matches!(tag_name(tag, content), "argument" | "item")
+        || tag_attribute_value(tag, content, "xsi:type").map(|t| get_node_str(t, content))
+            != Some("object")
+    {
+        return None;
+    }
+    let mut cursor = element.walk();
+    let text = element.children(&mut cursor).find(|n| n.kind() == "text")?;
+    let class = get_node_str(text, content).trim().trim_start_matches('\\');
+    (!class.is_empty()).then_some(class)
+}
+
+fn update_index_from_ui_component(
+    state: &mut State,
+    content: &str,
+    file_path: &Path,
+    component: &str,
+) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    state.add_ui_component_file(
+        component,
+        Location {
+            uri: uri.clone(),
+            range: Range::default(),
+        },
+    );
+    let tree = parse(content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        if let Some(class) = object_argument_class(element, content) {
+            state.add_class_usage(class);
+            continue;
+        }
+        if !matches!(tag_name(tag, content), "dataSource" | "dataProvider") {
+            continue;
+        }
+        if let Some(name) = tag_attribute_value(tag, content, "name") {
+            state.add_ui_data_source(
+                component,
+                get_node_str(name, content),
+                Location {
+                    uri: uri.clone(),
+                    range: get_range_from_node(name),
+                },
+            );
+        }
+    }
+}
+
+fn update_index_from_view_config(state: &mut State, content: &str, file_path: &Path) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    let tree = parse_view_config(content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        if tag_name(tag, content) != "images" {
+            elements.extend(child_elements(element));
+            continue;
+        }
+        let Some(module) = tag_attribute_value(tag, content, "module") else {
+            continue;
+        };
+        for image in child_elements(element) {
+            if let Some(view_image) =
+                view_image(image, content, get_node_str(module, content), &uri)
+            {
+                state.add_view_image(view_image);
+            }
+        }
+    }
+}
+
+fn view_image(image: Node, content: &str, module: &str, uri: &Url) -> Option<ViewImage> {
+    let tag = element_tag(image)?;
+    if tag_name(tag, content) != "image" {
+        return None;
+    }
+    let id = tag_attribute_value(tag, content, "id")?;
+    let mut values = vec![];
+    if let Some(image_type) = tag_attribute_value(tag, content, "type") {
+        values.push(("type".into(), get_node_str(image_type, content).into()));
+    }
+    for child in child_elements(image) {
+        let Some(child_tag) = element_tag(child) else {
+            continue;
+        };
+        let mut cursor = child.walk();
+        let value = child
+            .children(&mut cursor)
+            .find(|n| n.kind() == "text")
+            .map_or("", |n| n.utf8_text(content.as_bytes()).unwrap_or("").trim());
+        values.push((tag_name(child_tag, content).into(), value.into()));
+    }
+    Some(ViewImage {
+        module: module.into(),
+        id: get_node_str(id, content).into(),
+        values,
+        location: Location {
+            uri: uri.clone(),
+            range: get_range_from_node(id),
+        },
+    })
+}
+
+/// Module and id of `<image id=""/>` in `etc/view.xml` when position is on the id.
+pub fn get_view_image_from_position(
+    state: &State,
+    path: &Path,
+    pos: Position,
+) -> Option<(String, String)> {
+    if !is_view_config_file(path) {
+        return None;
+    }
+    let content = state.get_file(&path.to_path_buf())?;
+    get_view_image_at_pos(content, pos)
+}
+
+fn get_view_image_at_pos(content: &str, pos: Position) -> Option<(String, String)> {
+    let tree = parse_view_config(content);
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let mut node = tree.root_node().descendant_for_point_range(point, point)?;
+    while node.kind() != "element" {
+        node = node.parent()?;
+    }
+    let tag = element_tag(node)?;
+    let id = tag_attribute_value(tag, content, "id")?;
+    if tag_name(tag, content) != "image" || !node_at_position(id, pos) {
+        return None;
+    }
+    let images = element_tag(node.parent()?)?;
+    let module = tag_attribute_value(images, content, "module")?;
+    Some((
+        get_node_str(module, content).into(),
+        get_node_str(id, content).into(),
+    ))
+}
+
+/// Parent theme name (like `Magento/blank`) from `theme.xml` of the theme directory.
+pub fn get_theme_parent(theme_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(theme_path.join("theme.xml")).ok()?;
+    let tree = parse(&content);
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        if tag_name(tag, &content) == "parent" {
+            let mut cursor = element.walk();
+            let parent = element
+                .children(&mut cursor)
+                .find(|n| n.kind() == "text")?
+                .utf8_text(content.as_bytes())
+                .ok()?
+                .trim();
+            return (!parent.is_empty()).then(|| parent.into());
+        }
+    }
+    None
+}
+
+fn update_index_from_events(state: &mut State, content: &str, file_path: &Path) {
+    state.set_source_file(file_path);
+    let Ok(uri) = m2::path_to_uri(file_path) else {
+        return;
+    };
+    let tree = parse(content);
+    let configs = child_elements(tree.root_node());
+    for event in configs.into_iter().flat_map(child_elements) {
+        let Some(tag) = element_tag(event) else {
+            continue;
+        };
+        if tag_name(tag, content) != "event" {
+            continue;
+        }
+        let Some(event_name) = tag_attribute_value(tag, content, "name") else {
+            continue;
+        };
+        for observer in child_elements(event) {
+            let Some(tag) = element_tag(observer) else {
+                continue;
+            };
+            if tag_name(tag, content) != "observer"
+                || tag_attribute_value(tag, content, "disabled")
+                    .is_some_and(|d| get_node_str(d, content) == "true")
+            {
+                continue;
+            }
+            let name = tag_attribute_value(tag, content, "name");
+            let instance = tag_attribute_value(tag, content, "instance");
+            let Some(location_node) = instance.or(name) else {
+                continue;
+            };
+            state.add_event_observer(
+                get_node_str(event_name, content),
+                EventObserver {
+                    name: name.map_or("", |n| get_node_str(n, content)).into(),
+                    instance: instance.map_or("", |n| get_node_str(n, content)).into(),
+                    location: Location {
+                        uri: uri.clone(),
+                        range: get_range_from_node(location_node),
+                    },
+                },
+            );
+        }
+    }
+}
+
+fn index_di_argument(
+    state: &mut State,
+    argument: Node,
+    content: &str,
+    uri: &Url,
+    area: &M2Area,
+    class: &str,
+) {
+    let Some(tag) = element_tag(argument) else {
+        return;
+    };
+    if tag_name(tag, content) != "argument" {
+        return;
+    }
+    let Some(name) = tag_attribute_value(tag, content, "name") else {
+        return;
+    };
+    let mut cursor = argument.walk();
+    let value = argument
+        .children(&mut cursor)
+        .find(|n| n.kind() == "text")
+        .map_or("", |n| n.utf8_text(content.as_bytes()).unwrap_or("").trim());
+    state.add_di_argument(
+        area,
+        class,
+        get_node_str(name, content),
+        DiValue {
+            xsi_type: tag_attribute_value(tag, content, "xsi:type")
+                .map(|t| get_node_str(t, content).into()),
+            value: value.trim_start_matches('\\').into(),
+            location: Location {
+                uri: uri.clone(),
+                range: get_range_from_node(name),
+            },
+        },
+    );
+    // pool items are indexed with their key (`argument/item`), so they are merged by it
+    let mut items = vec![];
+    collect_pool_items(argument, content, uri, "argument", "", &mut items);
+    for (key, value) in items {
+        state.add_di_argument(area, class, key.as_str(), value);
+    }
+}
+
+/// `<referenceBlock/>` and `<referenceContainer/>` names that are not defined in any
+/// indexed layout of the file area, files of unknown area are skipped.
+pub fn get_orphan_layout_references(
+    state: &State,
+    path: &PathBuf,
+) -> Vec<(M2LayoutElement, String, Range)> {
+    let Some(area) = path.try_get_area() else {
+        return vec![];
+    };
+    let Some(content) = state.get_file(path) else {
+        return vec![];
+    };
+    if !is_layout_file(path) {
+        return vec![];
+    }
+    get_layout_references(content)
+        .into_iter()
+        .filter(|(element, name, _)| {
+            state
+                .get_layout_name_locations(&area, *element, name)
+                .is_empty()
+        })
+        .collect()
+}
+
+fn get_layout_references(content: &str) -> Vec<(M2LayoutElement, String, Range)> {
+    let tree = parse(content);
+    let mut result = vec![];
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        let layout_element = match tag_name(tag, content) {
+            "referenceBlock" => M2LayoutElement::Block,
+            "referenceContainer" => M2LayoutElement::Container,
+            _ => continue,
+        };
+        if let Some(name) = tag_attribute_value(tag, content, "name") {
+            result.push((
+                layout_element,
+                get_node_str(name, content).into(),
+                get_range_from_node(name),
+            ));
+        }
+    }
+    result.sort_by_key(|(_, _, range)| range.start);
+    result
+}
+
+/// Kind of identifier occurring in the document, occurrences match only the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum XmlIdentifier {
+    LayoutName,
+    Class,
+}
+
+/// Occurrence of the identifier in the document, `declaration` is set for block and
+/// container names and virtual type names, the rest are references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlOccurrence {
+    pub range: Range,
+    pub declaration: bool,
+}
+
+/// Occurrences (in the same document) of layout name or class under the cursor.
+pub fn get_occurrences_from_position(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<Vec<XmlOccurrence>> {
+    let content = state.get_file(path)?;
+    get_occurrences_at_pos(content, is_layout_file(path), pos)
+}
+
+fn get_occurrences_at_pos(
+    content: &str,
+    is_layout: bool,
+    pos: Position,
+) -> Option<Vec<XmlOccurrence>> {
+    let tree = parse(content);
+    let mut identifiers = vec![];
+    let mut elements = child_elements(tree.root_node());
+    while let Some(element) = elements.pop() {
+        elements.extend(child_elements(element));
+        let Some(tag) = element_tag(element) else {
+            continue;
+        };
+        let name = tag_name(tag, content);
+        let mut cursor = tag.walk();
+        for attribute in tag.children(&mut cursor) {
+            if attribute.kind() != "attribute" {
+                continue;
+            }
+            let (Some(attr), Some(value)) = (
+                attribute.child(0),
+                attribute.child(2).and_then(|v| v.child(1)),
+            ) else {
+                continue;
+            };
+            if value.kind() != "attribute_value" {
+                continue;
+            }
+            if let Some((kind, declaration)) =
+                attribute_identifier(name, get_node_str(attr, content), is_layout)
+            {
+                identifiers.push((kind, value, declaration));
+            }
+        }
+        if tag_attribute_value(tag, content, "xsi:type")
+            .is_some_and(|t| get_node_str(t, content) == "object")
+        {
+            let mut cursor = element.walk();
+            let text = element.children(&mut cursor).find(|n| n.kind() == "text");
+            if let Some(text) = text {
+                identifiers.push((XmlIdentifier::Class, text, false));
+            }
+        }
+    }
+
+    let identifier_text = |node: Node| get_node_str(node, content).trim().trim_start_matches('\\');
+    let (kind, text) = identifiers.iter().find_map(|(kind, node, _)| {
+        node_at_position(*node, pos).then(|| (*kind, identifier_text(*node)))
+    })?;
+    if text.is_empty() || text == "-" {
+        return None;
+    }
+    let mut occurrences = identifiers
+        .into_iter()
+        .filter(|(k, node, _)| *k == kind && identifier_text(*node) == text)
+        .map(|(_, node, declaration)| XmlOccurrence {
+            range: get_range_from_node(node),
+            declaration,
+        })
+        .collect::<Vec<_>>();
+    occurrences.sort_by_key(|o| o.range.start);
+    Some(occurrences)
+}
+
+/// Kind of identifier of the tag attribute, and if the attribute declares it.
+fn attribute_identifier(tag: &str, attr: &str, is_layout: bool) -> Option<(XmlIdentifier, bool)> {
+    match (tag, attr) {
+        ("block" | "container", "name") if is_layout => Some((XmlIdentifier::LayoutName, true)),
+        ("referenceBlock" | "referenceContainer" | "remove", "name") if is_layout => {
+            Some((XmlIdentifier::LayoutName, false))
+        }
+        (
+            "block" | "container" | "referenceBlock" | "referenceContainer" | "move",
+            "before" | "after",
+        )
+        | ("move", "element" | "destination")
+            if is_layout =>
+        {
+            Some((XmlIdentifier::LayoutName, false))
+        }
+        ("virtualType", "name") => Some((XmlIdentifier::Class, true)),
+        ("type", "name")
+        | ("preference", "for")
+        | ("preference" | "plugin" | "virtualType", "type") => Some((XmlIdentifier::Class, false)),
+        (_, "class" | "instance") => Some((XmlIdentifier::Class, false)),
+        _ => None,
+    }
+}
+
+fn element_tag(element: Node) -> Option<Node> {
+    element
+        .child(0)
+        .filter(|n| n.kind() == "start_tag" || n.kind() == "self_closing_tag")
+}
+
+fn child_elements(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|n| n.kind() == "element")
+        .collect()
+}
+
+/// Html parser splits tag names on underscores (`source_model` becomes tag `source` with
+/// attribute `_model`), so the name is taken from the raw tag text instead.
+fn tag_name<'a>(tag: Node, content: &'a str) -> &'a str {
+    tag.utf8_text(content.as_bytes())
+        .unwrap_or("")
+        .trim_start_matches("</")
+        .trim_start_matches('<')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or("")
+}
+
+fn tag_attribute_value<'a>(tag: Node<'a>, content: &str, attr: &str) -> Option<Node<'a>> {
+    let mut cursor = tag.walk();
+    let attribute = tag.children(&mut cursor).find(|n| {
+        n.kind() == "attribute"
+            && n.child(0)
+                .is_some_and(|name| get_node_str(name, content) == attr)
+    })?;
+    let value = attribute.child(2)?;
+    value.child(1).filter(|n| n.kind() == "attribute_value")
+}
+
+/// Class name from `<type name="">` when position is inside of its `<plugin/>` declaration.
+pub fn get_plugin_type_from_position(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<String> {
+    if !path.to_path_str().ends_with("di.xml") {
+        return None;
+    }
+    let content = state.get_file(path)?;
+    get_plugin_type_at_pos(content, pos)
+}
+
+fn get_plugin_type_at_pos(content: &str, pos: Position) -> Option<String> {
+    let tree = parse(content);
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let mut node = tree.root_node().descendant_for_point_range(point, point)?;
+    let plugin = loop {
+        if node.kind() == "element" && tag_name(element_tag(node)?, content) == "plugin" {
+            break node;
+        }
+        node = node.parent()?;
+    };
+    let type_tag = element_tag(plugin.parent()?)?;
+    if tag_name(type_tag, content) != "type" {
+        return None;
+    }
+    let name = tag_attribute_value(type_tag, content, "name")?;
+    Some(get_node_str(name, content).trim_matches('\\').into())
+}
+
+/// Name of `<type/>` (or `<virtualType/>`) under the cursor, only the opening tag counts,
+/// so hovers inside still work.
+pub fn get_pool_type_from_position(state: &State, path: &PathBuf, pos: Position) -> Option<String> {
+    if !path.to_path_str().ends_with("di.xml") {
+        return None;
+    }
+    let content = state.get_file(path)?;
+    get_pool_type_at_pos(content, pos)
+}
+
+fn get_pool_type_at_pos(content: &str, pos: Position) -> Option<String> {
+    let tree = parse(content);
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let mut node = tree.root_node().descendant_for_point_range(point, point)?;
+    let tag = loop {
+        if node.kind() == "start_tag" {
+            break node;
+        }
+        node = node.parent()?;
+    };
+    if !matches!(tag_name(tag, content), "type" | "virtualType") {
+        return None;
+    }
+    let name = tag_attribute_value(tag, content, "name")?;
+    Some(get_node_str(name, content).trim_matches('\\').into())
+}
+
+/// Class of `xsi:type="object"` item under the cursor, when it is a console command added
+/// to `Magento\Framework\Console\CommandList` arguments.
+pub fn get_console_command_from_position(
+    state: &State,
+    path: &PathBuf,
+    pos: Position,
+) -> Option<String> {
+    if !path.to_path_str().ends_with("di.xml") {
+        return None;
+    }
+    let content = state.get_file(path)?;
+    get_console_command_at_pos(content, pos)
+}
+
+fn get_console_command_at_pos(content: &str, pos: Position) -> Option<String> {
+    let tree = parse(content);
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let text = tree.root_node().descendant_for_point_range(point, point)?;
+    if text.kind() != "text" {
+        return None;
+    }
+    let item = text.parent()?;
+    let item_tag = element_tag(item)?;
+    if tag_name(item_tag, content) != "item"
+        || tag_attribute_value(item_tag, content, "xsi:type")
+            .is_none_or(|t| get_node_str(t, content) != "object")
+    {
+        return None;
+    }
+    let mut element = item.parent()?;
+    loop {
+        let tag = element_tag(element)?;
+        match tag_name(tag, content) {
+            "item" | "argument" | "arguments" => element = element.parent()?,
+            "type" => {
+                let name = tag_attribute_value(tag, content, "name")?;
+                if get_node_str(name, content).trim_matches('\\') != CONSOLE_COMMAND_LIST {
+                    return None;
+                }
+                break;
+            }
+            _ => return None,
+        }
+    }
+    let class = get_node_str(text, content).trim().trim_start_matches('\\');
+    (!class.is_empty()).then(|| class.into())
+}
+
+/// `xsi:type="object"` items of `xsi:type="array"` argument, keyed by the argument and item
+/// names (like `argument/item/nested`).
+fn collect_pool_items(
+    element: Node,
+    content: &str,
+    uri: &Url,
+    element_name: &str,
+    prefix: &str,
+    items: &mut Vec<(String, DiValue)>,
+) {
+    let Some(tag) = element_tag(element) else {
+        return;
+    };
+    if tag_name(tag, content) != element_name {
+        return;
+    }
+    let Some(name) = tag_attribute_value(tag, content, "name") else {
+        return;
+    };
+    let key = if prefix.is_empty() {
+        get_node_str(name, content).to_string()
+    } else {
+        format!("{prefix}/{}", get_node_str(name, content))
+    };
+    match tag_attribute_value(tag, content, "xsi:type").map(|t| get_node_str(t, content)) {
+        Some("array") => {
+            for item in child_elements(element) {
+                collect_pool_items(item, content, uri, "item", &key, items);
+            }
+        }
+        Some(xsi_type @ "object") if element_name == "item" => {
+            let mut cursor = element.walk();
+            let Some(text) = element.children(&mut cursor).find(|n| n.kind() == "text") else {
+                return;
+            };
+            let class = get_node_str(text, content).trim();
+            if !class.is_empty() {
+                items.push((
+                    key,
+                    DiValue {
+                        xsi_type: Some(xsi_type.into()),
+                        value: class.trim_start_matches('\\').into(),
+                        location: Location {
+                            uri: uri.clone(),
+                            range: get_range_from_node(text),
+                        },
+                    },
+                ));
+            }
+        }
+        _ => (),
+    }
+}
+
+fn try_config_path_item(content: &str, pos: Position) -> Option<M2Item> {
+    let tree = parse(content);
+    let point = Point {
+        row: pos.line as usize,
+        column: pos.character as usize,
+    };
+    let mut node = tree.root_node().descendant_for_point_range(point, point)?;
+    while !matches!(
+        node.kind(),
+        "start_tag" | "self_closing_tag" | "end_tag" | "text"
+    ) {
+        node = node.parent()?;
+    }
+    if node.kind() == "text" {
+        let text = get_node_str(node, content);
+        if text.contains('\\') || text.contains("::") {
+            return None;
+        }
+    }
+
+    let mut names = vec![];
+    let mut element = node.parent();
+    while let Some(el) = element {
+        if el.kind() == "element" {
+            names.push(tag_name(element_tag(el)?, content));
+        }
+        element = el.parent();
+    }
+    names.reverse();
+
+    let config_path = match names.as_slice() {
+        ["config", "default", path @ ..] => path,
+        ["config", "websites" | "stores", _, path @ ..] => path,
+        _ => return None,
+    };
+    if config_path.len() < 3 {
+        return None;
+    }
+    Some(M2Item::ConfigPath(config_path.join("/")))
+}
+
 pub fn get_current_position_path(content: &str, pos: Position) -> Option<XmlCompletion> {
-    let tree = tree_sitter_parsers::parse(content, "html");
+    let tree = parse(content);
     let query = queries::xml_current_position_path();
     let mut cursor = QueryCursor::new();
     let captures = cursor.captures(query, tree.root_node(), content.as_bytes());
@@ -237,28 +1455,66 @@ fn get_item_from_pos(
     path: &PathBuf,
     pos: Position,
 ) -> Option<M2Item> {
+    if path.has_components(&["etc", "config.xml"]) {
+        if let Some(item) = try_config_path_item(content, pos) {
+            return Some(item);
+        }
+    }
+
     let tag = get_xml_tag_at_pos(content, pos)?;
 
     match tag.hover_on {
         XmlPart::Attribute(ref attr_name) => match attr_name.as_str() {
+            "name" if tag.name == "module" => {
+                Some(M2Item::Module(tag.attributes.get(attr_name)?.into()))
+            }
+            "name" if tag.name == "uiComponent" => {
+                m2::try_ui_component_item_from_str(tag.attributes.get(attr_name)?, &path.get_area())
+            }
+            "class" if ui_component_name(path).is_some() => {
+                try_ui_class_item(tag.attributes.get(attr_name)?, path)
+            }
             "method" | "instance" | "class" => try_method_item_from_tag(&tag).or_else(|| {
                 m2::try_any_item_from_str(tag.attributes.get(attr_name)?, &path.get_area())
             }),
+            "src" if matches!(tag.name.as_str(), "css" | "script" | "link") => {
+                m2::try_web_asset_item_from_str(tag.attributes.get(attr_name)?, &path.get_area())
+            }
             "template" => {
                 m2::try_phtml_item_from_str(tag.attributes.get(attr_name)?, &path.get_area())
             }
+            "component" => js::text_to_component(state, tag.attributes.get(attr_name)?, path),
+            "before" | "after"
+                if is_layout_file(path)
+                    && matches!(
+                        tag.name.as_str(),
+                        "block" | "referenceBlock" | "container" | "referenceContainer" | "move"
+                    ) =>
+            {
+                try_layout_sibling_item(state, tag.attributes.get(attr_name)?, &path.get_area())
+            }
             _ => m2::try_any_item_from_str(tag.attributes.get(attr_name)?, &path.get_area()),
         },
         XmlPart::Text => {
+            if let Some(item) = try_ui_data_source_item(state, tag.text.trim(), path) {
+                return Some(item);
+            }
             let text = tag.text.trim_matches('\\');
             let empty = String::new();
             let xsi_type = tag.attributes.get("xsi:type").unwrap_or(&empty);
+            if ui_component_name(path).is_some() && is_ui_class_value(&tag, xsi_type) {
+                return try_ui_class_item(text, path);
+            }
 
             match xsi_type.as_str() {
                 "object" => Some(m2::get_class_item_from_str(text)),
-                "init_parameter" => m2::try_const_item_from_str(text),
+                "init_parameter" | "const" => m2::try_const_item_from_str(text),
                 "string" => {
-                    if tag.attributes.get("name").is_some_and(|s| s == "component") {
+                    if let Some(item) = m2::try_deploy_config_item_from_str(text) {
+                        Some(item)
+                    } else if let Some(item) = try_ui_component_item(state, text, path) {
+                        Some(item)
+                    } else if tag.attributes.get("name").is_some_and(|s| s == "component") {
                         js::text_to_component(state, text, path)
                     } else {
                         m2::try_any_item_from_str(text, &path.get_area())
@@ -271,8 +1527,72 @@ fn get_item_from_pos(
     }
 }
 
+/// Class references of UI components, objects and string `class` items (or arguments, like
+/// `class` of `dataProvider`), other string items are labels, data scopes and such.
+fn is_ui_class_value(tag: &XmlTag, xsi_type: &str) -> bool {
+    match xsi_type {
+        "object" => true,
+        "string" => {
+            matches!(tag.name.as_str(), "item" | "argument")
+                && tag
+                    .attributes
+                    .get("name")
+                    .is_some_and(|name| name == "class")
+        }
+        _ => false,
+    }
+}
+
+/// Class or virtual type, UI components often use virtual types of option sources.
+fn try_ui_class_item(text: &str, path: &PathBuf) -> Option<M2Item> {
+    let text = text.trim().trim_start_matches('\\');
+    if text.is_empty() {
+        return None;
+    }
+    Some(M2Item::ClassOrVirtualType(text.into(), path.get_area()))
+}
+
+/// UI component of string argument, `Vendor_Module::component_name` or bare name of
+/// indexed component (other strings are too common to be treated as component names).
+fn try_ui_component_item(state: &State, text: &str, path: &PathBuf) -> Option<M2Item> {
+    match m2::try_ui_component_item_from_str(text, &path.get_area())? {
+        M2Item::UiComponent(None, name, _) if state.get_ui_component_files(&name).is_empty() => {
+            None
+        }
+        item => Some(item),
+    }
+}
+
+/// Data source referenced as `<component>.<data_source>` (like `<provider/>` or `<dep/>`)
+/// in UI component file, only data sources of the same component are resolved.
+fn try_ui_data_source_item(state: &State, text: &str, path: &Path) -> Option<M2Item> {
+    let component = ui_component_name(path)?;
+    let (scope, name) = text.split_once('.')?;
+    if scope != component || state.get_ui_data_sources(component, name).is_empty() {
+        return None;
+    }
+    Some(M2Item::UiDataSource(component.into(), name.into()))
+}
+
+/// Sibling named by `before`/`after` positioning attribute, `-` (first or last) is not a
+/// name, and names not declared in any indexed layout are skipped.
+fn try_layout_sibling_item(state: &State, name: &str, area: &M2Area) -> Option<M2Item> {
+    let name = name.trim();
+    if name.is_empty() || name == "-" {
+        return None;
+    }
+    [M2LayoutElement::Block, M2LayoutElement::Container]
+        .into_iter()
+        .find(|element| {
+            !state
+                .get_layout_name_locations(area, *element, name)
+                .is_empty()
+        })
+        .map(|element| M2Item::LayoutName(element, name.into(), area.clone()))
+}
+
 fn get_xml_tag_at_pos(content: &str, pos: Position) -> Option<XmlTag> {
-    let tree = tree_sitter_parsers::parse(content, "html");
+    let tree = parse(content);
     let query = queries::xml_tag_at_pos();
 
     let mut cursor = QueryCursor::new();
@@ -331,12 +1651,12 @@ fn get_xml_tag_at_pos(content: &str, pos: Position) -> Option<XmlTag> {
 }
 
 fn try_method_item_from_tag(tag: &XmlTag) -> Option<M2Item> {
-    if tag.attributes.get("instance").is_some() && tag.attributes.get("method").is_some() {
+    if tag.attributes.contains_key("instance") && tag.attributes.contains_key("method") {
         Some(M2Item::Method(
             tag.attributes.get("instance")?.into(),
             tag.attributes.get("method")?.into(),
         ))
-    } else if tag.attributes.get("class").is_some() && tag.attributes.get("method").is_some() {
+    } else if tag.attributes.contains_key("class") && tag.attributes.contains_key("method") {
         Some(M2Item::Method(
             tag.attributes.get("class")?.into(),
             tag.attributes.get("method")?.into(),
@@ -349,6 +1669,7 @@ fn try_method_item_from_tag(tag: &XmlTag) -> Option<M2Item> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::m2::M2Area;
     use std::path::PathBuf;
 
     fn get_position_from_test_xml(xml: &str) -> Position {
@@ -389,6 +1710,51 @@ mod test {
         assert_eq!(item, Some(M2Item::Class("A\\B\\C".into())));
     }
 
+    #[test]
+    fn test_get_item_from_pos_class_string_in_ui_component() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?>
+<argument name="dataProvider" xsi:type="configurableObject">
+    <argument name="class" xsi:type="string">someItem|DataProvider</argument>
+</argument>"#,
+            "/a/view/adminhtml/ui_component/some_listing.xml",
+        );
+
+        assert_eq!(
+            item,
+            Some(M2Item::ClassOrVirtualType(
+                "someItemDataProvider".into(),
+                M2Area::Adminhtml
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_options_class_in_ui_component() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><options class="\A\Model\Config\Source\|Status"/>"#,
+            "/a/view/adminhtml/ui_component/some_listing.xml",
+        );
+
+        assert_eq!(
+            item,
+            Some(M2Item::ClassOrVirtualType(
+                "A\\Model\\Config\\Source\\Status".into(),
+                M2Area::Adminhtml
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_label_in_ui_component() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><item name="label" xsi:type="string">stat|us</item>"#,
+            "/a/view/adminhtml/ui_component/some_listing.xml",
+        );
+
+        assert_eq!(item, None);
+    }
+
     #[test]
     fn test_get_item_from_pos_template_in_tag_attribute() {
         let item = get_test_item_from_pos(
@@ -713,8 +2079,8 @@ mod test {
 
         let item = item.unwrap();
         assert_eq!(item.name, "item");
-        assert!(item.attributes.get("name").is_some());
-        assert!(item.attributes.get("attribute").is_some());
+        assert!(item.attributes.contains_key("name"));
+        assert!(item.attributes.contains_key("attribute"));
     }
 
     #[test]
@@ -900,4 +2266,702 @@ mod test {
         assert!(item.match_path("/source[$text]"));
         assert!(item.attribute_eq("_model", ""));
     }
+
+    #[test]
+    fn test_get_orphan_layout_references_by_area() {
+        let mut state = State::new();
+        state.set_file(
+            Path::new("/a/view/frontend/layout/default.xml"),
+            r#"<page><body><block name="front.block"/><container name="base.name"/></body></page>"#,
+        );
+        state.set_file(
+            Path::new("/a/view/base/page_layout/empty.xml"),
+            r#"<layout><container name="base.container"/></layout>"#,
+        );
+        let references = r#"<page><body>
+            <referenceBlock name="front.block"/>
+            <referenceContainer name="base.container"/>
+            <referenceBlock name="base.name"/>
+        </body></page>"#;
+        let admin_layout = PathBuf::from("/a/view/adminhtml/layout/default.xml");
+        state.set_file(&admin_layout, references);
+
+        let names = |path| {
+            get_orphan_layout_references(&state, path)
+                .into_iter()
+                .map(|(element, name, _)| (element, name))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&admin_layout),
+            vec![
+                (M2LayoutElement::Block, "front.block".into()),
+                (M2LayoutElement::Block, "base.name".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_orphan_layout_references_skips_unknown_area() {
+        let mut state = State::new();
+        let path = PathBuf::from("/a/layout/default.xml");
+        state.set_file(&path, r#"<page><referenceBlock name="missing"/></page>"#);
+        assert!(get_orphan_layout_references(&state, &path).is_empty());
+    }
+
+    #[test]
+    fn test_get_item_from_pos_layout_sibling_name() {
+        let mut state = State::new();
+        let path = PathBuf::from("/a/view/frontend/layout/default.xml");
+        state.set_file(
+            &path,
+            r#"<page><body><container name="main"/><block name="first"/></body></page>"#,
+        );
+        let item = |xml: &str| {
+            let pos = get_position_from_test_xml(xml);
+            get_item_from_pos(&state, &xml.replace('|', ""), &path, pos)
+        };
+        assert_eq!(
+            item(r#"<page><block name="second" after="fi|rst"/></page>"#),
+            Some(M2Item::LayoutName(
+                M2LayoutElement::Block,
+                "first".into(),
+                M2Area::Frontend
+            ))
+        );
+        assert_eq!(
+            item(r#"<page><referenceContainer name="side" before="ma|in"/></page>"#),
+            Some(M2Item::LayoutName(
+                M2LayoutElement::Container,
+                "main".into(),
+                M2Area::Frontend
+            ))
+        );
+        assert_eq!(
+            item(r#"<page><block name="second" before="|-"/></page>"#),
+            None
+        );
+        assert_eq!(
+            item(r#"<page><block name="second" after="unkn|own"/></page>"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_ui_data_source_reference() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/Other/view/adminhtml/ui_component/some_listing.xml"),
+            r#"<listing>
+    <dataSource name="some_listing_data_source">
+        <dataProvider class="Some\Provider" name="some_listing_data_source"/>
+    </dataSource>
+</listing>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/Some/view/adminhtml/ui_component/other_form.xml"),
+            r#"<form><dataSource name="other_form_data_source"/></form>"#,
+        );
+        let path = PathBuf::from("/a/Some/view/adminhtml/ui_component/some_listing.xml");
+        let item = |xml: &str| {
+            let pos = get_position_from_test_xml(xml);
+            get_item_from_pos(&state, &xml.replace('|', ""), &path, pos)
+        };
+        assert_eq!(
+            item("<listing><settings><deps><dep>some_listing.some_listing_da|ta_source</dep></deps></settings></listing>"),
+            Some(M2Item::UiDataSource(
+                "some_listing".into(),
+                "some_listing_data_source".into()
+            ))
+        );
+        assert_eq!(
+            state
+                .get_ui_data_sources("some_listing", "some_listing_data_source")
+                .len(),
+            2
+        );
+        assert_eq!(
+            item("<listing><columns><settings><provider>other_form.other_form_data_so|urce</provider></settings></columns></listing>"),
+            None
+        );
+        assert_eq!(
+            item("<listing><settings><deps><dep>some_listing.missing_da|ta_source</dep></deps></settings></listing>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_plugin_type_at_pos() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="\Some\Module\Api\ItemInterface">
+        <plugin name="some_plugin" type="Some\Module\Plugin\It|em"/>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(
+            get_plugin_type_at_pos(&xml.replace('|', ""), pos),
+            Some("Some\\Module\\Api\\ItemInterface".into())
+        );
+    }
+
+    #[test]
+    fn test_get_plugin_type_at_pos_outside_plugin() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="Some\Module\Api\ItemInter|face">
+        <plugin name="some_plugin" type="Some\Module\Plugin\Item"/>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(get_plugin_type_at_pos(&xml.replace('|', ""), pos), None);
+    }
+
+    #[test]
+    fn test_get_pool_type_at_pos() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="\Some\Module\Model\Item|Pool">
+        <arguments/>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(
+            get_pool_type_at_pos(&xml.replace('|', ""), pos),
+            Some("Some\\Module\\Model\\ItemPool".into())
+        );
+    }
+
+    #[test]
+    fn test_get_pool_type_at_pos_inside_type() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="Some\Module\Model\ItemPool">
+        <arguments>
+            <argument name="modifiers" xsi:type="ar|ray"/>
+        </arguments>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(get_pool_type_at_pos(&xml.replace('|', ""), pos), None);
+    }
+
+    #[test]
+    fn test_index_pool_items() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/etc/di.xml"),
+            r#"<?xml version="1.0"?>
+<config>
+    <type name="Some\Module\Model\ItemPool">
+        <arguments>
+            <argument name="title" xsi:type="string">Pool</argument>
+            <argument name="modifiers" xsi:type="array">
+                <item name="price" xsi:type="object">\Some\Module\Model\PriceModifier</item>
+                <item name="nested" xsi:type="array">
+                    <item name="stock" xsi:type="object">someStockModifier</item>
+                    <item name="sortOrder" xsi:type="number">10</item>
+                </item>
+            </argument>
+        </arguments>
+    </type>
+</config>"#,
+        );
+        assert_eq!(
+            state.get_di_pool_items(&M2Area::Base, "Some\\Module\\Model\\ItemPool"),
+            vec![
+                DiPoolItem {
+                    key: "modifiers/nested/stock".into(),
+                    class: "someStockModifier".into(),
+                },
+                DiPoolItem {
+                    key: "modifiers/price".into(),
+                    class: "Some\\Module\\Model\\PriceModifier".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_xml_class_usages() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/etc/di.xml"),
+            r#"<?xml version="1.0"?>
+<config>
+    <type name="A\Api\ItemInterface">
+        <plugin name="item_plugin" type="A\Plugin\ItemPlugin"/>
+    </type>
+</config>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/etc/webapi.xml"),
+            r#"<?xml version="1.0"?>
+<routes>
+    <route url="/V1/items" method="GET">
+        <service class="A\Api\ItemRepositoryInterface" method="getList"/>
+    </route>
+</routes>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/view/frontend/layout/default.xml"),
+            r#"<?xml version="1.0"?>
+<page>
+    <body>
+        <block class="A\Block\Item" name="item">
+            <arguments>
+                <argument name="view_model" xsi:type="object">A\ViewModel\Item</argument>
+                <argument name="renderers" xsi:type="array">
+                    <item name="price" xsi:type="object">\A\ViewModel\Price</item>
+                </argument>
+            </arguments>
+        </block>
+    </body>
+</page>"#,
+        );
+        state.set_file(
+            &PathBuf::from("/a/view/adminhtml/ui_component/item_listing.xml"),
+            r#"<?xml version="1.0"?>
+<listing>
+    <argument name="data" xsi:type="array">
+        <item name="provider" xsi:type="object">A\Ui\Provider</item>
+    </argument>
+</listing>"#,
+        );
+        for class in [
+            "A\\Api\\ItemInterface",
+            "A\\Plugin\\ItemPlugin",
+            "A\\Api\\ItemRepositoryInterface",
+            "A\\Block\\Item",
+            "A\\ViewModel\\Item",
+            "A\\ViewModel\\Price",
+            "A\\Ui\\Provider",
+        ] {
+            assert_eq!(state.count_class_usages(class), 1, "{class}");
+        }
+
+        state.clear_from_source(&PathBuf::from("/a/etc/webapi.xml"));
+        assert_eq!(
+            state.count_class_usages("A\\Api\\ItemRepositoryInterface"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_get_occurrences_at_pos_of_layout_name() {
+        let xml = r#"<?xml version="1.0"?>
+<page>
+    <body>
+        <referenceContainer name="content">
+            <block class="A\Block\Item" name="some.item" template="A_B::item.phtml"/>
+            <block class="A\Block\Item" name="other.item" after="some.i|tem"/>
+        </referenceContainer>
+        <move element="some.item" destination="sidebar" before="-"/>
+    </body>
+</page>"#;
+        let pos = get_position_from_test_xml(xml);
+        let occurrences = get_occurrences_at_pos(&xml.replace('|', ""), true, pos)
+            .expect("Should have occurrences");
+        assert_eq!(
+            occurrences
+                .iter()
+                .map(|o| (o.range.start.line, o.declaration))
+                .collect::<Vec<_>>(),
+            vec![(4, true), (5, false), (7, false)]
+        );
+    }
+
+    #[test]
+    fn test_get_occurrences_at_pos_of_class() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <preference for="A\Api\ItemInterface" type="A\Model\Item"/>
+    <type name="\A\Model\It|em"/>
+    <virtualType name="someItem" type="A\Model\Item">
+        <arguments>
+            <argument name="item" xsi:type="object">A\Model\Item</argument>
+            <argument name="name" xsi:type="string">A\Model\Item</argument>
+        </arguments>
+    </virtualType>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        let occurrences = get_occurrences_at_pos(&xml.replace('|', ""), false, pos)
+            .expect("Should have occurrences");
+        assert_eq!(
+            occurrences
+                .iter()
+                .map(|o| (o.range.start.line, o.declaration))
+                .collect::<Vec<_>>(),
+            vec![(2, false), (3, false), (4, false), (6, false)]
+        );
+    }
+
+    #[test]
+    fn test_get_occurrences_at_pos_outside_of_identifier() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="A\Model\Item">
+        <arguments>
+            <argument name="pre|fix" xsi:type="string">item</argument>
+        </arguments>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(
+            get_occurrences_at_pos(&xml.replace('|', ""), false, pos),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_console_command_at_pos() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="Magento\Framework\Console\CommandList">
+        <arguments>
+            <argument name="commands" xsi:type="array">
+                <item name="item_list" xsi:type="object">\Some\Module\Console\Command\Item|List</item>
+            </argument>
+        </arguments>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(
+            get_console_command_at_pos(&xml.replace('|', ""), pos),
+            Some("Some\\Module\\Console\\Command\\ItemList".into())
+        );
+    }
+
+    #[test]
+    fn test_get_console_command_at_pos_of_other_type() {
+        let xml = r#"<?xml version="1.0"?>
+<config>
+    <type name="Some\Module\Model\ItemPool">
+        <arguments>
+            <argument name="commands" xsi:type="array">
+                <item name="item_list" xsi:type="object">Some\Module\Console\Command\Item|List</item>
+            </argument>
+        </arguments>
+    </type>
+</config>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(get_console_command_at_pos(&xml.replace('|', ""), pos), None);
+    }
+
+    #[test]
+    fn test_index_virtual_type() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/etc/di.xml"),
+            r#"<?xml version="1.0"?>
+<config>
+    <virtualType name="someStockModifier" type="otherModifier"/>
+    <virtualType name="otherModifier" type="Some\Module\Model\StockModifier"/>
+</config>"#,
+        );
+        assert_eq!(
+            state.resolve_di_virtual_type(&M2Area::Frontend, "someStockModifier"),
+            Some("Some\\Module\\Model\\StockModifier".into())
+        );
+        assert_eq!(
+            state.resolve_di_virtual_type(&M2Area::Base, "Some\\Module\\Model\\StockModifier"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_index_file_with_doctype_and_entities() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/etc/di.xml"),
+            r#"<?xml version="1.0"?>
+<!DOCTYPE config [
+    <!ENTITY ext SYSTEM "file:///etc/passwd">
+    <!ENTITY fake '<preference for="A\FromEntity" type="A\Injected"/>'>
+]>
+<config>
+    <preference for="A\FooInterface" type="A\Foo"/>
+    <preference for="A\BarInterface" type="&ext;"/>
+</config>"#,
+        );
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Base, "A\\FooInterface"),
+            Some("A\\Foo".into())
+        );
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Base, "A\\BarInterface"),
+            Some("&ext;".into())
+        );
+        assert_eq!(
+            state.resolve_di_preference(&M2Area::Base, "A\\FromEntity"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mask_doctype_keeps_positions() {
+        let mut bytes = b"<!DOCTYPE a [\n<!ENTITY b \"]>\">\n]>\n<a/>".to_vec();
+        mask_doctype(&mut bytes);
+        assert_eq!(bytes, b"             \n                \n  \n<a/>".to_vec());
+    }
+
+    #[test]
+    fn test_index_view_images() {
+        let mut state = State::new();
+        state.set_file(
+            &PathBuf::from("/a/etc/view.xml"),
+            r#"<?xml version="1.0"?>
+<view>
+    <media>
+        <images module="Magento_Catalog">
+            <image id="category_page_grid" type="small_image">
+                <width>240</width>
+                <aspect_ratio>false</aspect_ratio>
+            </image>
+            <image id="cart_page_product_thumbnail" type="small_image"/>
+        </images>
+    </media>
+</view>"#,
+        );
+        let images = state.get_view_images("Magento_Catalog", "category_page_grid");
+        assert_eq!(images.len(), 1);
+        assert_eq!(
+            images[0].values,
+            vec![
+                ("type".into(), "small_image".into()),
+                ("width".into(), "240".into()),
+                ("aspect_ratio".into(), "false".into()),
+            ]
+        );
+        assert_eq!(
+            state
+                .get_view_images("Magento_Catalog", "cart_page_product_thumbnail")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_get_view_image_at_pos() {
+        let xml = r#"<view><media><images module="Magento_Catalog">
+    <image id="category_pa|ge_grid" type="small_image"><width>240</width></image>
+</images></media></view>"#;
+        let pos = get_position_from_test_xml(xml);
+        assert_eq!(
+            get_view_image_at_pos(&xml.replace('|', ""), pos),
+            Some(("Magento_Catalog".into(), "category_page_grid".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_class_constant_in_const_item() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><items><item name="foo" xsi:type="const">\Some\Module\Mod|el\Foo::class</item></items>"#,
+            "/a/etc/di.xml",
+        );
+        assert_eq!(item, Some(M2Item::Class("Some\\Module\\Model\\Foo".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_deploy_config_in_string_argument() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><arguments><argument name="prefix" xsi:type="string">%cache/fron|tend/id_prefix%</argument></arguments>"#,
+            "/a/etc/di.xml",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::DeployConfig("cache/frontend/id_prefix".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_string_argument_without_percent_delimiters() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><arguments><argument name="prefix" xsi:type="string">cache/fron|tend%</argument></arguments>"#,
+            "/a/etc/di.xml",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_get_item_from_pos_css_src_in_layout() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?><page><head><css src="Some_Module::css/sty|les.css"/></head></page>"#,
+            "/a/view/frontend/layout/default.xml",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::WebAsset(
+                Some("Some_Module".into()),
+                "css/styles.css".into(),
+                M2Area::Frontend
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_module_name_in_sequence() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?>
+            <config>
+                <module name="Some_Module">
+                    <sequence>
+                        <module name="Other_|Module"/>
+                    </sequence>
+                </module>
+            </config>
+            "#,
+            "/a/etc/module.xml",
+        );
+        assert_eq!(item, Some(M2Item::Module("Other_Module".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_config_path_in_config_xml() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?>
+            <config>
+                <default>
+                    <web>
+                        <unsecure>
+                            <base_url>http://exa|mple.com/</base_url>
+                        </unsecure>
+                    </web>
+                </default>
+            </config>
+            "#,
+            "/a/etc/config.xml",
+        );
+        assert_eq!(
+            item,
+            Some(M2Item::ConfigPath("web/unsecure/base_url".into()))
+        );
+    }
+
+    #[test]
+    fn test_get_item_from_pos_config_path_in_website_scope() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?>
+            <config>
+                <websites>
+                    <base>
+                        <general>
+                            <locale>
+                                <co|de>en_US</code>
+                            </locale>
+                        </general>
+                    </base>
+                </websites>
+            </config>
+            "#,
+            "/a/etc/config.xml",
+        );
+        assert_eq!(item, Some(M2Item::ConfigPath("general/locale/code".into())));
+    }
+
+    #[test]
+    fn test_get_item_from_pos_config_path_needs_full_path() {
+        let item = get_test_item_from_pos(
+            r#"<?xml version="1.0"?>
+            <config>
+                <default>
+                    <web>
+                        <unse|cure>
+                        </unsecure>
+                    </web>
+                </default>
+            </config>
+            "#,
+            "/a/etc/config.xml",
+        );
+        assert_eq!(item, None);
+    }
+
+    #[test]
+    fn test_parse_children_of_html_void_tags() {
+        let content = r#"<images><image id="a"><width>1</width></image><menuitem/></images>"#;
+        let tree = parse(content);
+        let width = tree
+            .root_node()
+            .descendant_for_byte_range(23, 23)
+            .and_then(|n| n.parent())
+            .and_then(|n| n.parent())
+            .expect("Width element should be parsed");
+        let image = width.parent().expect("Width should have parent");
+        assert_eq!(image.kind(), "element");
+        assert_eq!(get_node_str(image, content).get(..7), Some("<image "));
+    }
+
+    #[test]
+    fn test_update_index_keeps_opened_file_from_buffer() {
+        let system_config = |field| {
+            format!(
+                r#"<config><system><section id="web"><group id="unsecure"><field id="{field}"/></group></section></system></config>"#
+            )
+        };
+        let root = std::env::temp_dir().join(format!("magento2-ls-{}", std::process::id()));
+        let path = root.append(&["etc", "adminhtml", "system.xml"]);
+        std::fs::create_dir_all(path.parent().expect("Path should have parent"))
+            .expect("Directory should be created");
+        std::fs::write(&path, system_config("saved")).expect("File should be written");
+
+        let state = State::new().into_arc();
+        state.lock().set_file(&path, system_config("unsaved"));
+        update_index(&state, &root);
+        std::fs::remove_dir_all(&root).ok();
+
+        let state = state.lock();
+        assert!(state
+            .get_system_config_fields("web/unsecure/saved")
+            .is_empty());
+        assert_eq!(
+            state.get_system_config_fields("web/unsecure/unsaved").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_update_index_from_system_config() {
+        let path = PathBuf::from(if cfg!(windows) {
+            "c:\\a\\etc\\adminhtml\\system.xml"
+        } else {
+            "/a/etc/adminhtml/system.xml"
+        });
+        let mut state = State::new();
+        update_index_from_system_config(
+            &mut state,
+            r#"<?xml version="1.0"?>
+            <config>
+                <system>
+                    <section id="web">
+                        <group id="unsecure">
+                            <field id="base_url" type="text"/>
+                            <group id="nested">
+                                <field id="deep"><label>Deep</label></field>
+                            </group>
+                        </group>
+                    </section>
+                </system>
+            </config>
+            "#,
+            &path,
+        );
+
+        let fields = state.get_system_config_fields("web/unsecure/base_url");
+        assert_eq!(fields.len(), 1);
+        assert_eq!(
+            fields[0].range.start,
+            Position {
+                line: 5,
+                character: 39
+            }
+        );
+        assert_eq!(
+            state
+                .get_system_config_fields("web/unsecure/nested/deep")
+                .len(),
+            1
+        );
+        assert!(state.get_system_config_fields("web/unsecure").is_empty());
+    }
 }
@@ -0,0 +1,7 @@
+<?php
+
+namespace Some\Module\Api;
+
+interface ItemExportInterface
+{
+}
@@ -0,0 +1,8 @@
+<?php
+
+namespace Some\Module\Api;
+
+interface ItemInterface
+{
+    public function getName();
+}
@@ -0,0 +1,14 @@
+<?php
+namespace Some\Module\Console\Command;
+
+use Symfony\Component\Console\Command\Command;
+
+class ItemList extends Command
+{
+    protected function configure()
+    {
+        $this->setName('some:item:list')
+            ->setDescription('List items');
+        parent::configure();
+    }
+}
@@ -0,0 +1,18 @@
+<?php
+
+namespace Some\Module\Model;
+
+use Magento\Framework\App\Config\ScopeConfigInterface;
+
+class Config
+{
+    public function __construct(
+        private ScopeConfigInterface $scopeConfig
+    ) {
+    }
+
+    public function isEnabled()
+    {
+        return $this->scopeConfig->isSetFlag('some_section/general/enabled');
+    }
+}
@@ -0,0 +1,13 @@
+<?php
+
+namespace Some\Module\Model\Config\Source;
+
+use Magento\Framework\Data\OptionSourceInterface;
+
+class ItemType implements OptionSourceInterface
+{
+    public function toOptionArray()
+    {
+        return [['value' => 'item', 'label' => __('Item')]];
+    }
+}
@@ -0,0 +1,10 @@
+<?php
+
+namespace Some\Module\Model;
+
+trait ExportLoggerTrait
+{
+    public function logExport(string $message)
+    {
+    }
+}
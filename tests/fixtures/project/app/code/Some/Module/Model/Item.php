@@ -0,0 +1,15 @@
+<?php
+
+namespace Some\Module\Model;
+
+use Some\Module\Api\ItemInterface;
+
+class Item implements ItemInterface
+{
+    public const TYPE = 'item';
+
+    public function getName()
+    {
+        return 'item';
+    }
+}
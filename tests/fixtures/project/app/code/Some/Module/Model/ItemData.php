@@ -0,0 +1,15 @@
+<?php
+
+namespace Some\Module\Model;
+
+/**
+ * @method string getLabel()
+ * @method $this setLabel(string $label)
+ */
+class ItemData
+{
+    public function getTitle()
+    {
+        return $this->getLabel();
+    }
+}
@@ -0,0 +1,14 @@
+<?php
+
+namespace Some\Module\Model;
+
+use Magento\Framework\App\Filesystem\DirectoryList;
+use Magento\Framework\Filesystem;
+
+class ItemExport implements \Some\Module\Api\ItemExportInterface
+{
+    public function getDirectory(Filesystem $filesystem)
+    {
+        return $filesystem->getDirectoryWrite(DirectoryList::VAR_DIR);
+    }
+}
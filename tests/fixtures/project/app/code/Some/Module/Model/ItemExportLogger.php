@@ -0,0 +1,8 @@
+<?php
+
+namespace Some\Module\Model;
+
+class ItemExportLogger
+{
+    use ExportLoggerTrait;
+}
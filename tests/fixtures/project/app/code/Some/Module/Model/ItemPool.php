@@ -0,0 +1,12 @@
+<?php
+
+namespace Some\Module\Model;
+
+use Some\Module\Api\ItemInterface;
+
+class ItemPool
+{
+    private const ITEMS = [
+        ItemInterface::class => Item::class,
+    ];
+}
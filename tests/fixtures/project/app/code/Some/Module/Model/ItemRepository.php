@@ -0,0 +1,45 @@
+<?php
+
+namespace Some\Module\Model;
+
+use Some\Module\Api\ItemInterface;
+
+class ItemRepository
+{
+    public function __construct(
+        private ItemInterface $item,
+        string $prefix = ''
+    ) {
+    }
+
+    public function getItemName()
+    {
+        return $this->item->getName();
+    }
+
+    /**
+     * @see \Some\Module\Model\Item::getName()
+     * @uses Item
+     */
+    public function getItemType()
+    {
+        return Item::TYPE;
+    }
+
+    /**
+     * @param ItemInterface $item
+     * @return ItemInterface|null
+     */
+    public function withItem(ItemInterface $item)
+    {
+        return $item;
+    }
+
+    /**
+     * @return Item
+     */
+    public function createItem()
+    {
+        return new Item();
+    }
+}
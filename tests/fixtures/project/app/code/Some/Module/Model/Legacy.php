@@ -0,0 +1,19 @@
+<?php
+namespace Some\Module\Model;
+
+class Legacy
+{
+    public const TYPE = 'legacy';
+}
+
+class LegacyItem extends Legacy
+{
+    public const TYPE = 'legacy_item';
+}
+
+class_alias(LegacyItem::class, 'Some\Module\Model\OldItem');
+
+function legacy_class(): string
+{
+    return OldItem::class;
+}
@@ -0,0 +1,7 @@
+<?php
+
+namespace Some\Module\Model;
+
+class SpecialItem extends Item
+{
+}
@@ -0,0 +1,13 @@
+<?php
+
+namespace Some\Module\Plugin;
+
+use Some\Module\Api\ItemInterface;
+
+class ItemPlugin
+{
+    public function afterGetName(ItemInterface $subject, $result)
+    {
+        return $result;
+    }
+}
@@ -0,0 +1,23 @@
+<?php
+
+namespace Some\Module\Setup\Patch\Data;
+
+use Magento\Framework\Setup\Patch\DataPatchInterface;
+
+class InstallItems implements DataPatchInterface
+{
+    public function apply()
+    {
+        return $this;
+    }
+
+    public static function getDependencies()
+    {
+        return [];
+    }
+
+    public function getAliases()
+    {
+        return [];
+    }
+}
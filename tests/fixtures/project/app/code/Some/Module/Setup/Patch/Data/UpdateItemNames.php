@@ -0,0 +1,26 @@
+<?php
+
+namespace Some\Module\Setup\Patch\Data;
+
+use Magento\Framework\Setup\Patch\DataPatchInterface;
+
+class UpdateItemNames implements DataPatchInterface
+{
+    public function apply()
+    {
+        return $this;
+    }
+
+    public static function getDependencies()
+    {
+        return [
+            InstallItems::class,
+            \Some\Module\Setup\Patch\Data\InstallItems::class,
+        ];
+    }
+
+    public function getAliases()
+    {
+        return [];
+    }
+}
@@ -0,0 +1,10 @@
+<?php
+
+namespace Some\Module\Test\Fixture;
+
+class ItemFixture
+{
+    public static function createItem()
+    {
+    }
+}
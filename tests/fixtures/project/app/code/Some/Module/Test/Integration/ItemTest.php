@@ -0,0 +1,17 @@
+<?php
+
+namespace Some\Module\Test\Integration;
+
+use PHPUnit\Framework\TestCase;
+
+class ItemTest extends TestCase
+{
+    /**
+     * @magentoDataFixture Some_Module::Test/Integration/_files/item.php
+     * @magentoDataFixture Test/Integration/_files/item.php
+     * @magentoDataFixture Some\Module\Test\Fixture\ItemFixture::createItem
+     */
+    public function testItem()
+    {
+    }
+}
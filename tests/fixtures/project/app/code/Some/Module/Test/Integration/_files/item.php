@@ -0,0 +1,5 @@
+<?php
+
+use Some\Module\Test\Fixture\ItemFixture;
+
+ItemFixture::createItem();
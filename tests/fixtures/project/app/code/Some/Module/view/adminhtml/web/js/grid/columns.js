@@ -0,0 +1,5 @@
+define(['Magento_Ui/js/grid/listing'], function (Listing) {
+    'use strict';
+
+    return Listing.extend({});
+});
@@ -0,0 +1,2 @@
+define([],function(){"use strict";return function itemName(n){return n.name}});
+//# sourceMappingURL=item.min.js.map
@@ -0,0 +1,10 @@
+<?php
+return [
+    'cache' => [
+        'frontend' => [
+            'default' => [
+                'id_prefix' => '69d_'
+            ]
+        ]
+    ]
+];
@@ -23,6 +23,9 @@ module.exports = {
       documentSelector: [
         { scheme: "file", language: "xml" },
         { scheme: "file", language: "javascript" },
+        { scheme: "file", language: "typescript" },
+        { scheme: "file", language: "html" },
+        { scheme: "file", language: "php" },
       ],
     };
 